@@ -0,0 +1,30 @@
+//! Answers "does this file exist anywhere else?" for one file against the given directories
+//!
+//! `cargo run --example find_file -- <file> <directory>...`
+
+use std::env;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let (file, directories): (String, Vec<String>) = match args.next() {
+        Some(file) => (file, args.collect()),
+        None => {
+            eprintln!("usage: find_file <file> <directory>...");
+            std::process::exit(2);
+        }
+    };
+
+    let mut checker = dupefinder::DupeFinder::new_recursive(directories);
+    match checker.run_for_file(&file) {
+        Ok(Some(duplicate)) => {
+            for path in duplicate.paths() {
+                println!("{}", path.display());
+            }
+        },
+        Ok(None) => println!("no duplicates of {} found", file),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
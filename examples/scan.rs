@@ -0,0 +1,47 @@
+//! Recursively scans the given directories and prints every set of duplicate files,
+//! or a JSON report of them with `--json`
+//!
+//! `cargo run --example scan -- [--json] <directory>...`
+
+use std::{env, io};
+
+fn main() {
+    let mut directories: Vec<String> = env::args().skip(1).collect();
+    let json = directories.first().is_some_and(|arg| arg == "--json");
+    if json {
+        directories.remove(0);
+    }
+
+    if directories.is_empty() {
+        eprintln!("usage: scan [--json] <directory>...");
+        std::process::exit(2);
+    }
+
+    let checker = dupefinder::DupeFinder::builder(&directories)
+        .recursive(true)
+        .build();
+
+    let mut checker = match checker {
+        Ok(checker) => checker,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if json {
+        if let Err(e) = dupefinder::report::json::write(&checker.run(), &mut io::stdout()) {
+            eprintln!("unable to write report: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    for duplicate in checker.run_iter() {
+        println!("{}", duplicate);
+    }
+
+    for skipped in checker.skipped_files() {
+        eprintln!("skipped {}: {:?}", skipped.path.display(), skipped.reason);
+    }
+}
@@ -0,0 +1,14 @@
+#![no_main]
+
+use dupefinder::fuzzing::cache_value;
+use libfuzzer_sys::fuzz_target;
+
+// the first 20 bytes are the header of the file being looked up, the rest the stored value
+fuzz_target!(|data: &[u8]| {
+    if let Some((header, value)) = data.split_first_chunk::<20>() {
+        if let Some(hash) = cache_value(value, header) {
+            assert_eq!(&value[..20], header);
+            assert_eq!(hash.as_bytes(), &value[20..]);
+        }
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use std::path::Path;
+use dupefinder::fuzzing::{extension, glob_matches};
+use libfuzzer_sys::fuzz_target;
+
+// a glob pattern and a path separated by a newline, kept short as matching `*` backtracks
+fuzz_target!(|text: &str| {
+    if text.len() > 256 {
+        return;
+    }
+
+    let (pattern, path) = text.split_once('\n').unwrap_or((text, ""));
+    let _ = glob_matches(pattern, Path::new(path));
+
+    let normalized = extension(pattern);
+    assert_eq!(extension(&normalized), normalized);
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use dupefinder::fuzzing::{percent_decode, percent_encode};
+use libfuzzer_sys::fuzz_target;
+
+// any text either fails to decode or decodes to a path that is written and read back the same
+fuzz_target!(|text: &str| {
+    if let Some(path) = percent_decode(text) {
+        assert_eq!(percent_decode(&percent_encode(&path)), Some(path));
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use dupefinder::SizeManifest;
+use libfuzzer_sys::fuzz_target;
+
+// any input either fails to parse or reads back the same after being written again
+fuzz_target!(|data: &[u8]| {
+    if let Ok(manifest) = SizeManifest::read_from(data) {
+        let mut written: Vec<u8> = Vec::new();
+        manifest.write_to(&mut written).unwrap();
+        assert_eq!(SizeManifest::read_from(written.as_slice()).unwrap(), manifest);
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use dupefinder::ScanResults;
+use libfuzzer_sys::fuzz_target;
+
+// any input either fails to load or gives results whose path index can be queried
+fuzz_target!(|data: &[u8]| {
+    if let Ok(results) = ScanResults::read_from(data) {
+        let _ = results.total_reclaimable();
+        for duplicate in results.iter_by_wasted_space() {
+            for path in duplicate.paths() {
+                assert!(results.is_duplicate(path).is_some());
+            }
+        }
+    }
+});
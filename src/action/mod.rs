@@ -0,0 +1,689 @@
+use std::{collections::HashSet, fs::{self, File, Metadata}, io, path::{Component, Path, PathBuf}};
+use crate::{space::SpaceForecast, verify, DedupePlan, PlannedSet};
+
+/// Filesystem change applied to every file a `DedupePlan` removes
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    /// Delete the file
+    Delete,
+    /// Move the file to the system trash so it can be restored, nothing is reclaimed until the trash is emptied
+    #[cfg(feature = "trash")]
+    Trash,
+    /// Replace the file with a hard link to the retained file, both need to be on the same filesystem
+    HardLink,
+    /// Replace the file with a copy-on-write clone of the retained file, both keep independent
+    /// paths and contents while sharing their extents on disk. Needs a filesystem supporting
+    /// reflinks (btrfs, XFS, APFS) on Linux or macOS.
+    #[cfg(feature = "reflink")]
+    Reflink,
+    /// Move the file below this directory, recreating its full path there so names can not collide.
+    /// Reclaims nothing on disk, only within the searched directories. A directory on another
+    /// filesystem gets a copy that is compared against the file before the file is removed.
+    MoveTo(PathBuf),
+    /// Keep the file but rename it in place, nothing is reclaimed
+    Rename(RenameTemplate),
+}
+
+/// New name given to a file by `Action::Rename`, the extension is kept
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RenameTemplate {
+    /// Append this many characters of the content hash, "report.doc" becomes "report-1577245F.doc".
+    /// Lengths of zero or beyond the length of the hash fail the operations.
+    HashSuffix(usize),
+    /// Take the name of the retained file, with the hash suffix appended when the name is taken
+    /// in the file's directory. Files already named like it keep their name.
+    Canonical,
+}
+
+// hash characters appended when a canonical name is taken
+const CANONICAL_HASH_LENGTH: usize = 8;
+
+/// A single file operation of an action
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Operation {
+    /// File the action applies to
+    pub path: PathBuf,
+    /// Retained file linked to for `Action::HardLink` or cloned for `Action::Reflink`, destination for `Action::MoveTo` and `Action::Rename`
+    pub target: Option<PathBuf>,
+    /// Disk space freed by the operation, forecast like `DedupePlan::reclaimable_bytes`
+    pub bytes: u64,
+    /// Why the operation failed, or would fail in a dry run
+    pub error: Option<String>,
+}
+
+/// Outcome of applying an `Action` to a plan, or of a dry run of it
+///
+/// The operations of `MoveTo` and `Rename` record where each file went, `undo` moves them back.
+/// With the `serde` feature the report can be stored to undo a run later.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActionReport {
+    /// Action the operations applied, `None` for a report not produced by a plan
+    pub action: Option<Action>,
+    /// True when nothing was changed on disk
+    pub dry_run: bool,
+    /// Every operation in plan order
+    pub operations: Vec<Operation>,
+    /// Bytes freed by the operations that succeeded, or would succeed
+    pub bytes_reclaimed: u64,
+}
+
+impl ActionReport {
+    /// Operations that failed, or would fail
+    pub fn failed(&self) -> Vec<&Operation> {
+        self.operations.iter().filter(|operation| operation.error.is_some()).collect()
+    }
+
+    /// Moves the files of the successful `MoveTo` and `Rename` operations back to their original
+    /// paths, latest first, and reports it like a run of the same action. Other actions and dry
+    /// runs have nothing to undo. Files are never moved back over a file that took their place.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./downloads"]);
+    /// let plan = dupefinder::DedupePlan::builder(&checker.run()).build();
+    ///
+    /// let report = plan.apply(&dupefinder::Action::Rename(dupefinder::RenameTemplate::Canonical));
+    /// let undone = report.undo();
+    /// assert!(undone.failed().is_empty());
+    /// ```
+    pub fn undo(&self) -> ActionReport {
+        let mut undone = ActionReport { action: self.action.clone(), ..ActionReport::default() };
+        if self.dry_run || !matches!(self.action, Some(Action::MoveTo(_) | Action::Rename(_))) {
+            return undone;
+        }
+
+        for operation in self.operations.iter().rev().filter(|operation| operation.error.is_none()) {
+            let moved = match &operation.target {
+                Some(moved) if *moved != operation.path => moved,
+                _ => continue,
+            };
+
+            let result = match fs::symlink_metadata(moved) {
+                Ok(meta) if !meta.is_file() => Err(io::Error::new(io::ErrorKind::InvalidInput, "not a regular file")),
+                Ok(_) => match rename_no_clobber(moved, &operation.path) {
+                    Err(e) if e.kind() == io::ErrorKind::CrossesDevices => move_across_devices(moved, &operation.path),
+                    result => result,
+                },
+                Err(e) => Err(e),
+            };
+            undone.operations.push(Operation { path: moved.clone(), target: Some(operation.path.clone()), bytes: 0, error: result.err().map(|e| e.to_string()) });
+        }
+
+        undone
+    }
+}
+
+impl DedupePlan {
+    /// Reports exactly what `apply` would do without touching the filesystem, operations
+    /// that would fail a pre-check carry an error
+    ///
+    /// Every operation re-reads the file it acts on and fails when it is missing or its size or
+    /// modification time changed since the scan. Operations giving up the contents of a file
+    /// (`Delete`, `Trash`, `HardLink`, `Reflink`) also fail unless a retained file is still unchanged,
+    /// links additionally need the retained file on the same device.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+    /// let results = checker.run();
+    /// let plan = dupefinder::DedupePlan::builder(&results).build();
+    ///
+    /// let report = plan.dry_run(&dupefinder::Action::Delete);
+    /// for operation in report.operations.iter() {
+    ///     println!("would delete {}", operation.path.display());
+    /// }
+    /// println!("{} bytes would be reclaimed", report.bytes_reclaimed);
+    /// ```
+    pub fn dry_run(&self, action: &Action) -> ActionReport {
+        self.run_action(action, true)
+    }
+
+    /// Applies `action` to every file to remove, files that fail are reported and skipped
+    ///
+    /// `MoveTo` and `Rename` never replace a file, a destination created since the checks fails the operation.
+    pub fn apply(&self, action: &Action) -> ActionReport {
+        self.run_action(action, false)
+    }
+
+    fn run_action(&self, action: &Action, dry_run: bool) -> ActionReport {
+        let mut report = ActionReport { action: Some(action.clone()), dry_run, ..ActionReport::default() };
+        let mut forecast = SpaceForecast::default();
+        // destinations taken by earlier operations of this run, they may not exist on disk yet
+        let mut claimed: HashSet<PathBuf> = HashSet::new();
+
+        for set in self.sets.iter() {
+            for path in set.remove.iter() {
+                let target = match action {
+                    Action::Delete => None,
+                    #[cfg(feature = "trash")]
+                    Action::Trash => None,
+                    Action::HardLink => set.retain.first().cloned(),
+                    #[cfg(feature = "reflink")]
+                    Action::Reflink => set.retain.first().cloned(),
+                    Action::MoveTo(directory) => Some(move_destination(directory, path)),
+                    Action::Rename(template) => Some(rename_destination(template, set, path, &claimed)),
+                };
+
+                // forecast before the file is touched
+                let bytes = match action {
+                    Action::MoveTo(_) | Action::Rename(_) => 0,
+                    #[cfg(feature = "trash")]
+                    Action::Trash => 0,
+                    _ => forecast.remove(path, set.size),
+                };
+
+                let mut result = match target.as_ref().filter(|target| claimed.contains(*target)) {
+                    Some(target) => Err(destination_exists(target)),
+                    None => check(action, set, path, target.as_deref()),
+                };
+                if result.is_ok() && !dry_run {
+                    result = execute(action, path, target.as_deref());
+                }
+                if result.is_ok() {
+                    report.bytes_reclaimed += bytes;
+                    if let (Action::MoveTo(_) | Action::Rename(_), Some(target)) = (action, &target) {
+                        claimed.insert(target.clone());
+                    }
+                }
+
+                report.operations.push(Operation { path: path.clone(), target, bytes, error: result.err().map(|e| e.to_string()) });
+            }
+        }
+
+        report
+    }
+}
+
+// read-only checks shared by dry runs and real runs
+fn check(action: &Action, set: &PlannedSet, path: &Path, target: Option<&Path>) -> Result<(), io::Error> {
+    let meta = fs::symlink_metadata(path)?;
+    if !meta.is_file() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a regular file"));
+    }
+    if !set.unchanged(path, &meta) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "file changed since the scan"));
+    }
+
+    match (action, target) {
+        (Action::Delete, _) => retained(set).map(|_| ()),
+        #[cfg(feature = "trash")]
+        (Action::Trash, _) => retained(set).map(|_| ()),
+        (Action::HardLink, Some(target)) => same_device(&meta, &unchanged_retained(set, target)?),
+        #[cfg(feature = "reflink")]
+        (Action::Reflink, Some(target)) => same_device(&meta, &unchanged_retained(set, target)?),
+        (Action::Rename(RenameTemplate::HashSuffix(length)), _) if *length == 0 || *length > set.hash.len() => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("hash suffix length must be between 1 and {}", set.hash.len()))),
+        (Action::MoveTo(_) | Action::Rename(_), Some(target)) if target != path && target.exists() => Err(destination_exists(target)),
+        (Action::MoveTo(_) | Action::Rename(_), Some(_)) => Ok(()),
+        (_, None) => Err(io::Error::new(io::ErrorKind::NotFound, "no retained file to link to")),
+    }
+}
+
+// the first retained file still holding the contents of the set
+fn retained(set: &PlannedSet) -> Result<Metadata, io::Error> {
+    set.retain.iter()
+        .find_map(|file| fs::metadata(file).ok().filter(|meta| meta.is_file() && set.unchanged(file, meta)))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no retained file left unchanged since the scan"))
+}
+
+fn unchanged_retained(set: &PlannedSet, target: &Path) -> Result<Metadata, io::Error> {
+    let meta = fs::metadata(target)?;
+    match meta.is_file() && set.unchanged(target, &meta) {
+        true => Ok(meta),
+        false => Err(io::Error::new(io::ErrorKind::InvalidData, format!("retained file changed since the scan: {}", target.display()))),
+    }
+}
+
+#[cfg(unix)]
+fn same_device(a: &Metadata, b: &Metadata) -> Result<(), io::Error> {
+    use std::os::unix::fs::MetadataExt;
+
+    match a.dev() == b.dev() {
+        true => Ok(()),
+        false => Err(io::Error::new(io::ErrorKind::CrossesDevices, "retained file is on another device")),
+    }
+}
+
+#[cfg(not(unix))]
+fn same_device(_a: &Metadata, _b: &Metadata) -> Result<(), io::Error> {
+    Ok(())
+}
+
+fn execute(action: &Action, path: &Path, target: Option<&Path>) -> Result<(), io::Error> {
+    match (action, target) {
+        (Action::Delete, _) => fs::remove_file(path),
+        #[cfg(feature = "trash")]
+        (Action::Trash, _) => trash::delete(path).map_err(io::Error::other),
+        (Action::HardLink, Some(target)) => {
+            // the link is created next to the file first so the file is never missing
+            let link = temporary_path(path);
+            fs::hard_link(target, &link)?;
+            fs::rename(&link, path).inspect_err(|_| { let _ = fs::remove_file(&link); })
+        },
+        #[cfg(feature = "reflink")]
+        (Action::Reflink, Some(target)) => {
+            let clone = temporary_path(path);
+            clone_file(target, &clone)?;
+            fs::metadata(path)
+                .and_then(|meta| fs::set_permissions(&clone, meta.permissions()))
+                .and_then(|_| fs::rename(&clone, path))
+                .inspect_err(|_| { let _ = fs::remove_file(&clone); })
+        },
+        (Action::MoveTo(_), Some(target)) => {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match rename_no_clobber(path, target) {
+                Err(e) if e.kind() == io::ErrorKind::CrossesDevices => move_across_devices(path, target),
+                result => result,
+            }
+        },
+        (Action::Rename(_), Some(target)) if target == path => Ok(()),
+        (Action::Rename(_), Some(target)) => rename_no_clobber(path, target),
+        (_, None) => Err(io::Error::new(io::ErrorKind::NotFound, "no retained file to link to")),
+    }
+}
+
+// `path` recreated below `directory`, without its root or prefix
+fn move_destination(directory: &Path, path: &Path) -> PathBuf {
+    let mut destination = directory.to_path_buf();
+    destination.extend(path.components().filter(|component| matches!(component, Component::Normal(_))));
+    destination
+}
+
+// renames `path` to `destination` unless a file appeared there since the check, a plain rename
+// would replace it. Linking fails on an existing destination, filesystems without hard links
+// check it once more right before renaming.
+fn rename_no_clobber(path: &Path, destination: &Path) -> Result<(), io::Error> {
+    match fs::hard_link(path, destination) {
+        Ok(()) => fs::remove_file(path).inspect_err(|_| { let _ = fs::remove_file(destination); }),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(destination_exists(destination)),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => Err(e),
+        Err(_) if destination.exists() => Err(destination_exists(destination)),
+        Err(_) => fs::rename(path, destination),
+    }
+}
+
+fn destination_exists(destination: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::AlreadyExists, format!("destination exists: {}", destination.display()))
+}
+
+// moves `path` to `destination` on another filesystem, `path` is only removed once the copy
+// reads back the same, otherwise the copy is removed again
+fn move_across_devices(path: &Path, destination: &Path) -> Result<(), io::Error> {
+    let mut source = File::open(path)?;
+    let mut copy = File::options().write(true).create_new(true).open(destination)?;
+
+    let result = io::copy(&mut source, &mut copy)
+        .and_then(|_| source.metadata())
+        .and_then(|meta| {
+            copy.set_permissions(meta.permissions())?;
+            // not every filesystem keeps modification times
+            if let Ok(modified) = meta.modified() {
+                let _ = copy.set_modified(modified);
+            }
+            copy.sync_all()
+        })
+        .and_then(|_| match verify::same_contents(path, destination, None)? {
+            true => fs::remove_file(path),
+            false => Err(io::Error::new(io::ErrorKind::InvalidData, format!("copy differs from the file: {}", destination.display()))),
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(destination);
+    }
+
+    result
+}
+
+// creates `destination` as a copy-on-write clone of `source`
+#[cfg(all(feature = "reflink", target_os = "linux"))]
+fn clone_file(source: &Path, destination: &Path) -> Result<(), io::Error> {
+    use std::{fs::File, os::fd::AsRawFd};
+
+    let source = File::open(source)?;
+    let clone = File::options().write(true).create_new(true).open(destination)?;
+    // SAFETY: both descriptors stay open for the duration of the call
+    if unsafe { libc::ioctl(clone.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) } == -1 {
+        let error = io::Error::last_os_error();
+        let _ = fs::remove_file(destination);
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+#[cfg(all(feature = "reflink", target_os = "macos"))]
+fn clone_file(source: &Path, destination: &Path) -> Result<(), io::Error> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let source = CString::new(source.as_os_str().as_bytes())?;
+    let destination = CString::new(destination.as_os_str().as_bytes())?;
+    // SAFETY: both paths are valid nul terminated strings
+    if unsafe { libc::clonefile(source.as_ptr(), destination.as_ptr(), 0) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(all(feature = "reflink", not(any(target_os = "linux", target_os = "macos"))))]
+fn clone_file(_source: &Path, _destination: &Path) -> Result<(), io::Error> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "reflinks are not supported on this platform"))
+}
+
+// `claimed` holds the destinations of earlier renames of the run
+fn rename_destination(template: &RenameTemplate, set: &PlannedSet, path: &Path, claimed: &HashSet<PathBuf>) -> PathBuf {
+    match template {
+        RenameTemplate::HashSuffix(length) => hash_suffixed(path, &set.hash, *length),
+        RenameTemplate::Canonical => {
+            let name = match set.retain.first().and_then(|retained| retained.file_name()) {
+                Some(name) if Some(name) != path.file_name() => name,
+                _ => return path.to_path_buf(),
+            };
+
+            let destination = path.with_file_name(name);
+            match destination.exists() || claimed.contains(&destination) {
+                true => hash_suffixed(&destination, &set.hash, CANONICAL_HASH_LENGTH),
+                false => destination,
+            }
+        },
+    }
+}
+
+// "name.ext" -> "name-<hash prefix>.ext"
+fn hash_suffixed(path: &Path, hash: &str, length: usize) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push("-");
+    name.push(hash.get(..length).unwrap_or(hash));
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".dupefinder-tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{DupeEntry, PlannedSet};
+
+    fn plan(temp: &Path) -> DedupePlan {
+        let mut scanned: Vec<DupeEntry> = Vec::new();
+        for name in ["keep.txt", "a.txt", "b.txt"] {
+            fs::write(temp.join(name), "12345").unwrap();
+            scanned.push(DupeEntry::new(temp.join(name), &fs::metadata(temp.join(name)).unwrap()));
+        }
+
+        DedupePlan { sets: vec![PlannedSet {
+            hash: String::from("12345"),
+            size: 5,
+            retain: vec![temp.join("keep.txt")],
+            remove: vec![temp.join("a.txt"), temp.join("b.txt"), temp.join("missing.txt")],
+            scanned,
+        }] }
+    }
+
+    // disk space taken up by the files the plan removes
+    fn allocated(temp: &Path) -> u64 {
+        let mut forecast = SpaceForecast::default();
+        forecast.remove(&temp.join("a.txt"), 5) + forecast.remove(&temp.join("b.txt"), 5)
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+        let allocated = allocated(temp.path());
+
+        let report = plan.dry_run(&Action::Delete);
+        assert!(report.dry_run);
+        assert_eq!(report.operations.len(), 3);
+        assert_eq!(report.bytes_reclaimed, allocated);
+        assert_eq!(report.failed().len(), 1);
+        assert_eq!(report.failed()[0].path, temp.path().join("missing.txt"));
+        assert!(temp.path().join("a.txt").exists(), "dry run should not delete");
+
+        assert_eq!(plan.apply(&Action::Delete).operations, report.operations, "dry run should match the real run");
+        assert!(!temp.path().join("a.txt").exists(), "file should be deleted");
+        assert!(temp.path().join("keep.txt").exists(), "retained file should stay");
+    }
+
+    #[test]
+    fn test_changed_files_are_left_alone() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+        fs::write(temp.path().join("a.txt"), "123456").unwrap();
+
+        let report = plan.apply(&Action::Delete);
+        assert_eq!(report.failed().len(), 2);
+        assert_eq!(report.operations[0].error.as_deref(), Some("file changed since the scan"));
+        assert!(temp.path().join("a.txt").exists(), "changed file should not be deleted");
+
+        fs::remove_file(temp.path().join("keep.txt")).unwrap();
+        fs::write(temp.path().join("a.txt"), "12345").unwrap();
+        let report = plan.dry_run(&Action::Delete);
+        assert_eq!(report.failed().len(), 3, "nothing should be deleted without the retained file");
+        assert_eq!(report.bytes_reclaimed, 0);
+        let report = plan.dry_run(&Action::HardLink);
+        assert_eq!(report.failed().len(), 3, "nothing should be linked without the retained file");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hard_link() {
+        use std::os::unix::fs::MetadataExt;
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+        let allocated = allocated(temp.path());
+
+        let report = plan.apply(&Action::HardLink);
+        assert_eq!(report.bytes_reclaimed, allocated);
+        assert_eq!(report.operations[0].target, Some(temp.path().join("keep.txt")));
+        assert_eq!(fs::metadata(temp.path().join("a.txt")).unwrap().ino(), fs::metadata(temp.path().join("keep.txt")).unwrap().ino());
+        assert_eq!(fs::metadata(temp.path().join("keep.txt")).unwrap().nlink(), 3);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_same_device() {
+        let temp = tempfile::tempdir().unwrap();
+        let meta = fs::metadata(temp.path()).unwrap();
+
+        assert!(same_device(&meta, &meta).is_ok());
+        let error = same_device(&meta, &fs::metadata("/proc").unwrap()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::CrossesDevices, "links across devices should fail the check");
+    }
+
+    #[cfg(feature = "reflink")]
+    #[test]
+    fn test_reflink() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+        let allocated = allocated(temp.path());
+
+        // the temporary directory may live on a filesystem without reflinks
+        let report = plan.apply(&Action::Reflink);
+        for operation in report.operations.iter().filter(|operation| operation.path.exists()) {
+            assert_eq!(fs::read(&operation.path).unwrap(), b"12345", "contents should be unchanged");
+            assert!(!temporary_path(&operation.path).exists(), "clone should not be left behind");
+        }
+        assert!(report.bytes_reclaimed <= allocated, "at most the removed files can be freed");
+    }
+
+    #[cfg(feature = "trash")]
+    #[test]
+    fn test_trash_dry_run() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+
+        let report = plan.dry_run(&Action::Trash);
+        assert_eq!(report.operations.len(), 3);
+        assert_eq!(report.failed().len(), 1);
+        assert_eq!(report.bytes_reclaimed, 0, "trashed files still take up space");
+        assert!(temp.path().join("a.txt").exists(), "dry run should not trash");
+    }
+
+    #[test]
+    fn test_rename() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+
+        let report = plan.apply(&Action::Rename(RenameTemplate::HashSuffix(3)));
+        assert_eq!(report.bytes_reclaimed, 0);
+        assert!(temp.path().join("a-123.txt").exists(), "file should get the hash suffix");
+        assert!(temp.path().join("b-123.txt").exists(), "file should get the hash suffix");
+        assert!(!temp.path().join("a.txt").exists(), "file should be renamed");
+    }
+
+    #[test]
+    fn test_rename_suffix_length() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+
+        for length in [0, 6] {
+            let report = plan.dry_run(&Action::Rename(RenameTemplate::HashSuffix(length)));
+            assert_eq!(report.failed().len(), 3, "suffix length {} should be rejected", length);
+            assert_eq!(report.operations[0].error.as_deref(), Some("hash suffix length must be between 1 and 5"));
+        }
+        assert!(plan.dry_run(&Action::Rename(RenameTemplate::HashSuffix(5))).operations[0].error.is_none(), "whole hash should be accepted");
+    }
+
+    #[test]
+    fn test_undo() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+
+        let report = plan.apply(&Action::Rename(RenameTemplate::HashSuffix(3)));
+        let undone = report.undo();
+        assert_eq!(undone.operations.len(), 2, "only the files that were renamed should move back");
+        assert!(undone.failed().is_empty());
+        assert_eq!(undone.operations[0].path, temp.path().join("b-123.txt"), "latest rename should be undone first");
+        assert!(temp.path().join("a.txt").exists() && temp.path().join("b.txt").exists(), "files should get their names back");
+        assert!(!temp.path().join("a-123.txt").exists(), "renamed file should be gone");
+
+        let quarantine = temp.path().join("quarantine");
+        let report = plan.apply(&Action::MoveTo(quarantine.clone()));
+        fs::write(temp.path().join("b.txt"), "other").unwrap();
+        let undone = report.undo();
+        assert_eq!(undone.failed().len(), 1, "a file taking the original path should not be replaced");
+        assert_eq!(fs::read(temp.path().join("b.txt")).unwrap(), b"other");
+        assert!(temp.path().join("a.txt").exists(), "moved file should be back");
+
+        let report = plan.apply(&Action::Delete);
+        assert!(report.undo().operations.is_empty(), "deletions can not be undone");
+    }
+
+    #[test]
+    fn test_rename_canonical() {
+        let temp = tempfile::tempdir().unwrap();
+        let nested = temp.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("a.txt"), "12345").unwrap();
+        fs::write(nested.join("keep.txt"), "12345").unwrap();
+        let set = PlannedSet { hash: String::from("12345678910"), size: 5, retain: vec![temp.path().join("keep.txt")], remove: Vec::new(), scanned: Vec::new() };
+
+        assert_eq!(rename_destination(&RenameTemplate::Canonical, &set, &nested.join("a.txt"), &HashSet::new()), nested.join("keep-12345678.txt"), "taken name should get the hash suffix");
+        assert_eq!(rename_destination(&RenameTemplate::Canonical, &set, &nested.join("keep.txt"), &HashSet::new()), nested.join("keep.txt"), "canonical name should be kept");
+        assert_eq!(rename_destination(&RenameTemplate::Canonical, &set, &temp.path().join("other").join("a.txt"), &HashSet::new()), temp.path().join("other").join("keep.txt"));
+    }
+
+    #[test]
+    fn test_rename_canonical_claims_names() {
+        let temp = tempfile::tempdir().unwrap();
+        let nested = temp.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        let mut scanned: Vec<DupeEntry> = Vec::new();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(nested.join(name), "12345").unwrap();
+            scanned.push(DupeEntry::new(nested.join(name), &fs::metadata(nested.join(name)).unwrap()));
+        }
+        fs::write(temp.path().join("keep.txt"), "12345").unwrap();
+        let plan = DedupePlan { sets: vec![PlannedSet {
+            hash: String::from("12345678910"),
+            size: 5,
+            retain: vec![temp.path().join("keep.txt")],
+            remove: vec![nested.join("a.txt"), nested.join("b.txt"), nested.join("c.txt")],
+            scanned,
+        }] };
+
+        let action = Action::Rename(RenameTemplate::Canonical);
+        let report = plan.dry_run(&action);
+        let targets: Vec<Option<PathBuf>> = report.operations.iter().map(|operation| operation.target.clone()).collect();
+        assert_eq!(targets, vec![Some(nested.join("keep.txt")), Some(nested.join("keep-12345678.txt")), Some(nested.join("keep-12345678.txt"))]);
+        assert_eq!(report.failed().len(), 1, "the third file has no free name left");
+        assert_eq!(report.failed()[0].path, nested.join("c.txt"));
+
+        assert_eq!(plan.apply(&action).operations, report.operations, "dry run should match the real run");
+        assert!(nested.join("keep-12345678.txt").exists(), "second file should get the hash suffix");
+        assert!(nested.join("c.txt").exists(), "third file should keep its name");
+    }
+
+    #[test]
+    fn test_move_to() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = plan(temp.path());
+        let quarantine = temp.path().join("quarantine");
+
+        let report = plan.apply(&Action::MoveTo(quarantine.clone()));
+        let destination = move_destination(&quarantine, &temp.path().join("a.txt"));
+        assert_eq!(report.bytes_reclaimed, 0);
+        assert_eq!(report.operations[0].target, Some(destination.clone()));
+        assert!(destination.exists(), "file should be moved");
+        assert!(!temp.path().join("a.txt").exists(), "file should be moved");
+    }
+
+    #[test]
+    fn test_move_keeps_existing_destination() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.txt"), "12345").unwrap();
+        // created after the check of a run
+        fs::write(temp.path().join("a-123.txt"), "other").unwrap();
+
+        let error = execute(&Action::Rename(RenameTemplate::HashSuffix(3)), &temp.path().join("a.txt"), Some(&temp.path().join("a-123.txt"))).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(temp.path().join("a-123.txt")).unwrap(), b"other", "existing file should not be replaced");
+        assert!(temp.path().join("a.txt").exists(), "file should stay in place");
+
+        let quarantine = temp.path().join("quarantine");
+        let destination = move_destination(&quarantine, &temp.path().join("a.txt"));
+        fs::create_dir_all(destination.parent().unwrap()).unwrap();
+        fs::write(&destination, "other").unwrap();
+        let error = execute(&Action::MoveTo(quarantine), &temp.path().join("a.txt"), Some(&destination)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&destination).unwrap(), b"other", "existing file should not be replaced");
+        assert!(temp.path().join("a.txt").exists(), "file should stay in place");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_move_to_other_device() {
+        use std::os::unix::fs::MetadataExt;
+
+        // a tmpfs stands in for a quarantine on another filesystem
+        let other = match tempfile::tempdir_in("/dev/shm") {
+            Ok(other) => other,
+            Err(_) => return,
+        };
+        let temp = tempfile::tempdir().unwrap();
+        if fs::metadata(other.path()).unwrap().dev() == fs::metadata(temp.path()).unwrap().dev() {
+            return;
+        }
+        let plan = plan(temp.path());
+
+        let report = plan.apply(&Action::MoveTo(other.path().to_path_buf()));
+        let destination = move_destination(other.path(), &temp.path().join("a.txt"));
+        assert!(report.operations[0].error.is_none(), "move across filesystems should succeed");
+        assert_eq!(fs::read(&destination).unwrap(), b"12345", "copy should hold the contents");
+        assert!(!temp.path().join("a.txt").exists(), "file should be removed after copying");
+    }
+}
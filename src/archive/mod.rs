@@ -0,0 +1,246 @@
+use std::{collections::HashMap, ffi::OsStr, fs::File, io::{self, BufReader, Read}, path::{Path, PathBuf}};
+use log::Level;
+use crate::{remote::virtual_path, to_path_bufs, DupeEntry, DupeFinder, LogCategory, ScanResults};
+
+// a file inside an archive, `location` is `<archive>!<path inside>`
+struct Member {
+    location: PathBuf,
+    size: u64,
+    hash: String,
+}
+
+// archive formats told apart by file name
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArchiveKind {
+    #[cfg(feature = "zip")]
+    Zip,
+    #[cfg(feature = "tar")]
+    Tar,
+    #[cfg(feature = "tar")]
+    TarGz,
+}
+
+impl ArchiveKind {
+    fn from_path(path: &Path) -> Option<ArchiveKind> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+
+        #[cfg(feature = "zip")]
+        if name.ends_with(".zip") {
+            return Some(ArchiveKind::Zip);
+        }
+        #[cfg(feature = "tar")]
+        if name.ends_with(".tar") {
+            return Some(ArchiveKind::Tar);
+        }
+        #[cfg(feature = "tar")]
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            return Some(ArchiveKind::TarGz);
+        }
+
+        None
+    }
+}
+
+impl DupeFinder {
+    /// Runs the search and additionally matches the members of `archives` against each other
+    /// and against the searched files
+    ///
+    /// Zip archives are read with the `zip` feature, `.tar`, `.tar.gz` and `.tgz` archives with
+    /// the `tar` feature, the format is told by the file extension. Members are listed as
+    /// `<archive>!<path inside>`, which are not real paths. They are hashed in full with the
+    /// configured hasher, a custom `MatchStrategy` only applies to the searched files. Archives
+    /// nested inside archives are not opened.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./photos"]);
+    /// for duplicate in checker.run_with_archives(["./backups/photos-2023.zip", "./backups/home.tar.gz"]).values() {
+    ///     println!("{:?}", duplicate.paths().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn run_with_archives<I, P>(&mut self, archives: I) -> ScanResults
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let duplicates = self.run().into_inner();
+
+        // hash -> (size, member entries)
+        let mut found: HashMap<String, (u64, Vec<DupeEntry>)> = HashMap::new();
+        for member in to_path_bufs(archives).iter().flat_map(|archive| self.archive_members(archive)) {
+            found.entry(member.hash).or_insert_with(|| (member.size, Vec::new())).1.push(DupeEntry::detached(member.location, member.size));
+        }
+
+        self.merge_virtual_files(duplicates, found)
+    }
+
+    fn archive_members(&self, archive: &Path) -> Vec<Member> {
+        let mut members: Vec<Member> = Vec::new();
+
+        let kind = match ArchiveKind::from_path(archive) {
+            Some(kind) => kind,
+            None => {
+                self.log(LogCategory::Traversal, Level::Warn, format_args!("Unsupported archive format: {}", archive.display()));
+                return members;
+            }
+        };
+
+        let file = match File::open(archive) {
+            Ok(file) => file,
+            Err(e) => {
+                self.log(LogCategory::Traversal, Level::Warn, format_args!("Error opening archive: {}; error: {}", archive.display(), e));
+                return members;
+            }
+        };
+
+        let read = match kind {
+            #[cfg(feature = "zip")]
+            ArchiveKind::Zip => self.push_zip_members(file, archive, &mut members),
+            #[cfg(feature = "tar")]
+            ArchiveKind::Tar => self.push_tar_members(BufReader::new(file), archive, &mut members),
+            #[cfg(feature = "tar")]
+            ArchiveKind::TarGz => self.push_tar_members(flate2::read::GzDecoder::new(BufReader::new(file)), archive, &mut members),
+        };
+
+        if let Err(e) = read {
+            self.log(LogCategory::Traversal, Level::Warn, format_args!("Error reading archive: {}; error: {}", archive.display(), e));
+        }
+
+        members
+    }
+
+    // hashes every non-empty member, a member that can not be read is logged and left out
+    fn push_member(&self, archive: &Path, name: &OsStr, size: u64, reader: &mut dyn Read, members: &mut Vec<Member>) {
+        if size == 0 {
+            return;
+        }
+
+        let mut location = virtual_path(archive, "!");
+        location.as_mut_os_string().push(name);
+        match self.options.hasher.hash_reader(reader) {
+            Ok(hash) => members.push(Member { location, size, hash }),
+            Err(e) => self.log(LogCategory::Hashing, Level::Warn, format_args!("Error generating hash for archive member: {}; error: {}", location.display(), e)),
+        }
+    }
+
+    #[cfg(feature = "zip")]
+    fn push_zip_members(&self, file: File, archive: &Path, members: &mut Vec<Member>) -> Result<(), io::Error> {
+        let mut zip = zip::ZipArchive::new(BufReader::new(file))?;
+
+        for index in 0..zip.len() {
+            let mut member = match zip.by_index(index) {
+                Ok(member) => member,
+                Err(e) => {
+                    self.log(LogCategory::Traversal, Level::Warn, format_args!("Error reading member {} of archive: {}; error: {}", index, archive.display(), e));
+                    continue;
+                }
+            };
+            if !member.is_file() {
+                continue;
+            }
+
+            let name = member.name().to_string();
+            let size = member.size();
+            self.push_member(archive, OsStr::new(&name), size, &mut member, members);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tar")]
+    fn push_tar_members<R: Read>(&self, reader: R, archive: &Path, members: &mut Vec<Member>) -> Result<(), io::Error> {
+        let mut tar = tar::Archive::new(reader);
+
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let name = entry.path()?.into_owned().into_os_string();
+            let size = entry.size();
+            self.push_member(archive, &name, size, &mut entry, members);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_archive_kind() {
+        #[cfg(feature = "zip")]
+        assert_eq!(ArchiveKind::from_path(Path::new("backup/Photos.ZIP")), Some(ArchiveKind::Zip));
+        #[cfg(feature = "tar")]
+        {
+            assert_eq!(ArchiveKind::from_path(Path::new("home.tar")), Some(ArchiveKind::Tar));
+            assert_eq!(ArchiveKind::from_path(Path::new("home.tar.gz")), Some(ArchiveKind::TarGz));
+            assert_eq!(ArchiveKind::from_path(Path::new("home.tgz")), Some(ArchiveKind::TarGz));
+        }
+        assert_eq!(ArchiveKind::from_path(Path::new("home.rar")), None);
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_zip_members_match_files() {
+        use std::io::Write;
+
+        let temp = tempfile::tempdir().unwrap();
+        let files = temp.path().join("files");
+        fs::create_dir(&files).unwrap();
+        fs::write(files.join("report.txt"), "archived report").unwrap();
+        fs::write(files.join("other.txt"), "something else!").unwrap();
+
+        let archive = temp.path().join("backup.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("docs/", options).unwrap();
+        zip.start_file("docs/report.txt", options).unwrap();
+        zip.write_all(b"archived report").unwrap();
+        zip.start_file("docs/unique.txt", options).unwrap();
+        zip.write_all(b"only in archive").unwrap();
+        zip.finish().unwrap();
+
+        let mut checker = DupeFinder::new([&files]);
+        let results = checker.run_with_archives([&archive]);
+        assert_eq!(results.len(), 1);
+
+        let mut found: Vec<&Path> = results.values().next().unwrap().paths().collect();
+        found.sort();
+        assert_eq!(found, vec![virtual_path(&archive, "!docs/report.txt"), files.join("report.txt")]);
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn test_tar_members_match_each_other() {
+        let temp = tempfile::tempdir().unwrap();
+        let files = temp.path().join("files");
+        fs::create_dir(&files).unwrap();
+        fs::write(files.join("report.txt"), "archived report").unwrap();
+
+        let plain = temp.path().join("home.tar");
+        let mut builder = tar::Builder::new(File::create(&plain).unwrap());
+        builder.append_path_with_name(files.join("report.txt"), "home/report.txt").unwrap();
+        builder.finish().unwrap();
+
+        let compressed = temp.path().join("home.tar.gz");
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(File::create(&compressed).unwrap(), flate2::Compression::default()));
+        builder.append_path_with_name(files.join("report.txt"), "home/report.txt").unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let empty = temp.path().join("empty");
+        fs::create_dir(&empty).unwrap();
+        let mut checker = DupeFinder::new([&empty]);
+        let results = checker.run_with_archives([&plain, &compressed]);
+        assert_eq!(results.len(), 1);
+
+        let mut found: Vec<&Path> = results.values().next().unwrap().paths().collect();
+        found.sort();
+        assert_eq!(found, vec![virtual_path(&plain, "!home/report.txt"), virtual_path(&compressed, "!home/report.txt")]);
+    }
+}
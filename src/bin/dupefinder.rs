@@ -0,0 +1,180 @@
+//! Command line front end of the library, built with the `cli` feature
+//!
+//! `dupefinder [OPTIONS] <DIRECTORY>...`
+
+use std::{io::{self, Write}, path::{Path, PathBuf}, process::ExitCode};
+use clap::{Parser, ValueEnum};
+use dupefinder::{report::{self, csv::CsvWriter, json::JsonWriter, text::TextWriter, ReportWriter}, DupeFinderConfig, DupeFinderError};
+
+/// Finds duplicate files in one or more directories
+#[derive(Parser)]
+#[command(name = "dupefinder", version)]
+struct Args {
+    /// Directories to search, replacing those of the config file and environment
+    directories: Vec<PathBuf>,
+
+    /// JSON config file, see `DupeFinderConfig`. Environment variables (`DUPEFINDER_*`)
+    /// override the file, flags override both
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Search subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Search subdirectories up to this many levels deep, implies --recursive
+    #[arg(long, value_name = "LEVELS")]
+    max_depth: Option<usize>,
+
+    /// Do not descend into directories on other filesystems
+    #[arg(short = 'x', long)]
+    same_filesystem: bool,
+
+    /// Directories whose files are matched against but never reported
+    #[arg(long = "reference", value_name = "DIRECTORY")]
+    references: Vec<PathBuf>,
+
+    /// Only consider files with these extensions, comma separated
+    #[arg(long, value_name = "EXTENSIONS", value_delimiter = ',')]
+    include_ext: Vec<String>,
+
+    /// Never consider files with these extensions, comma separated
+    #[arg(long, value_name = "EXTENSIONS", value_delimiter = ',')]
+    exclude_ext: Vec<String>,
+
+    /// Skip files and directories matching this pattern, may be repeated
+    #[arg(long = "exclude", value_name = "GLOB")]
+    excludes: Vec<String>,
+
+    /// Skip dotfiles on unix, files with the hidden attribute on windows
+    #[arg(long)]
+    skip_hidden: bool,
+
+    /// Only report sets of at least this many files
+    #[arg(long, value_name = "COUNT")]
+    min_copies: Option<usize>,
+
+    /// Only report sets whose removable copies take up at least this many bytes
+    #[arg(long, value_name = "BYTES")]
+    min_group_waste: Option<u64>,
+
+    /// Read at most this many bytes per second while hashing
+    #[arg(long, value_name = "BYTES")]
+    max_read_rate: Option<u64>,
+
+    /// Compare matching files byte by byte before reporting them
+    #[arg(long)]
+    verify: bool,
+
+    /// Fail on the first file or directory that could not be read instead of skipping it
+    #[arg(long)]
+    strict: bool,
+
+    /// How the duplicate sets are written to stdout
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// List the files without a duplicate instead of the duplicate sets, one per line. Files in
+    /// reference directories are left out, on unix names are written as their raw bytes
+    #[arg(long, conflicts_with = "format")]
+    unique: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// One block per set listing its files
+    Text,
+    /// The JSON report of `dupefinder::report::json`
+    Json,
+    /// One row per file, see `dupefinder::report::csv`
+    Csv,
+}
+
+impl Args {
+    // the settings given as flags, unset flags leave the lower layers alone
+    fn overrides(&self) -> DupeFinderConfig {
+        let list = |values: &Vec<String>| Some(values.clone()).filter(|values| !values.is_empty());
+
+        DupeFinderConfig {
+            directories: Some(self.directories.clone()).filter(|directories| !directories.is_empty()),
+            recursive: self.recursive.then_some(true),
+            max_depth: self.max_depth,
+            same_filesystem: self.same_filesystem.then_some(true),
+            references: Some(self.references.clone()).filter(|references| !references.is_empty()),
+            include_extensions: list(&self.include_ext),
+            exclude_extensions: list(&self.exclude_ext),
+            exclude_globs: list(&self.excludes),
+            skip_hidden: self.skip_hidden.then_some(true),
+            min_copies: self.min_copies,
+            min_group_waste_bytes: self.min_group_waste,
+            max_read_rate: self.max_read_rate,
+            verify_contents: self.verify.then_some(true),
+            strict: self.strict.then_some(true),
+            ..DupeFinderConfig::default()
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let config = match DupeFinderConfig::resolve(args.config.as_ref()) {
+        Ok(config) => config.merge(args.overrides()),
+        Err(e) => {
+            eprintln!("dupefinder: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut checker = match config.builder().build() {
+        Ok(checker) => checker,
+        Err(e) => {
+            eprintln!("dupefinder: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    // `try_run` with the unique files of the same pass
+    let (results, unique) = checker.run_partitioned();
+    if let Some(error) = results.errors().first().filter(|_| config.strict == Some(true)) {
+        eprintln!("dupefinder: {}", DupeFinderError::from(error.clone()));
+        return ExitCode::FAILURE;
+    }
+
+    if args.unique {
+        let mut stdout = io::stdout().lock();
+        if let Err(e) = unique.iter().try_for_each(|file| write_path(&mut stdout, file)).and_then(|_| stdout.flush()) {
+            eprintln!("dupefinder: unable to write unique files: {}", e);
+            return ExitCode::FAILURE;
+        }
+    } else {
+        let stdout = io::stdout();
+        let mut report: Box<dyn ReportWriter + '_> = match args.format {
+            Format::Text => Box::new(TextWriter::new(stdout.lock())),
+            Format::Json => Box::new(JsonWriter::new(stdout.lock()).with_results(&results)),
+            Format::Csv => Box::new(CsvWriter::new(stdout.lock())),
+        };
+        if let Err(e) = report::write_results(&results, report.as_mut()) {
+            eprintln!("dupefinder: unable to write report: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    for skipped in checker.skipped_files() {
+        eprintln!("skipped {}: {:?}", skipped.path.display(), skipped.reason);
+    }
+    if results.stats().truncated {
+        eprintln!("dupefinder: scan limit reached, results are partial");
+    }
+
+    ExitCode::SUCCESS
+}
+
+// writes `path` on a line of its own, on unix as its raw bytes so names that are not valid UTF-8 are kept intact
+fn write_path<W: Write>(writer: &mut W, path: &Path) -> Result<(), io::Error> {
+    #[cfg(unix)]
+    writer.write_all(std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()))?;
+    #[cfg(not(unix))]
+    write!(writer, "{}", path.display())?;
+    writeln!(writer)
+}
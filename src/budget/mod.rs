@@ -0,0 +1,145 @@
+use std::{cmp::Reverse, collections::HashMap, sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}};
+use crate::dirdata::DirData;
+
+/// Order same-size groups are hashed in, deciding which sets are found first when a run
+/// stops early or streams its results through `DupeFinder::run_iter`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HashOrder {
+    /// Groups of the largest files first
+    #[default]
+    LargestFirst,
+    /// Groups whose files take up the most space beyond a single copy first
+    MostReclaimable,
+    /// Groups of the smallest files first, finding many small sets quickly
+    SmallestFirst,
+}
+
+impl HashOrder {
+    // sorts the candidate keys of `file_sizes`, ties are broken by the key
+    pub(crate) fn sort(&self, keys: &mut [u64], file_sizes: &HashMap<u64, Vec<DirData>>) {
+        keys.sort_by_cached_key(|key| {
+            let group = file_sizes.get(key).map(Vec::as_slice).unwrap_or(&[]);
+            let size = group.iter().map(|data| data.size).max().unwrap_or(0);
+            let rank = match self {
+                HashOrder::LargestFirst => size,
+                HashOrder::MostReclaimable => size.saturating_mul(group.len().saturating_sub(1) as u64),
+                HashOrder::SmallestFirst => u64::MAX - size,
+            };
+            (Reverse(rank), *key)
+        });
+    }
+}
+
+// Caps on the work of a single run, files beyond them are left out and the results marked truncated
+#[derive(Clone, Copy, Default)]
+pub struct ScanBudget {
+    // most files taken in while traversing
+    pub max_files: Option<usize>,
+    // most bytes of file contents hashed in full
+    pub max_bytes: Option<u64>,
+    // duplicate sets after which hashing stops
+    pub max_groups: Option<usize>,
+}
+
+// Work of the current run counted against a `ScanBudget`, shared with hashing threads
+#[derive(Default)]
+pub struct BudgetUsage {
+    files: AtomicUsize,
+    bytes: AtomicU64,
+    truncated: AtomicBool,
+}
+
+impl BudgetUsage {
+    // counts a file, false once the budget has no room left for it
+    pub fn take_file(&self, budget: &ScanBudget) -> bool {
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        self.within(budget.max_files.is_none_or(|max| files <= max))
+    }
+
+    // counts `bytes` about to be hashed, false when they do not fit the budget
+    pub fn take_bytes(&self, budget: &ScanBudget, bytes: u64) -> bool {
+        let max = match budget.max_bytes {
+            Some(max) => max,
+            None => return true,
+        };
+
+        let taken = self.bytes.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+            used.checked_add(bytes).filter(|total| *total <= max)
+        });
+        self.within(taken.is_ok())
+    }
+
+    // true once a file was left out for the budget
+    pub fn truncated(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+    }
+
+    pub fn truncate(&self) {
+        self.truncated.store(true, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.files.store(0, Ordering::Relaxed);
+        self.bytes.store(0, Ordering::Relaxed);
+        self.truncated.store(false, Ordering::Relaxed);
+    }
+
+    fn within(&self, within: bool) -> bool {
+        if !within {
+            self.truncate();
+        }
+        within
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn group(size: u64, files: usize) -> Vec<DirData> {
+        let meta = std::fs::metadata(env!("CARGO_MANIFEST_DIR")).unwrap();
+        (0..files).map(|index| DirData { size, path: std::path::PathBuf::from(index.to_string()), meta: meta.clone() }).collect()
+    }
+
+    #[test]
+    fn test_hash_order() {
+        let file_sizes: HashMap<u64, Vec<DirData>> = [(1, group(100, 2)), (2, group(10, 20)), (3, group(50, 2))].into_iter().collect();
+        let sorted = |order: HashOrder| {
+            let mut keys = vec![3, 1, 2];
+            order.sort(&mut keys, &file_sizes);
+            keys
+        };
+
+        assert_eq!(sorted(HashOrder::LargestFirst), vec![1, 3, 2]);
+        assert_eq!(sorted(HashOrder::MostReclaimable), vec![2, 1, 3]);
+        assert_eq!(sorted(HashOrder::SmallestFirst), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_default_unlimited() {
+        let usage = BudgetUsage::default();
+        let budget = ScanBudget::default();
+        assert!(usage.take_file(&budget));
+        assert!(usage.take_bytes(&budget, u64::MAX));
+        assert!(!usage.truncated());
+    }
+
+    #[test]
+    fn test_budget() {
+        let usage = BudgetUsage::default();
+        let budget = ScanBudget { max_files: Some(1), max_bytes: Some(100), max_groups: None };
+        assert!(usage.take_file(&budget));
+        assert!(usage.take_bytes(&budget, 60));
+        assert!(!usage.truncated());
+
+        assert!(!usage.take_bytes(&budget, 60), "bytes beyond the budget expected to be refused");
+        assert!(usage.take_bytes(&budget, 40), "a smaller file should still fit");
+        assert!(!usage.take_file(&budget));
+        assert!(usage.truncated());
+
+        usage.clear();
+        assert!(!usage.truncated(), "usage should be cleared");
+        assert!(usage.take_file(&budget));
+    }
+}
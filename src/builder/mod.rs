@@ -0,0 +1,836 @@
+use std::{fs::Metadata, path::{Path, PathBuf}, sync::Arc, time::{Duration, SystemTime}};
+use log::LevelFilter;
+use crate::{budget::HashOrder, filter::{normalize_extension, Glob, WalkDecision}, hardlinks::HardlinkPolicy, hasher::FileHasher, strategy::MatchStrategy, options::{Options, SymlinkPolicy}, shard::Shard, throttle::{ReadThrottle, ThrottledHasher}, to_path_bufs, DupeFinder, LogCategory, SpecialFilePolicy};
+
+/// Composes the configuration of a `DupeFinder` before constructing it
+///
+/// # Examples
+/// ```
+/// let directories = vec![String::from("./resources")];
+/// let checker = dupefinder::DupeFinderBuilder::new(directories)
+///     .recursive(true)
+///     .build();
+///
+/// if let Ok(mut checker) = checker {
+///     let results = checker.run();
+///     println!("{} duplicate sets found", results.len());
+/// };
+/// ```
+pub struct DupeFinderBuilder {
+    directories: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    options: Options,
+    // bytes per second, wraps the hasher on build
+    max_read_rate: Option<u64>,
+    #[cfg(feature = "parallel")]
+    threads: Option<usize>,
+    // (pattern, include) pairs compiled on build
+    #[cfg(feature = "regex")]
+    regex_rules: Vec<(String, bool)>,
+    // opened on build
+    #[cfg(feature = "cache")]
+    hash_cache: Option<PathBuf>,
+}
+
+impl DupeFinderBuilder {
+    /// Starts a configuration searching the provided directories
+    pub fn new<I, P>(directories: I) -> DupeFinderBuilder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        DupeFinderBuilder {
+            directories: to_path_bufs(directories),
+            files: Vec::new(),
+            options: Options::default(),
+            max_read_rate: None,
+            #[cfg(feature = "parallel")]
+            threads: None,
+            #[cfg(feature = "regex")]
+            regex_rules: Vec::new(),
+            #[cfg(feature = "cache")]
+            hash_cache: None,
+        }
+    }
+
+    /// Starts a configuration searching only the provided files, see `DupeFinder::from_files`
+    pub fn from_files<I, P>(files: I) -> DupeFinderBuilder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        DupeFinderBuilder::new(Vec::<PathBuf>::new()).files(files)
+    }
+
+    /// Also search `files` without traversing any directory, see `DupeFinder::from_files`
+    pub fn files<I, P>(mut self, files: I) -> DupeFinderBuilder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.files.extend(to_path_bufs(files));
+        self
+    }
+
+    /// Recursively traverse all subdirectories of the provided directories
+    pub fn recursive(mut self, recursive: bool) -> DupeFinderBuilder {
+        self.options.follow_subdirs = recursive;
+        self
+    }
+
+    /// Recursively traverse subdirectories, but at most `depth` levels below the provided
+    /// directories: `0` searches the provided directories only, `1` also their direct subdirectories
+    pub fn max_depth(mut self, depth: usize) -> DupeFinderBuilder {
+        self.options.follow_subdirs = true;
+        self.options.max_depth = Some(depth);
+        self
+    }
+
+    /// Do not descend into subdirectories on another filesystem, such as network mounts or external
+    /// drives mounted below a searched directory. Filesystems are only told apart on unix.
+    pub fn same_filesystem(mut self, same: bool) -> DupeFinderBuilder {
+        self.options.same_filesystem = same;
+        self
+    }
+
+    /// Also search `directories` as reference directories: their files are matched against
+    /// but never reported, see `DupeFinder::with_reference`
+    pub fn reference_directories<I, P>(mut self, directories: I) -> DupeFinderBuilder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut references = to_path_bufs(directories);
+        self.directories.extend(references.iter().cloned());
+        self.options.references.append(&mut references);
+        self
+    }
+
+    /// Only consider files with one of these extensions, e.g. `["jpg", "png"]`.
+    /// Extensions are matched case insensitively, a leading dot is optional.
+    pub fn include_extensions<I, S>(mut self, extensions: I) -> DupeFinderBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.options.filter.include_extensions = Some(extensions.into_iter().map(|extension| normalize_extension(extension.as_ref())).collect());
+        self
+    }
+
+    /// Never consider files with one of these extensions, takes precedence over `include_extensions`
+    pub fn exclude_extensions<I, S>(mut self, extensions: I) -> DupeFinderBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.options.filter.exclude_extensions = extensions.into_iter().map(|extension| normalize_extension(extension.as_ref())).collect();
+        self
+    }
+
+    /// Skip hidden files and directories: names starting with a dot on unix,
+    /// entries with the hidden attribute on windows. The provided directories are always searched.
+    pub fn skip_hidden(mut self, skip: bool) -> DupeFinderBuilder {
+        self.options.filter.skip_hidden = skip;
+        self
+    }
+
+    /// Only consider files last modified at or after `time`, e.g. to find out what a recent import
+    /// duplicated without hashing the older files. Files in reference directories are matched
+    /// whatever their age, so older copies are still found there.
+    pub fn modified_after(mut self, time: SystemTime) -> DupeFinderBuilder {
+        self.options.filter.modified_after = Some(time);
+        self
+    }
+
+    /// Only consider files last modified before `time`, see `modified_after`
+    pub fn modified_before(mut self, time: SystemTime) -> DupeFinderBuilder {
+        self.options.filter.modified_before = Some(time);
+        self
+    }
+
+    /// Skip files and directories matching one of these patterns while traversing,
+    /// e.g. `["**/node_modules/**", "*.tmp"]`. Excluded directories are not descended into.
+    ///
+    /// `*` and `?` match within a single path component and `**` matches any number of
+    /// components. Patterns without a `/` are matched against the file name only, others
+    /// against the full path as it is traversed.
+    pub fn exclude_globs<I, S>(mut self, patterns: I) -> DupeFinderBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.options.filter.exclude_globs = patterns.into_iter().map(|pattern| Glob::new(pattern.as_ref())).collect();
+        self
+    }
+
+    /// Skip files and directories whose full path matches the regular expression `pattern`,
+    /// excluded directories are not descended into
+    ///
+    /// Regex rules are checked in the order they were added and the last matching rule decides,
+    /// so a later `include_regex` can carve exceptions out of an exclusion.
+    /// Invalid patterns are reported by `build()`.
+    ///
+    /// # Examples
+    /// ```
+    /// // skip dot-directories, except for .config
+    /// let checker = dupefinder::DupeFinder::builder(vec!["./resources"])
+    ///     .recursive(true)
+    ///     .exclude_regex(r"/\.[^/]+(/|$)")
+    ///     .include_regex(r"/\.config(/|$)")
+    ///     .build();
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn exclude_regex(mut self, pattern: &str) -> DupeFinderBuilder {
+        self.regex_rules.push((pattern.to_string(), false));
+        self
+    }
+
+    /// Keep files and directories whose full path matches `pattern` even if an earlier
+    /// `exclude_regex` matched them, see `exclude_regex`
+    #[cfg(feature = "regex")]
+    pub fn include_regex(mut self, pattern: &str) -> DupeFinderBuilder {
+        self.regex_rules.push((pattern.to_string(), true));
+        self
+    }
+
+    /// How FIFOs, sockets and device files are handled, defaults to `SpecialFilePolicy::Skip`
+    pub fn special_files(mut self, policy: SpecialFilePolicy) -> DupeFinderBuilder {
+        self.options.special_files = policy;
+        self
+    }
+
+    /// Skip files and directories whose path is longer than `length` bytes,
+    /// recording them with `SkipReason::PathTooLong`
+    pub fn limit_path_length(mut self, length: usize) -> DupeFinderBuilder {
+        self.options.limits.max_path_length = Some(length);
+        self
+    }
+
+    /// Skip directories nested more than `depth` levels below a provided directory,
+    /// recording them with `SkipReason::TooDeep`. This is a sanity limit for automated scans,
+    /// unlike the recursion setting reaching it is reported.
+    pub fn limit_depth(mut self, depth: usize) -> DupeFinderBuilder {
+        self.options.limits.max_depth = Some(depth);
+        self
+    }
+
+    /// Stop reading a directory after `entries` entries, recording it with `SkipReason::TooManyEntries`
+    pub fn limit_directory_entries(mut self, entries: usize) -> DupeFinderBuilder {
+        self.options.limits.max_entries = Some(entries);
+        self
+    }
+
+    /// Take in at most `files` files while traversing, the results of a run reaching it only
+    /// cover the files found up to then and are marked with `ScanStats::truncated`
+    pub fn max_files(mut self, files: usize) -> DupeFinderBuilder {
+        self.options.budget.max_files = Some(files);
+        self
+    }
+
+    /// Hash at most `bytes` bytes of file contents per run, files that no longer fit are left
+    /// out and the results are marked with `ScanStats::truncated`. Hashes taken from
+    /// `incremental` runs or a hash cache are free, partial hashes are not counted.
+    pub fn max_bytes(mut self, bytes: u64) -> DupeFinderBuilder {
+        self.options.budget.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Stop hashing once `groups` duplicate sets are found, for a sample of what is duplicated.
+    /// Same-size groups are then hashed one after another, results of a run stopping early are
+    /// marked with `ScanStats::truncated`. Applies to `DupeFinder::run`.
+    pub fn max_groups(mut self, groups: usize) -> DupeFinderBuilder {
+        self.options.budget.max_groups = Some(groups);
+        self
+    }
+
+    /// Only report sets of at least `copies` files, reference files included, e.g. 3 to leave out
+    /// pairs. Applies to `DupeFinder::run`, `run_iter` and `watch`, not to searches for a single file.
+    pub fn min_copies(mut self, copies: usize) -> DupeFinderBuilder {
+        self.options.min_copies = copies;
+        self
+    }
+
+    /// Only report sets whose files beyond the one kept take up at least `bytes` bytes, every
+    /// listed file with reference directories, so pairs of tiny files do not clutter reports.
+    /// Applies like `min_copies`.
+    pub fn min_group_waste_bytes(mut self, bytes: u64) -> DupeFinderBuilder {
+        self.options.min_group_waste = bytes;
+        self
+    }
+
+    /// Order same-size groups are hashed in, defaults to `HashOrder::LargestFirst` so the
+    /// biggest duplicates are found first by `max_groups`, `max_bytes` and `DupeFinder::run_iter`
+    pub fn hash_order(mut self, order: HashOrder) -> DupeFinderBuilder {
+        self.options.hash_order = order;
+        self
+    }
+
+    /// Leave out files and directories matched by the `.gitignore` and `.ignore` files found in the
+    /// searched directories and below, such as `target/` or `node_modules/`. Rules of deeper files
+    /// win and `.ignore` wins over `.gitignore`, files outside of git repositories are honored too.
+    #[cfg(feature = "gitignore")]
+    pub fn respect_gitignore(mut self, respect: bool) -> DupeFinderBuilder {
+        self.options.respect_gitignore = respect;
+        self
+    }
+
+    /// Match files by their decompressed contents, so `report.log` and `report.log.gz` holding the same
+    /// text are duplicates, see `DecompressStrategy`. Replaces the configured strategy, `false` restores
+    /// `ContentStrategy`.
+    #[cfg(feature = "decompress")]
+    pub fn decompress(mut self, decompress: bool) -> DupeFinderBuilder {
+        self.options.strategy = match decompress {
+            true => Arc::new(crate::DecompressStrategy),
+            false => Arc::new(crate::ContentStrategy),
+        };
+        self
+    }
+
+    /// Call `filter` for every file and directory found while traversing that passes the other
+    /// filters, its `WalkDecision` accepts or skips the entry, or skips the whole directory holding
+    /// it. Symlinks that are followed are passed with the metadata of their target.
+    ///
+    /// # Examples
+    /// ```
+    /// use dupefinder::WalkDecision;
+    ///
+    /// let checker = dupefinder::DupeFinder::builder(vec!["./resources"])
+    ///     .recursive(true)
+    ///     .walk_filter(|path, meta| {
+    ///         if path.file_name().is_some_and(|name| name == "CACHEDIR.TAG") {
+    ///             WalkDecision::SkipDirectory
+    ///         } else if meta.is_file() && meta.len() > 1 << 30 {
+    ///             WalkDecision::Skip
+    ///         } else {
+    ///             WalkDecision::Accept
+    ///         }
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn walk_filter<F>(mut self, filter: F) -> DupeFinderBuilder
+    where
+        F: Fn(&Path, &Metadata) -> WalkDecision + Send + Sync + 'static,
+    {
+        self.options.filter.walk = Some(Arc::new(filter));
+        self
+    }
+
+    /// How symbolic links are handled, defaults to `SymlinkPolicy::Skip`.
+    /// Directory links are only followed when recursion is on.
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> DupeFinderBuilder {
+        self.options.symlinks = policy;
+        self
+    }
+
+    /// How multiple hard links to the same file are handled, defaults to `HardlinkPolicy::Keep`
+    pub fn hardlinks(mut self, policy: HardlinkPolicy) -> DupeFinderBuilder {
+        self.options.hardlinks = policy;
+        self
+    }
+
+    /// Hashing algorithm used to compare same-size files, defaults to `Xxh3Hasher`
+    pub fn hasher<H: FileHasher + 'static>(mut self, hasher: H) -> DupeFinderBuilder {
+        self.options.hasher = Arc::new(hasher);
+        self
+    }
+
+    /// Read at most `bytes_per_second` while hashing, across all hashing threads, so backups and
+    /// interactive work on the same disks are not starved by a scan. `0` turns this off.
+    ///
+    /// Applies to every read made through the configured hasher and to the byte by byte comparisons
+    /// of `verify_contents`, up to a second worth of bytes may be read at full speed after a pause.
+    /// Archive members and mail attachments count with their unpacked bytes as they are hashed.
+    /// A custom `FileHasher::hash_file` is not called while throttled. Not throttled are the reads
+    /// `DecompressStrategy` makes to find decompressed sizes and those of a custom `MatchStrategy`
+    /// reading files without the hasher.
+    pub fn max_read_rate(mut self, bytes_per_second: u64) -> DupeFinderBuilder {
+        self.max_read_rate = Some(bytes_per_second).filter(|rate| *rate > 0);
+        self
+    }
+
+    /// Hash only the first `bytes` of same-size files and hash them in full only when those match,
+    /// which avoids reading most of large same-size files that differ early on. `0` turns this off.
+    /// It compares file contents, so it is only meant for strategies matching on contents.
+    pub fn partial_hash(mut self, bytes: u64) -> DupeFinderBuilder {
+        self.options.partial_hash = Some(bytes).filter(|bytes| *bytes > 0);
+        self
+    }
+
+    /// Compare files byte by byte after their hashes matched and only report them when their
+    /// contents are identical, guarding against hash collisions and files modified mid-scan.
+    /// Files failing the comparison are left out of the set and logged. Compares file contents,
+    /// so it is only meant for strategies matching on contents.
+    pub fn verify_contents(mut self, verify: bool) -> DupeFinderBuilder {
+        self.options.verify_contents = verify;
+        self
+    }
+
+    /// How files are grouped and matched, defaults to `ContentStrategy`
+    pub fn strategy<S: MatchStrategy + 'static>(mut self, strategy: S) -> DupeFinderBuilder {
+        self.options.strategy = Arc::new(strategy);
+        self
+    }
+
+    /// Most verbose level of messages emitted for `category`, all levels are emitted by default
+    pub fn log_level(mut self, category: LogCategory, level: LevelFilter) -> DupeFinderBuilder {
+        self.options.log.set_level(category, level);
+        self
+    }
+
+    /// Keep scan messages on the `DupeFinder` (see `DupeFinder::log_messages`)
+    /// instead of sending them to the global logger
+    pub fn collect_logs(mut self, collect: bool) -> DupeFinderBuilder {
+        self.options.log.collect = collect;
+        self
+    }
+
+    /// Log a warning when a stat, open or read of a single file makes no progress for `timeout`,
+    /// the operation keeps waiting
+    pub fn soft_timeout(mut self, timeout: Duration) -> DupeFinderBuilder {
+        self.options.timeouts.soft = Some(timeout);
+        self
+    }
+
+    /// Skip a file with `SkipReason::TimedOut` when a stat, open or read of it makes no progress
+    /// for `timeout`, so dead network mounts or failing disks can not hang the scan.
+    /// Every operation then runs on a helper thread, a thread stuck in the operating system
+    /// is left behind rather than cancelled.
+    pub fn hard_timeout(mut self, timeout: Duration) -> DupeFinderBuilder {
+        self.options.timeouts.hard = Some(timeout);
+        self
+    }
+
+    /// Stop reading from a device once `limit` files on it failed to read, its remaining files are
+    /// skipped with `SkipReason::DeviceQuarantined` while other devices are still scanned.
+    /// Devices are only told apart on unix, elsewhere this setting has no effect.
+    pub fn device_error_limit(mut self, limit: usize) -> DupeFinderBuilder {
+        self.options.device_error_limit = Some(limit.max(1));
+        self
+    }
+
+    /// Keep the hashes of each run in memory so later runs of the same `DupeFinder` only hash
+    /// files whose size or modification time changed. Directories are still traversed on every
+    /// run, so added and removed files are picked up. Files that are gone are forgotten.
+    pub fn incremental(mut self, incremental: bool) -> DupeFinderBuilder {
+        self.options.incremental = incremental;
+        self
+    }
+
+    /// Stop at the first file or directory that could not be read instead of skipping it,
+    /// `DupeFinder::try_run` then returns it as an error. Files skipped for a `SkipReason`
+    /// are not errors.
+    pub fn strict(mut self, strict: bool) -> DupeFinderBuilder {
+        self.options.strict = strict;
+        self
+    }
+
+    /// Have `DupeFinder::run_for_file` stop traversing and hashing as soon as one copy of the
+    /// searched file is confirmed, the resulting `Duplicate` then holds the file and that copy only
+    pub fn find_first(mut self, find_first: bool) -> DupeFinderBuilder {
+        self.options.find_first = find_first;
+        self
+    }
+
+    /// Hash a file up to `retries` more times when it fails with a stale network file handle
+    /// (`ESTALE`), which NFS and SMB mounts report transiently after server side changes
+    pub fn retry_stale_handles(mut self, retries: u32) -> DupeFinderBuilder {
+        self.options.stale_handle_retries = retries;
+        self
+    }
+
+    /// Settings suited to NFS and SMB mounts: a 10 second soft and 2 minute hard timeout,
+    /// 3 retries of stale file handles, a device error limit of 20 and, with the `parallel`
+    /// feature, 2 hashing threads. Settings made after this call override it.
+    ///
+    /// File metadata is always read once per run and kept for the whole scan. Server side
+    /// checksums are not exposed through the standard library, files are still read in full.
+    pub fn network_profile(self) -> DupeFinderBuilder {
+        let builder = self
+            .soft_timeout(Duration::from_secs(10))
+            .hard_timeout(Duration::from_secs(120))
+            .retry_stale_handles(3)
+            .device_error_limit(20);
+        #[cfg(feature = "parallel")]
+        let builder = builder.parallel(2);
+        builder
+    }
+
+    /// Only search slice `index` of `count` (`0..count`) so a very large search can be split across
+    /// processes or hosts, merge the results of every slice with `ScanResults::merge`.
+    ///
+    /// Every shard still traverses all directories, but only counts and hashes the files
+    /// assigned to it. Files are assigned by their size (the candidate key of the strategy),
+    /// not their path, so files that might be duplicates of each other are always in the same
+    /// shard. All shards need the same directories and strategy.
+    pub fn shard(mut self, index: usize, count: usize) -> DupeFinderBuilder {
+        self.options.shard = Some(Shard { index, count });
+        self
+    }
+
+    /// Hash same-size candidates in parallel across a pool of `threads` threads,
+    /// `0` uses one thread per available CPU. Results are identical to a sequential run.
+    #[cfg(feature = "parallel")]
+    pub fn parallel(mut self, threads: usize) -> DupeFinderBuilder {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Keeps the hashes of files in an on-disk cache at `path` between runs, files whose canonical
+    /// path, size and modification time are unchanged are not read again. Hashes are kept apart by
+    /// the `identity` of the hasher and strategy, so one cache can serve several configurations.
+    #[cfg(feature = "cache")]
+    pub fn hash_cache<P: AsRef<Path>>(mut self, path: P) -> DupeFinderBuilder {
+        self.hash_cache = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Validates the configuration and constructs the `DupeFinder`
+    pub fn build(self) -> Result<DupeFinder, ConfigError> {
+        if self.directories.is_empty() && self.files.is_empty() {
+            return Err(ConfigError::NoDirectories);
+        }
+
+        if let Some(shard) = self.options.shard.filter(|shard| !shard.is_valid()) {
+            return Err(ConfigError::InvalidShard(shard.index, shard.count));
+        }
+
+        let options = match self.max_read_rate {
+            Some(rate) => {
+                let throttle = Arc::new(ReadThrottle::new(rate));
+                Options { hasher: Arc::new(ThrottledHasher::new(self.options.hasher.clone(), throttle.clone())), read_throttle: Some(throttle), ..self.options }
+            },
+            None => self.options,
+        };
+        #[cfg(feature = "parallel")]
+        let options = Options { thread_pool: build_thread_pool(self.threads)?, ..options };
+        #[cfg(feature = "regex")]
+        let options = Options {
+            filter: crate::filter::PathFilter { regex_rules: build_regex_rules(&self.regex_rules)?, ..options.filter },
+            ..options
+        };
+        #[cfg(feature = "cache")]
+        let options = Options { hash_cache: open_hash_cache(self.hash_cache.as_deref(), &options)?, ..options };
+
+        Ok(DupeFinder { files: self.files, ..DupeFinder::with_options(self.directories, options) })
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn build_thread_pool(threads: Option<usize>) -> Result<Option<Arc<rayon::ThreadPool>>, ConfigError> {
+    let threads = match threads {
+        Some(threads) => threads,
+        None => return Ok(None),
+    };
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| ConfigError::ThreadPool(e.to_string()))?;
+
+    Ok(Some(Arc::new(pool)))
+}
+
+#[cfg(feature = "regex")]
+fn build_regex_rules(rules: &[(String, bool)]) -> Result<Vec<crate::filter::RegexRule>, ConfigError> {
+    rules.iter()
+        .map(|(pattern, include)| match regex::Regex::new(pattern) {
+            Ok(regex) => Ok(crate::filter::RegexRule { regex, include: *include }),
+            Err(e) => Err(ConfigError::InvalidRegex(e.to_string())),
+        })
+        .collect()
+}
+
+#[cfg(feature = "cache")]
+fn open_hash_cache(path: Option<&Path>, options: &Options) -> Result<Option<Arc<crate::cache::HashCache>>, ConfigError> {
+    let identity = format!("{}+{}", options.strategy.identity(), options.hasher.identity());
+    match path {
+        Some(path) => crate::cache::HashCache::open(path, identity)
+            .map(|cache| Some(Arc::new(cache)))
+            .map_err(|e| ConfigError::HashCache(e.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Problems found while validating a `DupeFinderBuilder` configuration
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ConfigError {
+    /// No directories or files to search were provided
+    #[error("no directories or files to search were provided")]
+    NoDirectories,
+    /// The shard index is not below the shard count
+    #[error("shard {0} does not exist, shards range from 0 to {last}", last = .1.saturating_sub(1))]
+    InvalidShard(usize, usize),
+    /// A setting of `DupeFinderConfig` holds a value that can not be parsed, (variable, value)
+    #[error("invalid value for {0}: {1}")]
+    InvalidSetting(String, String),
+    /// A `DupeFinderConfig` file could not be read or parsed
+    #[cfg(feature = "serde")]
+    #[error("unable to read config file: {0}")]
+    ConfigFile(String),
+    /// The thread pool for parallel hashing could not be created
+    #[cfg(feature = "parallel")]
+    #[error("unable to create hashing thread pool: {0}")]
+    ThreadPool(String),
+    /// A path filter is not a valid regular expression
+    #[cfg(feature = "regex")]
+    #[error("invalid path filter: {0}")]
+    InvalidRegex(String),
+    /// The hash cache could not be opened
+    #[cfg(feature = "cache")]
+    #[error("unable to open hash cache: {0}")]
+    HashCache(String),
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ScanResults;
+
+    #[test]
+    fn test_build_defaults() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let checker = DupeFinderBuilder::new(vec![path.display().to_string()]).build();
+
+        assert!(checker.is_ok(), "no config error expected");
+        assert!(!checker.unwrap().options.follow_subdirs, "recursion should be off by default");
+    }
+
+    #[test]
+    fn test_build_recursive() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let checker = DupeFinderBuilder::new(vec![path.display().to_string()]).recursive(true).build();
+
+        assert!(checker.is_ok(), "no config error expected");
+        let mut checker = checker.unwrap();
+        assert!(checker.options.follow_subdirs, "recursion should be on");
+        assert_eq!(checker.run().len(), 1);
+    }
+
+    #[test]
+    fn test_build_reference_directories() {
+        let reference: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let target: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&target]).reference_directories([&reference]).recursive(true).build().unwrap();
+
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+        let duplicate = results.values().next().unwrap();
+        assert_eq!(duplicate.entries.len(), 2);
+        assert_eq!(results.references(&duplicate.hash).len(), 2);
+    }
+
+    #[test]
+    fn test_build_extensions() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&path]).recursive(true).include_extensions(["png", "jpg"]).build().unwrap();
+        assert!(checker.run().values().all(|duplicate| !duplicate.paths().any(|file| file.extension().is_some_and(|extension| extension == "txt"))));
+
+        let mut checker = DupeFinderBuilder::new([&path]).recursive(true).include_extensions([".TXT"]).build().unwrap();
+        assert!(!checker.run().is_empty(), "text duplicates expected");
+
+        let mut checker = DupeFinderBuilder::new([&path]).recursive(true).include_extensions(["txt"]).exclude_extensions(["txt"]).build().unwrap();
+        assert!(checker.run().is_empty(), "exclusion should take precedence");
+    }
+
+    #[test]
+    fn test_build_exclude_globs() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&path]).recursive(true).exclude_globs(["**/dir_b/**"]).build().unwrap();
+
+        assert!(checker.run().is_empty(), "duplicate in excluded directory expected to be skipped");
+        assert_eq!(checker.checked_directories.len(), 2);
+
+        let mut checker = DupeFinderBuilder::new([&path]).recursive(true).exclude_globs(["*.png"]).build().unwrap();
+        assert_eq!(checker.run().len(), 1);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_build_regex() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&path]).recursive(true).exclude_regex("dir_").build().unwrap();
+        assert!(checker.run().is_empty(), "all subdirectories should be excluded");
+        assert_eq!(checker.checked_directories.len(), 1);
+
+        let mut checker = DupeFinderBuilder::new([&path]).recursive(true).exclude_regex("dir_").include_regex("dir_[ab](/|$)").build().unwrap();
+        assert_eq!(checker.run().len(), 1);
+
+        let checker = DupeFinderBuilder::new([&path]).exclude_regex("dir_(").build();
+        assert!(matches!(checker.err(), Some(ConfigError::InvalidRegex(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_skip_hidden() {
+        let temp = tempfile::tempdir().unwrap();
+        let source: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        std::fs::create_dir(temp.path().join(".cache")).unwrap();
+        std::fs::copy(&source, temp.path().join("a.txt")).unwrap();
+        std::fs::copy(&source, temp.path().join(".b.txt")).unwrap();
+        std::fs::copy(&source, temp.path().join(".cache").join("c.txt")).unwrap();
+
+        let mut checker = DupeFinderBuilder::new([temp.path()]).recursive(true).build().unwrap();
+        assert_eq!(checker.run().values().next().unwrap().entries.len(), 3);
+
+        let mut checker = DupeFinderBuilder::new([temp.path()]).recursive(true).skip_hidden(true).build().unwrap();
+        assert!(checker.run().is_empty(), "hidden duplicates should be skipped");
+        assert_eq!(checker.checked_directories.len(), 1);
+    }
+
+    #[test]
+    fn test_build_strategy() {
+        struct ExtensionStrategy;
+        impl MatchStrategy for ExtensionStrategy {
+            fn candidate_key(&self, _path: &Path, _meta: &std::fs::Metadata) -> Option<u64> {
+                Some(1)
+            }
+
+            fn match_key(&self, path: &Path, _hasher: &dyn FileHasher) -> Result<String, std::io::Error> {
+                Ok(path.extension().unwrap_or_default().to_string_lossy().to_string())
+            }
+        }
+
+        let path_a: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "base"].iter().collect();
+        let path_b: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "insert_size"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&path_a, &path_b]).strategy(ExtensionStrategy).build().unwrap();
+
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results["txt"].entries.len(), 2);
+
+        let result = checker.run_for_file(path_a.join("a.txt")).unwrap();
+        assert_eq!(result.unwrap().entries.len(), 2);
+    }
+
+    #[test]
+    fn test_build_hasher() {
+        struct LengthHasher;
+        impl FileHasher for LengthHasher {
+            fn hash_reader(&self, reader: &mut dyn std::io::Read) -> Result<String, std::io::Error> {
+                let mut data: Vec<u8> = Vec::new();
+                reader.read_to_end(&mut data)?;
+                Ok(format!("LEN{}", data.len()))
+            }
+        }
+
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&path]).hasher(LengthHasher).build().unwrap();
+
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+        assert!(results.contains_key("LEN100"));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_build_parallel_matches_sequential() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let mut sequential = DupeFinderBuilder::new([&path]).recursive(true).build().unwrap();
+        let mut parallel = DupeFinderBuilder::new([&path]).recursive(true).parallel(4).build().unwrap();
+        assert!(parallel.options.thread_pool.is_some(), "thread pool expected");
+
+        let expected = sequential.run();
+        let results = parallel.run();
+        assert_eq!(results.len(), expected.len());
+        for (hash, duplicate) in expected.iter() {
+            assert_eq!(results[hash].entries, duplicate.entries);
+        }
+    }
+
+    #[test]
+    fn test_build_collect_logs() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let missing: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "doesnotexist"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&path, &missing])
+            .collect_logs(true)
+            .log_level(LogCategory::Hashing, LevelFilter::Off)
+            .build()
+            .unwrap();
+
+        checker.run();
+        let messages = checker.log_messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].category, LogCategory::Traversal);
+        assert_eq!(messages[0].level, log::Level::Warn);
+
+        let mut checker = DupeFinderBuilder::new([&path, &missing])
+            .collect_logs(true)
+            .log_level(LogCategory::Traversal, LevelFilter::Error)
+            .build()
+            .unwrap();
+
+        checker.run();
+        assert!(checker.log_messages().is_empty(), "traversal warnings should be filtered");
+    }
+
+    #[test]
+    fn test_build_hard_timeout() {
+        struct StalledHasher;
+        impl FileHasher for StalledHasher {
+            fn hash_reader(&self, _reader: &mut dyn std::io::Read) -> Result<String, std::io::Error> {
+                std::thread::sleep(Duration::from_millis(500));
+                Ok(String::from("STALLED"))
+            }
+        }
+
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&path])
+            .hasher(StalledHasher)
+            .hard_timeout(Duration::from_millis(50))
+            .collect_logs(true)
+            .build()
+            .unwrap();
+
+        assert!(checker.run().is_empty(), "stalled files should be skipped");
+        let skipped = checker.skipped_files();
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped.iter().all(|file| file.reason == crate::SkipReason::TimedOut));
+
+        let mut checker = DupeFinderBuilder::new([&path])
+            .soft_timeout(Duration::from_millis(500))
+            .hard_timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+
+        assert_eq!(checker.run().len(), 1);
+        assert!(checker.skipped_files().is_empty(), "nothing should be skipped");
+    }
+
+    #[test]
+    fn test_build_shards_merge() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let full = DupeFinderBuilder::new([&path]).recursive(true).build().unwrap().run();
+
+        let merged = (0..3)
+            .map(|index| DupeFinderBuilder::new([&path]).recursive(true).shard(index, 3).build().unwrap().run())
+            .reduce(ScanResults::merge)
+            .unwrap();
+
+        let mut hashes: Vec<&String> = merged.keys().collect();
+        let mut expected: Vec<&String> = full.keys().collect();
+        hashes.sort();
+        expected.sort();
+        assert_eq!(hashes, expected);
+        assert_eq!(merged.stats(), full.stats());
+
+        let checker = DupeFinderBuilder::new([&path]).shard(3, 3).build();
+        assert_eq!(checker.err(), Some(ConfigError::InvalidShard(3, 3)));
+    }
+
+    #[test]
+    fn test_build_network_profile() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinderBuilder::new([&path]).network_profile().retry_stale_handles(1).build().unwrap();
+
+        assert_eq!(checker.options.timeouts.hard, Some(Duration::from_secs(120)));
+        assert_eq!(checker.options.stale_handle_retries, 1, "later settings should override the profile");
+        assert_eq!(checker.run().len(), 1);
+    }
+
+    #[test]
+    fn test_build_no_directories() {
+        let checker = DupeFinderBuilder::new(Vec::<String>::new()).build();
+        assert_eq!(checker.err(), Some(ConfigError::NoDirectories));
+    }
+}
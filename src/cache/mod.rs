@@ -0,0 +1,162 @@
+use std::{fs::{self, Metadata}, io, path::{Path, PathBuf}, time::UNIX_EPOCH};
+use log::Level;
+use crate::{dirdata::DirData, DupeFinder, LogCategory};
+
+// size (8) + modification seconds (8) + nanoseconds (4), followed by the hash
+pub(crate) const HEADER_LENGTH: usize = 20;
+
+// On-disk store of match keys between runs, keyed by the identity of the hasher and strategy
+// producing them and the canonical path, checked against the size and modification time of the file
+pub struct HashCache {
+    db: sled::Db,
+    identity: String,
+}
+
+impl HashCache {
+    pub fn open(path: &Path, identity: String) -> Result<HashCache, sled::Error> {
+        Ok(HashCache { db: sled::open(path)?, identity })
+    }
+
+    // the stored hash of `path` if the file is unchanged since it was stored
+    pub fn get(&self, path: &Path, meta: &Metadata) -> Result<Option<String>, io::Error> {
+        let header = match header(meta) {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        let value = match self.db.get(self.key(path)).map_err(io::Error::other)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        Ok(decode(&value, &header))
+    }
+
+    pub fn insert(&self, path: &Path, meta: &Metadata, hash: &str) -> Result<(), io::Error> {
+        let mut value = match header(meta) {
+            Some(header) => header.to_vec(),
+            None => return Ok(()),
+        };
+        value.extend_from_slice(hash.as_bytes());
+
+        self.db.insert(self.key(path), value).map(|_| ()).map_err(io::Error::other)
+    }
+
+    // the identity, a NUL byte which no identity contains and the path
+    fn key(&self, path: &Path) -> Vec<u8> {
+        let path: PathBuf = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut key = self.identity.replace('\0', "").into_bytes();
+        key.push(0);
+        key.extend_from_slice(path.as_os_str().as_encoded_bytes());
+        key
+    }
+}
+
+// the hash stored in `value` if it was stored for a file matching `header`
+pub(crate) fn decode(value: &[u8], header: &[u8; HEADER_LENGTH]) -> Option<String> {
+    if value.len() < HEADER_LENGTH || value[..HEADER_LENGTH] != header[..] {
+        return None;
+    }
+
+    String::from_utf8(value[HEADER_LENGTH..].to_vec()).ok()
+}
+
+// files whose modification time can not be read are never cached
+fn header(meta: &Metadata) -> Option<[u8; HEADER_LENGTH]> {
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+    let mut header = [0; HEADER_LENGTH];
+    header[..8].copy_from_slice(&meta.len().to_le_bytes());
+    header[8..16].copy_from_slice(&modified.as_secs().to_le_bytes());
+    header[16..].copy_from_slice(&modified.subsec_nanos().to_le_bytes());
+    Some(header)
+}
+
+impl DupeFinder {
+    // the stored match key of `data`, read errors are logged and count as a miss
+    pub(crate) fn cached_hash(&self, cache: &HashCache, data: &DirData) -> Option<String> {
+        cache.get(&data.path, &data.meta).unwrap_or_else(|e| {
+            self.log(LogCategory::Hashing, Level::Warn, format_args!("Error reading hash cache for file: {}; error: {}", data.path.display(), e));
+            None
+        })
+    }
+
+    pub(crate) fn cache_hash(&self, cache: &HashCache, data: &DirData, hash: &str) {
+        if let Err(e) = cache.insert(&data.path, &data.meta, hash) {
+            self.log(LogCategory::Hashing, Level::Warn, format_args!("Error writing hash cache for file: {}; error: {}", data.path.display(), e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_get_checks_metadata() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("a.txt");
+        fs::write(&file, "12345").unwrap();
+        let cache = HashCache::open(&temp.path().join("cache"), String::from("test")).unwrap();
+
+        let meta = fs::metadata(&file).unwrap();
+        assert_eq!(cache.get(&file, &meta).unwrap(), None);
+        cache.insert(&file, &meta, "ABC").unwrap();
+        assert_eq!(cache.get(&file, &meta).unwrap(), Some(String::from("ABC")));
+
+        fs::write(&file, "123456").unwrap();
+        assert_eq!(cache.get(&file, &fs::metadata(&file).unwrap()).unwrap(), None, "changed file should miss");
+    }
+
+    #[test]
+    fn test_unchanged_files_are_not_hashed_again() {
+        let temp = tempfile::tempdir().unwrap();
+        let files = temp.path().join("files");
+        fs::create_dir(&files).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(files.join(name), "12345").unwrap();
+        }
+        let checker = || DupeFinder::builder([&files]).hash_cache(temp.path().join("cache")).build().unwrap();
+
+        let first = checker().run();
+        assert_eq!(first.stats().files_hashed, 3);
+
+        fs::write(files.join("c.txt"), "54321").unwrap();
+        // same size, the modification time alone has to reveal the change
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        fs::File::options().write(true).open(files.join("c.txt")).unwrap().set_modified(later).unwrap();
+        let second = checker().run();
+        assert_eq!(second.stats().files_hashed, 1, "only the changed file should be hashed");
+        assert_eq!(second.values().next().unwrap().entries.len(), 2);
+    }
+
+    #[test]
+    fn test_hashers_do_not_share_hashes() {
+        struct ConstantHasher;
+        impl crate::FileHasher for ConstantHasher {
+            fn hash_reader(&self, _reader: &mut dyn std::io::Read) -> Result<String, io::Error> {
+                Ok(String::from("SAME"))
+            }
+        }
+
+        let temp = tempfile::tempdir().unwrap();
+        let files = temp.path().join("files");
+        fs::create_dir(&files).unwrap();
+        fs::write(files.join("a.txt"), "12345").unwrap();
+        fs::write(files.join("b.txt"), "12345").unwrap();
+        fs::write(files.join("c.txt"), "54321").unwrap();
+        let cache = temp.path().join("cache");
+
+        let first = DupeFinder::builder([&files]).hash_cache(&cache).build().unwrap().run();
+        assert_eq!(first.values().next().unwrap().entries.len(), 2);
+
+        let other = DupeFinder::builder([&files]).hash_cache(&cache).hasher(ConstantHasher).build().unwrap().run();
+        assert_eq!(other.stats().files_hashed, 3, "hashes of another hasher should not be used");
+        assert_eq!(other.values().next().unwrap().entries.len(), 3);
+
+        let again = DupeFinder::builder([&files]).hash_cache(&cache).build().unwrap().run();
+        assert_eq!(again.stats().files_hashed, 0, "hashes of the same hasher should still be cached");
+        assert_eq!(again.values().next().unwrap().entries.len(), 2);
+    }
+}
@@ -0,0 +1,398 @@
+use std::{env, ffi::OsString, path::PathBuf, str::FromStr, time::{Duration, UNIX_EPOCH}};
+use crate::{ConfigError, DupeFinder, DupeFinderBuilder};
+
+// prefix of the environment variables read by `DupeFinderConfig::from_env`
+const ENV_PREFIX: &str = "DUPEFINDER_";
+
+/// Settings of a `DupeFinder` that can be kept in a config file or set through the environment
+///
+/// Every setting is optional, unset settings keep the `DupeFinderBuilder` default. Layers are
+/// combined with `merge`, where a later layer wins for every setting it holds. The documented
+/// order is: defaults, then the config file (`load`), then environment variables (`from_env`),
+/// then programmatic overrides such as command line flags. `resolve` combines the first three.
+///
+/// Environment variables are named after the fields, e.g. `DUPEFINDER_MAX_DEPTH=2`. Booleans
+/// accept `1` / `true` / `yes` and `0` / `false` / `no`, directory lists are separated like
+/// `PATH` and other lists by commas. Timeouts are in milliseconds, modification times in seconds
+/// since the unix epoch.
+///
+/// # Examples
+/// ```
+/// use dupefinder::DupeFinderConfig;
+///
+/// let overrides = DupeFinderConfig { recursive: Some(true), ..DupeFinderConfig::default() };
+/// let config = DupeFinderConfig::default()
+///     .merge(DupeFinderConfig::from_env().unwrap())
+///     .merge(overrides);
+///
+/// if let Ok(mut checker) = config.builder().build() {
+///     println!("{} duplicate sets", checker.run().len());
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct DupeFinderConfig {
+    /// Directories to search
+    pub directories: Option<Vec<PathBuf>>,
+    /// See `DupeFinderBuilder::recursive`
+    pub recursive: Option<bool>,
+    /// See `DupeFinderBuilder::max_depth`
+    pub max_depth: Option<usize>,
+    /// See `DupeFinderBuilder::same_filesystem`
+    pub same_filesystem: Option<bool>,
+    /// See `DupeFinderBuilder::reference_directories`
+    pub references: Option<Vec<PathBuf>>,
+    /// See `DupeFinderBuilder::include_extensions`
+    pub include_extensions: Option<Vec<String>>,
+    /// See `DupeFinderBuilder::exclude_extensions`
+    pub exclude_extensions: Option<Vec<String>>,
+    /// See `DupeFinderBuilder::exclude_globs`
+    pub exclude_globs: Option<Vec<String>>,
+    /// See `DupeFinderBuilder::skip_hidden`
+    pub skip_hidden: Option<bool>,
+    /// See `DupeFinderBuilder::modified_after`, in seconds since the unix epoch
+    pub modified_after: Option<u64>,
+    /// See `DupeFinderBuilder::modified_before`, in seconds since the unix epoch
+    pub modified_before: Option<u64>,
+    /// See `DupeFinderBuilder::partial_hash`
+    pub partial_hash: Option<u64>,
+    /// See `DupeFinderBuilder::verify_contents`
+    pub verify_contents: Option<bool>,
+    /// See `DupeFinderBuilder::incremental`
+    pub incremental: Option<bool>,
+    /// See `DupeFinderBuilder::strict`
+    pub strict: Option<bool>,
+    /// See `DupeFinderBuilder::soft_timeout`, in milliseconds
+    pub soft_timeout_ms: Option<u64>,
+    /// See `DupeFinderBuilder::hard_timeout`, in milliseconds
+    pub hard_timeout_ms: Option<u64>,
+    /// See `DupeFinderBuilder::retry_stale_handles`
+    pub retry_stale_handles: Option<u32>,
+    /// See `DupeFinderBuilder::device_error_limit`
+    pub device_error_limit: Option<usize>,
+    /// See `DupeFinderBuilder::max_files`
+    pub max_files: Option<usize>,
+    /// See `DupeFinderBuilder::max_bytes`
+    pub max_bytes: Option<u64>,
+    /// See `DupeFinderBuilder::max_groups`
+    pub max_groups: Option<usize>,
+    /// See `DupeFinderBuilder::min_copies`
+    pub min_copies: Option<usize>,
+    /// See `DupeFinderBuilder::min_group_waste_bytes`
+    pub min_group_waste_bytes: Option<u64>,
+    /// See `DupeFinderBuilder::max_read_rate`
+    pub max_read_rate: Option<u64>,
+    /// See `DupeFinderBuilder::parallel`, ignored without the `parallel` feature
+    pub threads: Option<usize>,
+    /// See `DupeFinderBuilder::hash_cache`, ignored without the `cache` feature
+    pub hash_cache: Option<PathBuf>,
+    /// See `DupeFinderBuilder::respect_gitignore`, ignored without the `gitignore` feature
+    pub respect_gitignore: Option<bool>,
+    /// See `DupeFinderBuilder::decompress`, ignored without the `decompress` feature
+    pub decompress: Option<bool>,
+}
+
+impl DupeFinderConfig {
+    /// Reads a JSON config file, unknown settings are rejected
+    #[cfg(feature = "serde")]
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<DupeFinderConfig, ConfigError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).map_err(|e| ConfigError::ConfigFile(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| ConfigError::ConfigFile(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Combines the defaults, the config file at `file` if any and the environment variables
+    #[cfg(feature = "serde")]
+    pub fn resolve<P: AsRef<std::path::Path>>(file: Option<P>) -> Result<DupeFinderConfig, ConfigError> {
+        let config = match file {
+            Some(file) => DupeFinderConfig::load(file)?,
+            None => DupeFinderConfig::default(),
+        };
+
+        Ok(config.merge(DupeFinderConfig::from_env()?))
+    }
+
+    /// Reads the settings set through `DUPEFINDER_*` environment variables
+    ///
+    /// Paths are taken as they are, other settings that are not valid UTF-8 are an `InvalidSetting`.
+    pub fn from_env() -> Result<DupeFinderConfig, ConfigError> {
+        DupeFinderConfig::from_vars(|name| env::var_os(name))
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<OsString>) -> Result<DupeFinderConfig, ConfigError> {
+        // paths are used as they are, every other setting has to be valid UTF-8
+        let path_setting = |name: &str| var(&format!("{}{}", ENV_PREFIX, name.to_uppercase()));
+        let setting = |name: &str| path_setting(name)
+            .map(|value| value.into_string().map_err(|value| ConfigError::InvalidSetting(format!("{}{}", ENV_PREFIX, name.to_uppercase()), value.to_string_lossy().into_owned())))
+            .transpose()
+            .map(|value| value.map(|value| (name.to_uppercase(), value)));
+
+        Ok(DupeFinderConfig {
+            directories: path_setting("directories").map(|value| env::split_paths(&value).collect()),
+            recursive: setting("recursive")?.map(parse_bool).transpose()?,
+            max_depth: setting("max_depth")?.map(parse).transpose()?,
+            same_filesystem: setting("same_filesystem")?.map(parse_bool).transpose()?,
+            references: path_setting("references").map(|value| env::split_paths(&value).collect()),
+            include_extensions: setting("include_extensions")?.map(|(_, value)| split_list(&value)),
+            exclude_extensions: setting("exclude_extensions")?.map(|(_, value)| split_list(&value)),
+            exclude_globs: setting("exclude_globs")?.map(|(_, value)| split_list(&value)),
+            skip_hidden: setting("skip_hidden")?.map(parse_bool).transpose()?,
+            modified_after: setting("modified_after")?.map(parse).transpose()?,
+            modified_before: setting("modified_before")?.map(parse).transpose()?,
+            partial_hash: setting("partial_hash")?.map(parse).transpose()?,
+            verify_contents: setting("verify_contents")?.map(parse_bool).transpose()?,
+            incremental: setting("incremental")?.map(parse_bool).transpose()?,
+            strict: setting("strict")?.map(parse_bool).transpose()?,
+            soft_timeout_ms: setting("soft_timeout_ms")?.map(parse).transpose()?,
+            hard_timeout_ms: setting("hard_timeout_ms")?.map(parse).transpose()?,
+            retry_stale_handles: setting("retry_stale_handles")?.map(parse).transpose()?,
+            device_error_limit: setting("device_error_limit")?.map(parse).transpose()?,
+            max_files: setting("max_files")?.map(parse).transpose()?,
+            max_bytes: setting("max_bytes")?.map(parse).transpose()?,
+            max_groups: setting("max_groups")?.map(parse).transpose()?,
+            min_copies: setting("min_copies")?.map(parse).transpose()?,
+            min_group_waste_bytes: setting("min_group_waste_bytes")?.map(parse).transpose()?,
+            max_read_rate: setting("max_read_rate")?.map(parse).transpose()?,
+            threads: setting("threads")?.map(parse).transpose()?,
+            hash_cache: path_setting("hash_cache").map(PathBuf::from),
+            respect_gitignore: setting("respect_gitignore")?.map(parse_bool).transpose()?,
+            decompress: setting("decompress")?.map(parse_bool).transpose()?,
+        })
+    }
+
+    /// Layers `overrides` on top of this configuration, settings held by `overrides` win
+    pub fn merge(self, overrides: DupeFinderConfig) -> DupeFinderConfig {
+        DupeFinderConfig {
+            directories: overrides.directories.or(self.directories),
+            recursive: overrides.recursive.or(self.recursive),
+            max_depth: overrides.max_depth.or(self.max_depth),
+            same_filesystem: overrides.same_filesystem.or(self.same_filesystem),
+            references: overrides.references.or(self.references),
+            include_extensions: overrides.include_extensions.or(self.include_extensions),
+            exclude_extensions: overrides.exclude_extensions.or(self.exclude_extensions),
+            exclude_globs: overrides.exclude_globs.or(self.exclude_globs),
+            skip_hidden: overrides.skip_hidden.or(self.skip_hidden),
+            modified_after: overrides.modified_after.or(self.modified_after),
+            modified_before: overrides.modified_before.or(self.modified_before),
+            partial_hash: overrides.partial_hash.or(self.partial_hash),
+            verify_contents: overrides.verify_contents.or(self.verify_contents),
+            incremental: overrides.incremental.or(self.incremental),
+            strict: overrides.strict.or(self.strict),
+            soft_timeout_ms: overrides.soft_timeout_ms.or(self.soft_timeout_ms),
+            hard_timeout_ms: overrides.hard_timeout_ms.or(self.hard_timeout_ms),
+            retry_stale_handles: overrides.retry_stale_handles.or(self.retry_stale_handles),
+            device_error_limit: overrides.device_error_limit.or(self.device_error_limit),
+            max_files: overrides.max_files.or(self.max_files),
+            max_bytes: overrides.max_bytes.or(self.max_bytes),
+            max_groups: overrides.max_groups.or(self.max_groups),
+            min_copies: overrides.min_copies.or(self.min_copies),
+            min_group_waste_bytes: overrides.min_group_waste_bytes.or(self.min_group_waste_bytes),
+            max_read_rate: overrides.max_read_rate.or(self.max_read_rate),
+            threads: overrides.threads.or(self.threads),
+            hash_cache: overrides.hash_cache.or(self.hash_cache),
+            respect_gitignore: overrides.respect_gitignore.or(self.respect_gitignore),
+            decompress: overrides.decompress.or(self.decompress),
+        }
+    }
+
+    /// A builder for the configured directories with every setting applied
+    pub fn builder(&self) -> DupeFinderBuilder {
+        self.apply(DupeFinder::builder(self.directories.iter().flatten()))
+    }
+
+    /// Applies every setting held by this configuration to `builder`, `directories` are left out
+    pub fn apply(&self, mut builder: DupeFinderBuilder) -> DupeFinderBuilder {
+        if let Some(recursive) = self.recursive {
+            builder = builder.recursive(recursive);
+        }
+        if let Some(depth) = self.max_depth {
+            builder = builder.max_depth(depth);
+        }
+        if let Some(same) = self.same_filesystem {
+            builder = builder.same_filesystem(same);
+        }
+        if let Some(references) = &self.references {
+            builder = builder.reference_directories(references);
+        }
+        if let Some(extensions) = &self.include_extensions {
+            builder = builder.include_extensions(extensions);
+        }
+        if let Some(extensions) = &self.exclude_extensions {
+            builder = builder.exclude_extensions(extensions);
+        }
+        if let Some(patterns) = &self.exclude_globs {
+            builder = builder.exclude_globs(patterns);
+        }
+        if let Some(skip) = self.skip_hidden {
+            builder = builder.skip_hidden(skip);
+        }
+        if let Some(seconds) = self.modified_after {
+            builder = builder.modified_after(UNIX_EPOCH + Duration::from_secs(seconds));
+        }
+        if let Some(seconds) = self.modified_before {
+            builder = builder.modified_before(UNIX_EPOCH + Duration::from_secs(seconds));
+        }
+        if let Some(bytes) = self.partial_hash {
+            builder = builder.partial_hash(bytes);
+        }
+        if let Some(verify) = self.verify_contents {
+            builder = builder.verify_contents(verify);
+        }
+        if let Some(incremental) = self.incremental {
+            builder = builder.incremental(incremental);
+        }
+        if let Some(strict) = self.strict {
+            builder = builder.strict(strict);
+        }
+        if let Some(millis) = self.soft_timeout_ms {
+            builder = builder.soft_timeout(Duration::from_millis(millis));
+        }
+        if let Some(millis) = self.hard_timeout_ms {
+            builder = builder.hard_timeout(Duration::from_millis(millis));
+        }
+        if let Some(retries) = self.retry_stale_handles {
+            builder = builder.retry_stale_handles(retries);
+        }
+        if let Some(limit) = self.device_error_limit {
+            builder = builder.device_error_limit(limit);
+        }
+        if let Some(files) = self.max_files {
+            builder = builder.max_files(files);
+        }
+        if let Some(bytes) = self.max_bytes {
+            builder = builder.max_bytes(bytes);
+        }
+        if let Some(groups) = self.max_groups {
+            builder = builder.max_groups(groups);
+        }
+        if let Some(copies) = self.min_copies {
+            builder = builder.min_copies(copies);
+        }
+        if let Some(bytes) = self.min_group_waste_bytes {
+            builder = builder.min_group_waste_bytes(bytes);
+        }
+        if let Some(rate) = self.max_read_rate {
+            builder = builder.max_read_rate(rate);
+        }
+        #[cfg(feature = "parallel")]
+        if let Some(threads) = self.threads {
+            builder = builder.parallel(threads);
+        }
+        #[cfg(feature = "cache")]
+        if let Some(path) = &self.hash_cache {
+            builder = builder.hash_cache(path);
+        }
+        #[cfg(feature = "gitignore")]
+        if let Some(respect) = self.respect_gitignore {
+            builder = builder.respect_gitignore(respect);
+        }
+        #[cfg(feature = "decompress")]
+        if let Some(decompress) = self.decompress {
+            builder = builder.decompress(decompress);
+        }
+
+        builder
+    }
+}
+
+fn parse<T: FromStr>((name, value): (String, String)) -> Result<T, ConfigError> {
+    value.trim().parse().map_err(|_| ConfigError::InvalidSetting(format!("{}{}", ENV_PREFIX, name), value))
+}
+
+fn parse_bool((name, value): (String, String)) -> Result<bool, ConfigError> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" => Ok(true),
+        "0" | "false" | "no" => Ok(false),
+        _ => Err(ConfigError::InvalidSetting(format!("{}{}", ENV_PREFIX, name), value)),
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::collections::HashMap;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = pairs.iter().map(|(name, value)| (name.to_string(), OsString::from(value))).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let config = DupeFinderConfig::from_vars(vars(&[
+            ("DUPEFINDER_RECURSIVE", "yes"),
+            ("DUPEFINDER_MAX_DEPTH", "2"),
+            ("DUPEFINDER_EXCLUDE_EXTENSIONS", "tmp, log"),
+        ])).unwrap();
+
+        assert_eq!(config.recursive, Some(true));
+        assert_eq!(config.max_depth, Some(2));
+        assert_eq!(config.exclude_extensions, Some(vec![String::from("tmp"), String::from("log")]));
+        assert_eq!(config.verify_contents, None);
+
+        let invalid = DupeFinderConfig::from_vars(vars(&[("DUPEFINDER_MAX_DEPTH", "deep")]));
+        assert_eq!(invalid, Err(ConfigError::InvalidSetting(String::from("DUPEFINDER_MAX_DEPTH"), String::from("deep"))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_vars_invalid_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+        let invalid = OsString::from_vec(b"/data/a\xff".to_vec());
+        let mut joined = invalid.clone();
+        joined.push(":/backup");
+
+        let config = DupeFinderConfig::from_vars(|name| match name {
+            "DUPEFINDER_DIRECTORIES" => Some(joined.clone()),
+            "DUPEFINDER_HASH_CACHE" => Some(invalid.clone()),
+            _ => None,
+        }).unwrap();
+        assert_eq!(config.directories, Some(vec![PathBuf::from(invalid.clone()), PathBuf::from("/backup")]));
+        assert_eq!(config.hash_cache, Some(PathBuf::from(invalid.clone())));
+
+        let config = DupeFinderConfig::from_vars(|name| (name == "DUPEFINDER_EXCLUDE_GLOBS").then(|| invalid.clone()));
+        assert_eq!(config, Err(ConfigError::InvalidSetting(String::from("DUPEFINDER_EXCLUDE_GLOBS"), String::from("/data/a\u{FFFD}"))));
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let file = DupeFinderConfig { recursive: Some(false), max_depth: Some(1), ..DupeFinderConfig::default() };
+        let env = DupeFinderConfig { recursive: Some(true), ..DupeFinderConfig::default() };
+        let overrides = DupeFinderConfig { max_depth: Some(3), ..DupeFinderConfig::default() };
+
+        let config = DupeFinderConfig::default().merge(file).merge(env).merge(overrides);
+        assert_eq!(config.recursive, Some(true));
+        assert_eq!(config.max_depth, Some(3));
+        assert_eq!(config.skip_hidden, None);
+    }
+
+    #[test]
+    fn test_builder() {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let config = DupeFinderConfig { directories: Some(vec![path]), recursive: Some(true), ..DupeFinderConfig::default() };
+        assert_eq!(config.builder().build().unwrap().run().len(), 1);
+
+        assert!(DupeFinderConfig::default().builder().build().is_err(), "no directories should fail");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_load() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("dupefinder.json");
+        std::fs::write(&file, r#"{"recursive": true, "exclude_globs": ["*.tmp"]}"#).unwrap();
+
+        let config = DupeFinderConfig::load(&file).unwrap();
+        assert_eq!(config.recursive, Some(true));
+        assert_eq!(config.exclude_globs, Some(vec![String::from("*.tmp")]));
+
+        std::fs::write(&file, r#"{"recursiv": true}"#).unwrap();
+        assert!(matches!(DupeFinderConfig::load(&file), Err(ConfigError::ConfigFile(_))), "unknown settings should fail");
+    }
+}
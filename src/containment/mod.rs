@@ -0,0 +1,110 @@
+use std::{collections::HashSet, path::{Path, PathBuf}};
+use crate::{reference::is_reference, DupeFinder};
+
+/// Outcome of a containment check, see `DupeFinder::contains`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContainmentReport {
+    /// Number of files checked in the contained tree
+    pub files: usize,
+    /// Files of the contained tree paired with a copy found in the containing tree, sorted by path
+    pub copies: Vec<(PathBuf, PathBuf)>,
+    /// Files of the contained tree without a copy in the containing tree, sorted by path
+    pub missing: Vec<PathBuf>,
+}
+
+impl ContainmentReport {
+    /// True when every checked file has a copy
+    pub fn is_contained(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl DupeFinder {
+    /// Checks whether every file in `tree_a`, by content, also exists somewhere in `tree_b`
+    ///
+    /// Both trees are searched recursively. Empty files are not checked, files that could
+    /// not be read are reported as missing.
+    ///
+    /// # Examples
+    /// ```
+    /// let report = dupefinder::DupeFinder::contains("./resources/dupes", "./resources/dupes_directories");
+    /// if report.is_contained() {
+    ///     println!("all {} files have a copy", report.files);
+    /// } else {
+    ///     println!("{} files have no copy", report.missing.len());
+    /// }
+    /// ```
+    pub fn contains<P: AsRef<Path>, Q: AsRef<Path>>(tree_a: P, tree_b: Q) -> ContainmentReport {
+        let mut checker = DupeFinder::with_reference([tree_b], [tree_a]);
+        checker.options.follow_subdirs = true;
+        checker.run_containment()
+    }
+
+    /// Checks whether every file in the target directories has a copy in the reference
+    /// directories (`DupeFinderBuilder::reference_directories`), all other options apply as for `run`
+    pub fn run_containment(&mut self) -> ContainmentReport {
+        let results = self.run();
+
+        let mut copies: Vec<(PathBuf, PathBuf)> = Vec::new();
+        for duplicate in results.values() {
+            if let Some(reference) = results.references(&duplicate.hash).first() {
+                copies.extend(duplicate.paths().map(|file| (file.to_path_buf(), reference.clone())));
+            }
+        }
+        copies.sort();
+
+        let copied: HashSet<&Path> = copies.iter().map(|(file, _)| file.as_path()).collect();
+        let mut files: usize = 0;
+        let mut missing: Vec<PathBuf> = Vec::new();
+        for data in self.file_sizes.values().flatten() {
+            if is_reference(&self.reference_dirs, &data.path) {
+                continue;
+            }
+
+            files += 1;
+            if !copied.contains(data.path.as_path()) {
+                missing.push(data.path.clone());
+            }
+        }
+        missing.sort();
+
+        ContainmentReport { files, copies, missing }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let dupes: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let directories: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+
+        let report = DupeFinder::contains(&dupes, &directories);
+        assert!(report.is_contained(), "every file should have a copy");
+        assert_eq!(report.files, 2);
+        assert_eq!(report.copies.len(), 2);
+        assert_eq!(report.copies[0].0, dupes.join("a.txt"));
+        assert!(report.copies[0].1.starts_with(&directories), "copy should be in the containing tree");
+    }
+
+    #[test]
+    fn test_contains_missing() {
+        let temp = tempfile::tempdir().unwrap();
+        let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+        std::fs::create_dir_all(a.join("nested")).unwrap();
+        std::fs::create_dir_all(b.join("elsewhere")).unwrap();
+        std::fs::write(a.join("kept.txt"), "kept").unwrap();
+        std::fs::write(a.join("nested").join("only_a.txt"), "only in a").unwrap();
+        std::fs::write(a.join("nested").join("copy.txt"), "only in a").unwrap();
+        std::fs::write(b.join("elsewhere").join("kept.txt"), "kept").unwrap();
+
+        let report = DupeFinder::contains(&a, &b);
+        assert!(!report.is_contained(), "files should be missing");
+        assert_eq!(report.files, 3);
+        assert_eq!(report.copies, vec![(a.join("kept.txt"), b.join("elsewhere").join("kept.txt"))]);
+        assert_eq!(report.missing, vec![a.join("nested").join("copy.txt"), a.join("nested").join("only_a.txt")]);
+    }
+}
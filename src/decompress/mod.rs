@@ -0,0 +1,110 @@
+use std::{fs::{File, Metadata}, io::{self, BufReader, Read}, path::Path};
+use crate::{FileHasher, MatchStrategy};
+
+// compression formats told apart by file extension
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    fn from_path(path: &Path) -> Option<Compression> {
+        match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+}
+
+/// Matches files by their decompressed contents, so `report.log` and `report.log.gz` holding the
+/// same text are duplicates. Built with the `decompress` feature, see `DupeFinderBuilder::decompress`.
+///
+/// Files ending in `.gz`, `.zst` or `.xz` are decompressed while they are hashed, all other files are
+/// hashed as they are. As the decompressed size is only known after reading the file, every
+/// compressed file is decompressed once while traversing and once more if it has a candidate.
+/// `Duplicate::size` and the byte budget still use the size on disk, and `verify_contents` compares
+/// the files as stored, so it drops sets mixing compressed and plain files.
+#[derive(Clone, Copy, Default)]
+pub struct DecompressStrategy;
+
+impl MatchStrategy for DecompressStrategy {
+    fn candidate_key(&self, path: &Path, meta: &Metadata) -> Option<u64> {
+        if Compression::from_path(path).is_none() {
+            return Some(meta.len());
+        }
+
+        // a file that does not decompress keeps its size on disk, hashing it reports the error
+        let decompressed = open(path).and_then(|mut reader| io::copy(&mut reader, &mut io::sink()));
+        Some(decompressed.unwrap_or(meta.len()))
+    }
+
+    fn match_key(&self, path: &Path, hasher: &dyn FileHasher) -> Result<String, io::Error> {
+        hasher.hash_reader(&mut open(path)?)
+    }
+
+    fn prefix_key(&self, path: &Path, bytes: u64, hasher: &dyn FileHasher) -> Result<String, io::Error> {
+        hasher.hash_reader(&mut open(path)?.take(bytes))
+    }
+}
+
+// opens `path`, decompressing its contents when the extension names a supported format
+fn open(path: &Path) -> Result<Box<dyn Read>, io::Error> {
+    let file = BufReader::new(File::open(path)?);
+
+    Ok(match Compression::from_path(path) {
+        Some(Compression::Gzip) => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        Some(Compression::Zstd) => Box::new(ruzstd::decoding::StreamingDecoder::new(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?),
+        Some(Compression::Xz) => Box::new(lzma_rust2::XzReader::new(file, true)),
+        None => Box::new(file),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Xxh3Hasher;
+
+    fn resource(name: &str) -> std::path::PathBuf {
+        [env!("CARGO_MANIFEST_DIR"), "resources", "compressed", name].iter().collect()
+    }
+
+    #[test]
+    fn test_compression_from_path() {
+        assert_eq!(Compression::from_path(Path::new("report.log.GZ")), Some(Compression::Gzip));
+        assert_eq!(Compression::from_path(Path::new("report.log.zst")), Some(Compression::Zstd));
+        assert_eq!(Compression::from_path(Path::new("report.log.xz")), Some(Compression::Xz));
+        assert_eq!(Compression::from_path(Path::new("report.log")), None);
+    }
+
+    #[test]
+    fn test_decompressed_keys() {
+        let plain = resource("report.log");
+        let meta = std::fs::metadata(&plain).unwrap();
+        let hash = DecompressStrategy.match_key(&plain, &Xxh3Hasher).unwrap();
+        let prefix = DecompressStrategy.prefix_key(&plain, 64, &Xxh3Hasher).unwrap();
+
+        for name in ["report.log.gz", "report.log.zst", "report.log.xz"] {
+            let path = resource(name);
+            let compressed = std::fs::metadata(&path).unwrap();
+            assert_eq!(DecompressStrategy.candidate_key(&path, &compressed), Some(meta.len()), "{} should be keyed by its decompressed size", name);
+            assert_eq!(DecompressStrategy.match_key(&path, &Xxh3Hasher).unwrap(), hash, "{} should hash its decompressed contents", name);
+            assert_eq!(DecompressStrategy.prefix_key(&path, 64, &Xxh3Hasher).unwrap(), prefix, "{} should hash its decompressed prefix", name);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("broken.gz");
+        std::fs::write(&path, "not compressed").unwrap();
+        let meta = std::fs::metadata(&path).unwrap();
+
+        assert_eq!(DecompressStrategy.candidate_key(&path, &meta), Some(meta.len()));
+        assert!(DecompressStrategy.match_key(&path, &Xxh3Hasher).is_err());
+    }
+}
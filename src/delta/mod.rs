@@ -0,0 +1,232 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, fs::File, io::{self, BufReader, Read}, path::Path};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Estimated difference between two files, measured the way rsync would transfer them
+pub struct DeltaStats {
+    /// Block size in bytes used for matching
+    pub block_size: usize,
+    /// Size of the source file in bytes
+    pub source_size: u64,
+    /// Size of the target file in bytes
+    pub target_size: u64,
+    /// Bytes of the target found as whole blocks of the source, at any offset
+    pub matched_bytes: u64,
+    /// Bytes of the target that do not appear in the source and would have to be stored
+    pub literal_bytes: u64,
+}
+
+impl DeltaStats {
+    /// Share of the target (0.0 - 1.0) that would have to be stored to rebuild it from the source
+    pub fn delta_ratio(&self) -> f64 {
+        if self.target_size == 0 {
+            return 0.0;
+        }
+
+        self.literal_bytes as f64 / self.target_size as f64
+    }
+}
+
+/// Estimates how many bytes of `target` differ from `source`
+///
+/// The source is split into `block_size` blocks, the target is then scanned with a rolling
+/// checksum so blocks shifted by insertions or deletions are still found. Useful to decide
+/// whether two near-duplicate files are worth keeping separately.
+///
+/// # Examples
+/// ```
+/// if let Ok(delta) = dupefinder::estimate_delta("./old.bin", "./new.bin", 4096) {
+///     println!("{} of {} bytes differ", delta.literal_bytes, delta.target_size);
+/// };
+/// ```
+pub fn estimate_delta<P: AsRef<Path>, Q: AsRef<Path>>(source: P, target: Q, block_size: usize) -> Result<DeltaStats, io::Error> {
+    if block_size == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "block size must be greater than zero"));
+    }
+
+    let (blocks, source_size) = source_blocks(source.as_ref(), block_size)?;
+    let mut target = BufReader::new(File::open(target)?).bytes();
+
+    let mut stats = DeltaStats { block_size, source_size, target_size: 0, matched_bytes: 0, literal_bytes: 0 };
+    let mut window: VecDeque<u8> = VecDeque::with_capacity(block_size);
+
+    loop {
+        // (re)fill the window after a match or at the start
+        while window.len() < block_size {
+            match target.next() {
+                Some(byte) => {
+                    let byte = byte?;
+                    stats.target_size += 1;
+                    window.push_back(byte);
+                },
+                None => break,
+            }
+        }
+
+        if window.len() < block_size {
+            // trailing partial block can not match a whole source block
+            stats.literal_bytes += window.len() as u64;
+            return Ok(stats);
+        }
+
+        let mut checksum = RollingChecksum::new(window.make_contiguous());
+
+        loop {
+            let matched = blocks.get(&checksum.value())
+                .is_some_and(|strong| strong.contains(&xxh3_64(window.make_contiguous())));
+
+            if matched {
+                stats.matched_bytes += block_size as u64;
+                window.clear();
+                break;
+            }
+
+            let next = match target.next() {
+                Some(byte) => byte?,
+                None => {
+                    stats.literal_bytes += window.len() as u64;
+                    return Ok(stats);
+                }
+            };
+            stats.target_size += 1;
+
+            let outgoing = window.pop_front().unwrap_or_default();
+            stats.literal_bytes += 1;
+            window.push_back(next);
+            checksum.roll(outgoing, next, block_size);
+        }
+    }
+}
+
+// weak rolling checksum -> strong hashes of all whole source blocks, and the source size
+fn source_blocks(path: &Path, block_size: usize) -> Result<(HashMap<u32, HashSet<u64>>, u64), io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut blocks: HashMap<u32, HashSet<u64>> = HashMap::new();
+    let mut buffer = vec![0u8; block_size];
+    let mut size: u64 = 0;
+
+    loop {
+        let read = read_full(&mut reader, &mut buffer)?;
+        size += read as u64;
+        if read < block_size {
+            break;
+        }
+
+        let weak = RollingChecksum::new(&buffer).value();
+        blocks.entry(weak).or_default().insert(xxh3_64(&buffer));
+    }
+
+    Ok((blocks, size))
+}
+
+// reads until `buffer` is full or the reader is exhausted
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<usize, io::Error> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(filled)
+}
+
+// rsync style weak checksum that can be rolled forward one byte at a time
+#[derive(Default)]
+struct RollingChecksum {
+    a: u32,
+    b: u32,
+}
+
+impl RollingChecksum {
+    fn new(data: &[u8]) -> RollingChecksum {
+        let mut checksum = RollingChecksum::default();
+        let len = data.len() as u32;
+        for (i, byte) in data.iter().enumerate() {
+            checksum.a = checksum.a.wrapping_add(*byte as u32);
+            checksum.b = checksum.b.wrapping_add((len - i as u32).wrapping_mul(*byte as u32));
+        }
+
+        checksum
+    }
+
+    fn roll(&mut self, outgoing: u8, incoming: u8, block_size: usize) {
+        self.a = self.a.wrapping_sub(outgoing as u32).wrapping_add(incoming as u32);
+        self.b = self.b.wrapping_sub((block_size as u32).wrapping_mul(outgoing as u32)).wrapping_add(self.a);
+    }
+
+    fn value(&self) -> u32 {
+        (self.a & 0xffff) | (self.b << 16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| ((i * 7 + i / 256) % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_estimate_delta_identical() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let delta = estimate_delta(&path, &path, 16).unwrap();
+
+        assert_eq!(delta.source_size, 100);
+        assert_eq!(delta.target_size, 100);
+        assert_eq!(delta.matched_bytes, 96);
+        assert_eq!(delta.literal_bytes, 4);
+    }
+
+    #[test]
+    fn test_estimate_delta_shifted() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("source.bin");
+        let target = temp.path().join("target.bin");
+        let data = pattern(4096);
+        let mut shifted = b"inserted!!".to_vec();
+        shifted.extend_from_slice(&data);
+        std::fs::write(&source, &data).unwrap();
+        std::fs::write(&target, &shifted).unwrap();
+
+        let delta = estimate_delta(&source, &target, 512).unwrap();
+        assert_eq!(delta.target_size, 4106);
+        assert_eq!(delta.matched_bytes, 4096);
+        assert_eq!(delta.literal_bytes, 10);
+        assert!(delta.delta_ratio() < 0.01);
+    }
+
+    #[test]
+    fn test_estimate_delta_different() {
+        let source: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let target: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "images", "pixels.png"].iter().collect();
+        let delta = estimate_delta(&source, &target, 8).unwrap();
+
+        assert_eq!(delta.matched_bytes, 0);
+        assert_eq!(delta.literal_bytes, 165);
+        assert_eq!(delta.delta_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_rolling_checksum_matches_fresh() {
+        let data = pattern(64);
+        let mut checksum = RollingChecksum::new(&data[0..16]);
+        for i in 0..48 {
+            checksum.roll(data[i], data[i + 16], 16);
+            assert_eq!(checksum.value(), RollingChecksum::new(&data[i + 1..i + 17]).value());
+        }
+    }
+
+    #[test]
+    fn test_estimate_delta_errors() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let missing: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "doesnotexist.txt"].iter().collect();
+
+        assert!(estimate_delta(&path, &path, 0).is_err(), "zero block size should fail");
+        assert!(estimate_delta(&path, &missing, 16).is_err(), "io error expected");
+    }
+}
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, fs::{Metadata, DirEntry, self}, io};
+use std::{path::{Path, PathBuf}, fs::{Metadata, DirEntry, self}, io};
 
 // convenience struct for holding unwrapped data
 #[derive(Clone)]
@@ -9,20 +9,20 @@ pub struct DirData {
 }
 
 impl DirData {
-    pub fn new(path: Result<DirEntry, std::io::Error>) -> Result<DirData, Box<dyn std::error::Error>> {
+    pub fn new(path: Result<DirEntry, io::Error>) -> Result<DirData, io::Error> {
         let path_data = path?;
         let meta_data = path_data.metadata()?;
         let size = meta_data.len();
         
-        Ok(DirData{path: path_data.path(), meta: meta_data, size: size})
+        Ok(DirData{path: path_data.path(), meta: meta_data, size})
     }
 
-    pub fn new_from_path(path: String) -> Result<DirData, io::Error> {
-        let path_buf: PathBuf = path.clone().into();
-        let meta_data = fs::metadata(path)?;
+    pub fn new_from_path<P: AsRef<Path>>(path: P) -> Result<DirData, io::Error> {
+        let path_buf: PathBuf = path.as_ref().to_path_buf();
+        let meta_data = fs::metadata(path_buf.as_path())?;
         let size = meta_data.len();
 
-        Ok(DirData { path: path_buf, meta: meta_data, size: size })
+        Ok(DirData { path: path_buf, meta: meta_data, size })
     }
 }
 
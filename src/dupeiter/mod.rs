@@ -0,0 +1,109 @@
+use std::collections::{HashMap, VecDeque};
+use crate::{dirdata::DirData, DupeFinder, Duplicate};
+
+/// Iterator over duplicate sets returned by `DupeFinder::run_iter`
+///
+/// Directories are traversed on the first call to `next()`, after that same-size
+/// groups are hashed one at a time and every duplicate set is yielded, with all of
+/// its files (target files only with reference directories), as soon as the group it
+/// belongs to has been hashed.
+pub struct DupeIter<'a> {
+    finder: &'a mut DupeFinder,
+    sizes: Option<std::vec::IntoIter<u64>>,
+    pending: VecDeque<Duplicate>,
+}
+
+impl<'a> DupeIter<'a> {
+    pub(crate) fn new(finder: &'a mut DupeFinder) -> DupeIter<'a> {
+        DupeIter {
+            finder,
+            sizes: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    // hashes a single same-size group and queues any duplicate sets found
+    fn check_size(&mut self, size: u64) {
+        let finder: &DupeFinder = self.finder;
+        let paths = match finder.file_sizes.get(&size) {
+            Some(paths) => paths,
+            None => return,
+        };
+
+        let mut results: HashMap<String, Duplicate> = HashMap::new();
+        for paths in finder.prefilter(vec![paths], &mut Vec::new()) {
+            let candidates: Vec<&DirData> = paths.iter().skip(finder.skipped_entries()).collect();
+            let hashes = finder.hash_candidates(&candidates);
+            finder.check_path_duplicates(&paths, hashes, &mut results);
+        }
+        self.pending.extend(results.into_values().filter_map(|duplicate| finder.split_references(duplicate)).map(|(duplicate, _)| duplicate));
+    }
+}
+
+impl Iterator for DupeIter<'_> {
+    type Item = Duplicate;
+
+    fn next(&mut self) -> Option<Duplicate> {
+        if self.sizes.is_none() {
+            self.finder.initialize();
+            self.finder.build_directories();
+            self.sizes = Some(self.finder.duplicate_keys().into_iter());
+        }
+
+        loop {
+            if let Some(duplicate) = self.pending.pop_front() {
+                return Some(duplicate);
+            }
+
+            let size = self.sizes.as_mut()?.next()?;
+            self.check_size(size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_run_iter_matches_run() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let mut checker = DupeFinder::new_recursive([&path]);
+        let expected = checker.run();
+
+        let results: Vec<Duplicate> = checker.run_iter().collect();
+        assert_eq!(results.len(), expected.len());
+        for duplicate in results.iter() {
+            assert_eq!(duplicate.entries, expected[&duplicate.hash].entries);
+        }
+    }
+
+    #[test]
+    fn test_run_iter_lazy() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinder::new([&path]);
+
+        let mut iter = checker.run_iter();
+        assert!(iter.finder.checked_directories.is_empty(), "nothing should be traversed before next()");
+
+        let first = iter.next();
+        assert!(first.is_some(), "duplicate expected");
+        assert_eq!(first.unwrap().entries.len(), 2);
+        assert!(iter.next().is_none(), "single duplicate expected");
+    }
+
+    #[test]
+    fn test_run_iter_order() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+
+        let mut checker = DupeFinder::new_recursive([&path]);
+        let sizes: Vec<u64> = checker.run_iter().map(|duplicate| duplicate.size).collect();
+        assert!(sizes.len() > 1, "several sets expected");
+        assert!(sizes.windows(2).all(|pair| pair[0] >= pair[1]), "largest sets expected first");
+
+        let mut checker = DupeFinder::builder([&path]).recursive(true).hash_order(crate::HashOrder::SmallestFirst).build().unwrap();
+        let sizes: Vec<u64> = checker.run_iter().map(|duplicate| duplicate.size).collect();
+        assert!(sizes.windows(2).all(|pair| pair[0] <= pair[1]), "smallest sets expected first");
+    }
+}
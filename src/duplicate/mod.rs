@@ -1,27 +1,167 @@
+use std::{fmt, fs::Metadata, path::{Path, PathBuf}, time::SystemTime};
+use log::warn;
+use crate::{preview::{Preview, PreviewOptions}, LogCategory};
+#[cfg(feature = "exif")]
+use crate::imagemeta::{compare_exif, ExifDifference};
+
+/// How far the files of a set were compared, ordered from weakest to strongest
+///
+/// # Examples
+/// ```
+/// use dupefinder::Confidence;
+///
+/// let mut checker = dupefinder::DupeFinder::new(vec!["./resources"]);
+/// for duplicate in checker.run().values().filter(|duplicate| duplicate.confidence >= Confidence::FullHash) {
+///     println!("safe to act on: {:?}", duplicate.paths().collect::<Vec<_>>());
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Confidence {
+    /// Only the sizes (or the candidate keys of the strategy) matched
+    SizeOnly,
+    /// The leading bytes of the files hashed the same
+    PrefixHash,
+    /// The full contents hashed the same
+    #[default]
+    FullHash,
+    /// The contents were compared byte by byte
+    ByteVerified,
+}
+
+/// A file of a duplicate set with the metadata read while scanning, so deciding which copy
+/// to keep does not need to read it again
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DupeEntry {
+    /// Full path of the file
+    pub path: PathBuf,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Last modification time, `None` where the platform does not provide it
+    pub modified: Option<SystemTime>,
+    /// (device, inode) of the file, `None` outside of unix
+    pub file_id: Option<(u64, u64)>,
+}
+
+impl DupeEntry {
+    pub(crate) fn new(path: PathBuf, meta: &Metadata) -> DupeEntry {
+        DupeEntry { path, size: meta.len(), modified: meta.modified().ok(), file_id: file_id(meta) }
+    }
+
+    // an entry that is not a file on disk, such as a mail attachment or an archive member
+    #[cfg(any(feature = "mail", feature = "zip", feature = "tar"))]
+    pub(crate) fn detached(path: PathBuf, size: u64) -> DupeEntry {
+        DupeEntry { path, size, modified: None, file_id: None }
+    }
+}
+
 /// Holds information about a specific set of duplicate files
+///
+/// Displays as a summary line followed by one indented line per file.
+///
+/// # Examples
+/// ```
+/// let mut checker = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+/// for duplicate in checker.run().values() {
+///     println!("{}", duplicate);
+/// }
+/// ```
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Duplicate {
     /// File contents hash that match occurred on
     pub hash: String,
-    /// List of all full file paths that share the hash
-    pub files: Vec<String>,
+    /// Every file that shares the hash
+    pub entries: Vec<DupeEntry>,
     /// Size of the files in bytes
     pub size: u64,
+    /// How far the files were compared, see `MatchStrategy::confidence` and `DupeFinderBuilder::verify_contents`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub confidence: Confidence,
 }
 
 impl Clone for Duplicate {
     fn clone(&self) -> Duplicate {
         let hash = self.hash.clone();
-        let files = self.files.clone();
-        let size = self.size.clone();
+        let entries = self.entries.clone();
+        let size = self.size;
+        let confidence = self.confidence;
 
         Duplicate { 
-            hash: hash, 
-            files: files, 
-            size: size,
+            hash, 
+            entries, 
+            size,
+            confidence,
         }
     }
 }
 
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} files of size {} bytes found with hash {}", self.entries.len(), self.size, self.hash)?;
+        for file in self.paths() {
+            write!(f, "\n  {}", file.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Duplicate {
+    /// The full paths of the files in the set
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().map(|entry| entry.path.as_path())
+    }
+
+    // whether `path` is one of the files in the set
+    pub(crate) fn contains(&self, path: &Path) -> bool {
+        self.entries.iter().any(|entry| entry.path == path)
+    }
+
+    /// Extracts a `Preview` for every file in the set, files that cannot be read are skipped
+    pub fn previews(&self, options: &PreviewOptions) -> Vec<Preview> {
+        let mut previews: Vec<Preview> = Vec::new();
+
+        for file in self.paths() {
+            match Preview::new(file, options) {
+                Ok(preview) => previews.push(preview),
+                Err(e) => {
+                    warn!(target: LogCategory::Metadata.target(), "Error generating preview for file: {}; error: {}", file.display(), e);
+                    continue;
+                }
+            }
+        }
+
+        previews
+    }
+
+    /// Lists the EXIF fields (capture date, camera, GPS, rating) that differ between the files
+    #[cfg(feature = "exif")]
+    pub fn exif_differences(&self) -> Vec<ExifDifference> {
+        compare_exif(&self.paths().collect::<Vec<&Path>>())
+    }
+}
+
+// (device, inode) of any file
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+// file indexes are not exposed by stable std outside of unix
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+// entries for made up paths, without metadata
+#[cfg(test)]
+pub(crate) fn entries<P: AsRef<Path>>(paths: &[P]) -> Vec<DupeEntry> {
+    paths.iter().map(|path| DupeEntry { path: path.as_ref().to_path_buf(), size: 0, modified: None, file_id: None }).collect()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -29,32 +169,52 @@ mod tests {
 
     #[test]
     fn test_clone_same() {
-        let original: Duplicate = Duplicate{hash: String::from("12345"), files: vec![String::from("first"), String::from("second")], size: 542};
+        let original: Duplicate = Duplicate{hash: String::from("12345"), entries: entries(&["first", "second"]), size: 542, confidence: Confidence::FullHash};
         let duplicate = original.clone();
 
         assert_eq!(original.hash, duplicate.hash);
         assert_eq!(original.size, duplicate.size);
-        assert_eq!(original.files, duplicate.files);
+        assert_eq!(original.entries, duplicate.entries);
     }
 
     #[test]
     fn test_clone_changed() {
-        let original: Duplicate = Duplicate{hash: String::from("12345"), files: vec![String::from("first"), String::from("second")], size: 542};
+        let original: Duplicate = Duplicate{hash: String::from("12345"), entries: entries(&["first", "second"]), size: 542, confidence: Confidence::FullHash};
         let mut duplicate = original.clone();
 
         assert_eq!(original.hash, duplicate.hash);
         assert_eq!(original.size, duplicate.size);
-        assert_eq!(original.files, duplicate.files);
+        assert_eq!(original.entries, duplicate.entries);
 
         duplicate.size = 539;
         duplicate.hash = String::from("54321");
-        duplicate.files.remove(1);
+        duplicate.entries.remove(1);
 
         assert_ne!(original.hash, duplicate.hash);
         assert_ne!(original.size, duplicate.size);
-        assert_ne!(original.files, duplicate.files);
-        assert_eq!(original.files.len(), 2);
-        assert_eq!(duplicate.files.len(), 1);
+        assert_ne!(original.entries, duplicate.entries);
+        assert_eq!(original.entries.len(), 2);
+        assert_eq!(duplicate.entries.len(), 1);
+
+    }
+
+    #[test]
+    fn test_display() {
+        let duplicate: Duplicate = Duplicate{hash: String::from("12345"), entries: entries(&["first", "second"]), size: 542, confidence: Confidence::FullHash};
+
+        assert_eq!(duplicate.to_string(), "2 files of size 542 bytes found with hash 12345\n  first\n  second");
+        assert_eq!(duplicate, duplicate.clone());
+    }
+
+    #[test]
+    fn test_previews_skip_missing() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let missing: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "doesnotexist.txt"].iter().collect();
+        let duplicate: Duplicate = Duplicate{hash: String::from("12345"), entries: entries(&[&path, &missing]), size: 100, confidence: Confidence::FullHash};
 
+        let previews = duplicate.previews(&PreviewOptions::default());
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].path, path);
+        assert!(previews[0].text.is_some(), "text preview expected");
     }
 }
\ No newline at end of file
@@ -0,0 +1,118 @@
+use std::{io, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, Ordering}, Mutex, MutexGuard}};
+use crate::{ConfigError, LogCategory};
+
+/// Errors returned by `DupeFinder`, telling apart the part of the scan that failed
+#[derive(Debug, thiserror::Error)]
+pub enum DupeFinderError {
+    /// A directory could not be read
+    #[error("unable to read directory {}: {source}", path.display())]
+    Traversal { path: PathBuf, source: io::Error },
+    /// The metadata of a file could not be read, or the file can not be searched for
+    #[error("unable to read metadata of {}: {source}", path.display())]
+    Metadata { path: PathBuf, source: io::Error },
+    /// The contents of a file could not be hashed or compared
+    #[error("unable to hash {}: {source}", path.display())]
+    Hashing { path: PathBuf, source: io::Error },
+    /// The configuration is invalid
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+impl DupeFinderError {
+    /// The file or directory the error occurred on, `None` for configuration errors
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            DupeFinderError::Traversal { path, .. } | DupeFinderError::Metadata { path, .. } | DupeFinderError::Hashing { path, .. } => Some(path),
+            DupeFinderError::Config(_) => None,
+        }
+    }
+}
+
+impl From<ScanError> for DupeFinderError {
+    fn from(error: ScanError) -> DupeFinderError {
+        let source = io::Error::new(error.kind, error.message);
+        match error.phase {
+            LogCategory::Traversal => DupeFinderError::Traversal { path: error.path, source },
+            LogCategory::Metadata => DupeFinderError::Metadata { path: error.path, source },
+            LogCategory::Hashing | LogCategory::Actions => DupeFinderError::Hashing { path: error.path, source },
+        }
+    }
+}
+
+/// A file or directory that could not be read during a scan, see `ScanResults::errors`
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[error("{}: {message}", path.display())]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: io::ErrorKind,
+    /// Part of the scan the error occurred in
+    pub phase: LogCategory,
+    pub message: String,
+}
+
+impl ScanError {
+    pub(crate) fn new(path: &Path, phase: LogCategory, error: &io::Error) -> ScanError {
+        ScanError { path: path.to_path_buf(), kind: error.kind(), phase, message: error.to_string() }
+    }
+}
+
+// Errors of the current run, shared with hashing threads
+#[derive(Default)]
+pub struct ErrorList {
+    errors: Mutex<Vec<ScanError>>,
+    // set by strict runs on the first error, remaining work is given up
+    stopped: AtomicBool,
+}
+
+impl ErrorList {
+    pub fn push(&self, path: &Path, phase: LogCategory, error: &io::Error) {
+        self.lock().push(ScanError::new(path, phase, error));
+    }
+
+    pub fn errors(&self) -> Vec<ScanError> {
+        self.lock().clone()
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+        self.stopped.store(false, Ordering::Relaxed);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<ScanError>> {
+        self.errors.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_error_list() {
+        let list = ErrorList::default();
+        list.push(Path::new("a.txt"), LogCategory::Hashing, &io::Error::from(io::ErrorKind::PermissionDenied));
+        list.push(Path::new("b.txt"), LogCategory::Metadata, &io::Error::other("not an io error"));
+
+        let errors = list.errors();
+        assert_eq!(errors[0].kind, io::ErrorKind::PermissionDenied);
+        assert_eq!(errors[0].phase, LogCategory::Hashing);
+        assert_eq!(errors[1].kind, io::ErrorKind::Other);
+        assert_eq!(errors[1].message, "not an io error");
+
+        list.stop();
+        assert!(list.stopped());
+
+        list.clear();
+        assert!(list.errors().is_empty(), "list should be cleared");
+        assert!(!list.stopped(), "list should be cleared");
+    }
+}
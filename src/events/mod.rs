@@ -0,0 +1,119 @@
+use std::{path::{Path, PathBuf}, sync::{mpsc::Sender, Mutex, MutexGuard}};
+use crate::{DupeFinder, Duplicate, ScanError, ScanResults, SkipReason};
+
+/// Progress of a run started with `DupeFinder::run_with_events`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScanEvent {
+    /// A directory is about to be read
+    DirEntered(PathBuf),
+    /// A file or directory was left out, see `DupeFinder::skipped_files`
+    FileSkipped { path: PathBuf, reason: SkipReason },
+    /// The match key of a file was produced, files whose key was kept from an earlier run are not hashed again
+    FileHashed { path: PathBuf, size: u64 },
+    /// A duplicate set passed every filter and will be part of the results
+    GroupFound(Duplicate),
+    /// A file or directory could not be read, see `ScanResults::errors`
+    Error(ScanError),
+}
+
+// Receiver of the events of the current run, shared with hashing threads
+#[derive(Default)]
+pub struct EventSender {
+    sender: Mutex<Option<Sender<ScanEvent>>>,
+}
+
+impl EventSender {
+    // `event` is only built while someone is listening, a receiver that hung up is ignored
+    pub fn send<F: FnOnce() -> ScanEvent>(&self, event: F) {
+        if let Some(sender) = self.lock().as_ref() {
+            let _ = sender.send(event());
+        }
+    }
+
+    pub fn set(&self, sender: Option<Sender<ScanEvent>>) {
+        *self.lock() = sender;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Sender<ScanEvent>>> {
+        self.sender.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl DupeFinder {
+    /// Runs the search like `run` while sending each step of it to `events`
+    ///
+    /// Events are sent from the thread running the search and, with a thread pool, from its hashing
+    /// threads. The sender is dropped when the run returns, which ends iteration on the receiver.
+    /// Sets are announced once all files are hashed, as they are in `run`, `run_iter` yields them earlier.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::{sync::mpsc, thread};
+    /// use dupefinder::ScanEvent;
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    /// let scan = thread::spawn(move || dupefinder::DupeFinder::new_recursive(vec!["./resources"]).run_with_events(sender));
+    /// for event in receiver {
+    ///     match event {
+    ///         ScanEvent::DirEntered(directory) => println!("reading {}", directory.display()),
+    ///         ScanEvent::GroupFound(duplicate) => println!("{}", duplicate),
+    ///         _ => {},
+    ///     }
+    /// }
+    /// let results = scan.join().unwrap();
+    /// ```
+    pub fn run_with_events(&mut self, events: Sender<ScanEvent>) -> ScanResults {
+        self.events.set(Some(events));
+        let results = self.run();
+        self.events.set(None);
+
+        results
+    }
+
+    // leaves `path` out of the run
+    pub(crate) fn skip(&self, path: &Path, reason: SkipReason) {
+        self.events.send(|| ScanEvent::FileSkipped { path: path.to_path_buf(), reason: reason.clone() });
+        self.skipped.push(path, reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::{fs, sync::mpsc};
+
+    #[test]
+    fn test_run_with_events() {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let (sender, receiver) = mpsc::channel();
+        let mut checker = DupeFinder::new([&path]);
+
+        let results = checker.run_with_events(sender);
+        let events: Vec<ScanEvent> = receiver.iter().collect();
+
+        assert_eq!(events[0], ScanEvent::DirEntered(path.clone()));
+        let hashed = events.iter().filter(|event| matches!(event, ScanEvent::FileHashed { .. })).count();
+        assert_eq!(hashed, results.stats().files_hashed);
+        let found: Vec<&Duplicate> = events.iter().filter_map(|event| match event { ScanEvent::GroupFound(duplicate) => Some(duplicate), _ => None }).collect();
+        assert_eq!(found, results.values().collect::<Vec<&Duplicate>>());
+    }
+
+    #[test]
+    fn test_skip_and_error_events() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.txt"), "contents").unwrap();
+        let missing = temp.path().join("missing");
+        let (sender, receiver) = mpsc::channel();
+        let mut checker = DupeFinder::builder([temp.path().to_path_buf(), missing.clone()])
+            .limit_path_length(temp.path().as_os_str().len() + 3)
+            .build()
+            .unwrap();
+
+        checker.run_with_events(sender);
+        let events: Vec<ScanEvent> = receiver.iter().collect();
+
+        assert!(events.contains(&ScanEvent::FileSkipped { path: temp.path().join("a.txt"), reason: SkipReason::PathTooLong }));
+        assert!(events.iter().any(|event| matches!(event, ScanEvent::Error(error) if error.path == missing)));
+    }
+}
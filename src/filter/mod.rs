@@ -0,0 +1,287 @@
+use std::{collections::HashSet, fs::{DirEntry, Metadata}, path::{Component, Path}, sync::Arc, time::SystemTime};
+
+/// What to do with an entry found while traversing, returned by the filter of `DupeFinderBuilder::walk_filter`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WalkDecision {
+    /// Search the entry like any other
+    Accept,
+    /// Leave out the entry, a directory with everything below it
+    Skip,
+    /// Leave out the directory holding the entry with all of its entries, e.g. on finding a marker file
+    SkipDirectory,
+}
+
+// user supplied filter called for every traversed entry
+pub type WalkFilter = Arc<dyn Fn(&Path, &Metadata) -> WalkDecision + Send + Sync>;
+
+// Decides which traversed files are considered, composed via `DupeFinderBuilder`
+#[derive(Clone, Default)]
+pub struct PathFilter {
+    // when set only files with one of these extensions are considered
+    pub include_extensions: Option<HashSet<String>>,
+    // files with one of these extensions are never considered
+    pub exclude_extensions: HashSet<String>,
+    // files and directories matching one of these are skipped during traversal
+    pub exclude_globs: Vec<Glob>,
+    // skip dotfiles on unix, files with the hidden attribute on windows
+    pub skip_hidden: bool,
+    // only files last modified at or after this time are considered, reference files excepted
+    pub modified_after: Option<SystemTime>,
+    // only files last modified before this time are considered, reference files excepted
+    pub modified_before: Option<SystemTime>,
+    // ordered include / exclude rules over the full path, the last matching rule decides
+    #[cfg(feature = "regex")]
+    pub regex_rules: Vec<RegexRule>,
+    // called last for every entry passing the other rules, with resolved metadata
+    pub walk: Option<WalkFilter>,
+}
+
+impl PathFilter {
+    // excluded directories are not descended into
+    pub fn excludes(&self, path: &Path) -> bool {
+        if self.exclude_globs.iter().any(|glob| glob.matches(path)) {
+            return true;
+        }
+
+        #[cfg(feature = "regex")]
+        if !self.regex_rules.is_empty() {
+            let full_path = path.to_string_lossy();
+            if let Some(rule) = self.regex_rules.iter().rev().find(|rule| rule.regex.is_match(&full_path)) {
+                return !rule.include;
+            }
+        }
+
+        false
+    }
+
+    pub fn hides(&self, entry: &DirEntry) -> bool {
+        self.skip_hidden && is_hidden(entry)
+    }
+
+    // `hides` for a path reported without a directory entry, such as by the file watcher
+    #[cfg(feature = "notify")]
+    pub fn hides_path(&self, path: &Path) -> bool {
+        self.skip_hidden && is_hidden_path(path)
+    }
+
+    // whether the modification time of a file lies within the configured window,
+    // files whose modification time can not be read are kept
+    pub fn allows_modified(&self, meta: &Metadata) -> bool {
+        if self.modified_after.is_none() && self.modified_before.is_none() {
+            return true;
+        }
+
+        match meta.modified() {
+            Ok(modified) => self.modified_after.is_none_or(|after| modified >= after) && self.modified_before.is_none_or(|before| modified < before),
+            Err(_) => true,
+        }
+    }
+
+    pub fn allows_file(&self, path: &Path) -> bool {
+        let extension = path.extension().map(|extension| normalize_extension(&extension.to_string_lossy()));
+
+        if let Some(include) = &self.include_extensions {
+            if !extension.as_ref().is_some_and(|extension| include.contains(extension)) {
+                return false;
+            }
+        }
+
+        match extension {
+            Some(extension) => !self.exclude_extensions.contains(&extension),
+            None => true,
+        }
+    }
+}
+
+#[cfg(windows)]
+fn is_hidden(entry: &DirEntry) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+    entry.metadata().is_ok_and(|meta| meta.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+}
+
+#[cfg(not(windows))]
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().to_string_lossy().starts_with('.')
+}
+
+#[cfg(all(windows, feature = "notify"))]
+fn is_hidden_path(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+    std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+}
+
+#[cfg(all(not(windows), feature = "notify"))]
+fn is_hidden_path(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+// Regular expression that includes or excludes the paths it matches
+#[cfg(feature = "regex")]
+#[derive(Clone, Debug)]
+pub struct RegexRule {
+    pub regex: regex::Regex,
+    pub include: bool,
+}
+
+// Shell style pattern: `*` and `?` match within a path component, `**` matches any
+// number of components. Patterns without a `/` are matched against the file name only.
+#[derive(Clone, Debug)]
+pub struct Glob {
+    components: Vec<Vec<char>>,
+    name_only: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Glob {
+        let pattern = if cfg!(windows) { pattern.replace('\\', "/") } else { pattern.to_string() };
+        Glob {
+            components: pattern.split('/').filter(|component| !component.is_empty()).map(|component| component.chars().collect()).collect(),
+            name_only: !pattern.contains('/'),
+        }
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.name_only {
+            return match (path.file_name(), self.components.first()) {
+                (Some(name), Some(pattern)) => match_component(pattern, &name.to_string_lossy().chars().collect::<Vec<char>>()),
+                _ => false,
+            };
+        }
+
+        let components: Vec<Vec<char>> = path.components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().chars().collect()),
+                _ => None,
+            })
+            .collect();
+
+        match_components(&self.components, &components)
+    }
+}
+
+fn match_components(pattern: &[Vec<char>], path: &[Vec<char>]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first.iter().eq(['*', '*'].iter()) => {
+            (0..=path.len()).any(|skipped| match_components(rest, &path[skipped..]))
+        },
+        Some((first, rest)) => match path.split_first() {
+            Some((component, path)) => match_component(first, component) && match_components(rest, path),
+            None => false,
+        },
+    }
+}
+
+fn match_component(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skipped| match_component(rest, &text[skipped..])),
+        Some(('?', rest)) => !text.is_empty() && match_component(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && match_component(rest, &text[1..]),
+    }
+}
+
+// extensions are compared case insensitively and may be given with a leading dot
+pub fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn extensions(list: &[&str]) -> HashSet<String> {
+        list.iter().map(|extension| normalize_extension(extension)).collect()
+    }
+
+    #[test]
+    fn test_default_allows_all() {
+        let filter = PathFilter::default();
+        assert!(filter.allows_file(Path::new("photo.jpg")));
+        assert!(filter.allows_file(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn test_include_extensions() {
+        let filter = PathFilter { include_extensions: Some(extensions(&["jpg", ".PNG"])), ..PathFilter::default() };
+        assert!(filter.allows_file(Path::new("photo.JPG")));
+        assert!(filter.allows_file(Path::new("dir/image.png")));
+        assert!(!filter.allows_file(Path::new("notes.txt")));
+        assert!(!filter.allows_file(Path::new("Makefile")), "files without extension are not included");
+    }
+
+    #[test]
+    fn test_exclude_extensions() {
+        let filter = PathFilter { exclude_extensions: extensions(&["tmp"]), ..PathFilter::default() };
+        assert!(!filter.allows_file(Path::new("download.TMP")));
+        assert!(filter.allows_file(Path::new("notes.txt")));
+        assert!(filter.allows_file(Path::new("Makefile")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hides_dotfiles() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join(".hidden"), "hidden").unwrap();
+        std::fs::write(temp.path().join("visible"), "visible").unwrap();
+
+        let filter = PathFilter { skip_hidden: true, ..PathFilter::default() };
+        for entry in std::fs::read_dir(temp.path()).unwrap() {
+            let entry = entry.unwrap();
+            assert_eq!(filter.hides(&entry), entry.file_name() == ".hidden");
+            assert!(!PathFilter::default().hides(&entry), "hidden files are kept by default");
+        }
+    }
+
+    #[test]
+    fn test_glob_name_only() {
+        let glob = Glob::new("*.tmp");
+        assert!(glob.matches(Path::new("/home/user/download.tmp")));
+        assert!(glob.matches(Path::new(".tmp")));
+        assert!(!glob.matches(Path::new("/home/user/download.tmp.txt")));
+        assert!(Glob::new("?.txt").matches(Path::new("dir/a.txt")));
+        assert!(!Glob::new("?.txt").matches(Path::new("dir/ab.txt")));
+    }
+
+    #[test]
+    fn test_glob_components() {
+        let glob = Glob::new("**/node_modules/**");
+        assert!(glob.matches(Path::new("/project/node_modules")));
+        assert!(glob.matches(Path::new("/project/node_modules/left-pad/index.js")));
+        assert!(!glob.matches(Path::new("/project/src/node_modules.rs")));
+
+        let glob = Glob::new("/data/*/cache");
+        assert!(glob.matches(Path::new("/data/app/cache")));
+        assert!(!glob.matches(Path::new("/data/app/nested/cache")));
+        assert!(!glob.matches(Path::new("/other/data/app/cache")));
+    }
+
+    #[test]
+    fn test_excludes() {
+        let filter = PathFilter { exclude_globs: vec![Glob::new("*.tmp"), Glob::new("**/.git")], ..PathFilter::default() };
+        assert!(filter.excludes(Path::new("/repo/.git")));
+        assert!(filter.excludes(Path::new("/repo/file.tmp")));
+        assert!(!filter.excludes(Path::new("/repo/src")));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_last_rule_wins() {
+        let rule = |pattern: &str, include: bool| RegexRule { regex: regex::Regex::new(pattern).unwrap(), include };
+        let filter = PathFilter {
+            regex_rules: vec![rule(r"/\.[^/]+(/|$)", false), rule(r"/\.config(/|$)", true)],
+            ..PathFilter::default()
+        };
+
+        assert!(filter.excludes(Path::new("/home/user/.cache")));
+        assert!(filter.excludes(Path::new("/home/user/.git/config")));
+        assert!(!filter.excludes(Path::new("/home/user/.config")));
+        assert!(!filter.excludes(Path::new("/home/user/.config/app/settings.toml")));
+        assert!(!filter.excludes(Path::new("/home/user/photos")));
+    }
+}
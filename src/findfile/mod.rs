@@ -1,20 +1,32 @@
-use std::io;
-use crate::{dirdata::DirData, Hashable};
+use std::{io, path::Path};
+use crate::{dirdata::DirData, hasher::FileHasher, DupeFinderError, MatchStrategy};
 
 // Holds data about a specific file we may be trying to find
 pub struct FindFile {
     pub data: DirData,
+    // candidate key other files need to share to be compared
+    pub key: u64,
     pub hash: String,
 }
 
 impl FindFile {
-    pub fn new(path: String) -> Result<FindFile, io::Error> {
-        let hash = path.get_file_hash()?;
-        let data: DirData = DirData::new_from_path(path)?;
+    pub fn new<P: AsRef<Path>>(path: P, strategy: &dyn MatchStrategy, hasher: &dyn FileHasher) -> Result<FindFile, DupeFinderError> {
+        let metadata_error = |source: io::Error| DupeFinderError::Metadata { path: path.as_ref().to_path_buf(), source };
+        let data: DirData = DirData::new_from_path(&path).map_err(metadata_error)?;
+        // only regular files are opened, reading a FIFO could block forever
+        if !data.meta.is_file() {
+            return Err(metadata_error(io::Error::new(io::ErrorKind::InvalidInput, "not a regular file")));
+        }
+        let key = match strategy.candidate_key(&data.path, &data.meta) {
+            Some(key) => key,
+            None => return Err(metadata_error(io::Error::new(io::ErrorKind::InvalidInput, "file is left out by the match strategy"))),
+        };
+        let hash = strategy.match_key(&data.path, hasher).map_err(|source| DupeFinderError::Hashing { path: data.path.clone(), source })?;
         
         Ok(FindFile{
-            hash: hash,
-            data: data,
+            hash,
+            key,
+            data,
         })
     }
 }
@@ -23,13 +35,14 @@ impl FindFile {
 mod tests {
 
     use super::*;
+    use crate::{hasher::Xxh3Hasher, ContentStrategy};
 
     #[test]
     fn test_create_find_file() {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
         let path_string: String = path.display().to_string();
 
-        let find_file = FindFile::new(path_string);
+        let find_file = FindFile::new(path_string, &ContentStrategy, &Xxh3Hasher);
         assert!(find_file.is_ok(), "no io error expected");
 
         if let Ok(find_file) = find_file {
@@ -44,7 +57,7 @@ mod tests {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","doesnotexist.txt"].iter().collect();
         let path_string: String = path.display().to_string();
 
-        let find_file = FindFile::new(path_string);
+        let find_file = FindFile::new(path_string, &ContentStrategy, &Xxh3Hasher);
         assert!(find_file.is_err(), "io error expected");
     }
 
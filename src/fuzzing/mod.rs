@@ -0,0 +1,31 @@
+//! Crate private decoders reachable by the cargo-fuzz targets in `fuzz/`, only built with `--cfg fuzzing`
+
+use std::path::Path;
+use crate::filter::{normalize_extension, Glob};
+
+/// Restores a path written by `PathEncoding::Percent`, as snapshots written by `ScanResults::save` hold them
+#[cfg(feature = "serde")]
+pub fn percent_decode(text: &str) -> Option<std::path::PathBuf> {
+    crate::report::percent_decode(text)
+}
+
+/// Writes `path` like `PathEncoding::Percent`
+pub fn percent_encode(path: &Path) -> String {
+    crate::report::PathEncoding::Percent.encode(path).0
+}
+
+/// The hash held by a value of the hash cache if it was stored for a file matching `header`
+#[cfg(feature = "cache")]
+pub fn cache_value(value: &[u8], header: &[u8; 20]) -> Option<String> {
+    crate::cache::decode(value, header)
+}
+
+/// Whether the glob `pattern` of `DupeFinderBuilder::exclude_globs` matches `path`
+pub fn glob_matches(pattern: &str, path: &Path) -> bool {
+    Glob::new(pattern).matches(path)
+}
+
+/// An extension as `DupeFinderBuilder::include_extensions` compares it
+pub fn extension(extension: &str) -> String {
+    normalize_extension(extension)
+}
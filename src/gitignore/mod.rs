@@ -0,0 +1,97 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::Arc};
+use ignore::{gitignore::{Gitignore, GitignoreBuilder}, Match};
+
+// files read from every traversed directory, rules of later files win
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+// `.gitignore` / `.ignore` rules of the traversed directories, each directory holds the
+// rules of its own ignore files and those of the directories above it
+#[derive(Default)]
+pub struct IgnoreRules {
+    directories: HashMap<PathBuf, Vec<Arc<Gitignore>>>,
+}
+
+impl IgnoreRules {
+    // reads the ignore files of `directory`, its parent has to be loaded first to inherit its rules,
+    // returns the files that could not be parsed
+    pub fn load(&mut self, directory: &Path) -> Vec<ignore::Error> {
+        let mut errors: Vec<ignore::Error> = Vec::new();
+        let mut rules: Vec<Arc<Gitignore>> = directory.parent()
+            .and_then(|parent| self.directories.get(parent))
+            .cloned()
+            .unwrap_or_default();
+
+        let mut builder = GitignoreBuilder::new(directory);
+        let mut found = false;
+        for name in IGNORE_FILES {
+            let file = directory.join(name);
+            if file.is_file() {
+                found = true;
+                errors.extend(builder.add(file));
+            }
+        }
+
+        if found {
+            match builder.build() {
+                Ok(gitignore) => rules.push(Arc::new(gitignore)),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        self.directories.insert(directory.to_path_buf(), rules);
+        errors
+    }
+
+    // whether the rules of the directory holding `path` leave it out, the closest ignore file decides
+    pub fn ignores(&self, path: &Path, is_dir: bool) -> bool {
+        let rules = match path.parent().and_then(|parent| self.directories.get(parent)) {
+            Some(rules) => rules,
+            None => return false,
+        };
+
+        for gitignore in rules.iter().rev() {
+            match gitignore.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => continue,
+            }
+        }
+
+        false
+    }
+
+    pub fn clear(&mut self) {
+        self.directories.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+    use super::*;
+
+    #[test]
+    fn test_ignore_rules() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let sub = root.join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(sub.join(".ignore"), "!keep.log\n").unwrap();
+
+        let mut rules = IgnoreRules::default();
+        assert!(rules.load(root).is_empty(), "no parse error expected");
+        assert!(rules.load(&sub).is_empty(), "no parse error expected");
+
+        assert!(rules.ignores(&root.join("target"), true));
+        assert!(!rules.ignores(&root.join("target"), false), "directory rules should not match files");
+        assert!(rules.ignores(&root.join("a.log"), false));
+        assert!(rules.ignores(&sub.join("b.log"), false), "parent rules should apply");
+        assert!(!rules.ignores(&sub.join("keep.log"), false), "closer rules should win");
+        assert!(!rules.ignores(&root.join("a.txt"), false));
+
+        rules.clear();
+        assert!(!rules.ignores(&root.join("a.log"), false), "rules should be cleared");
+    }
+}
@@ -0,0 +1,91 @@
+use std::{collections::HashMap, fs::Metadata, path::{Path, PathBuf}};
+
+/// How multiple hard links to the same file are handled
+///
+/// Hard links share their storage so they are not wasted space. Links are only
+/// recognized on unix, elsewhere every link is treated as a separate file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HardlinkPolicy {
+    /// Every link is treated as a separate file and can be reported as a duplicate
+    #[default]
+    Keep,
+    /// Only the first link found is searched, further links to the same file are left out
+    Collapse,
+    /// Like `Collapse`, additionally listing all links of each file in `ScanResults::hardlinks`
+    Separate,
+}
+
+// Paths found so far for every file with more than one hard link
+#[derive(Default)]
+pub struct HardlinkIndex {
+    links: HashMap<(u64, u64), Vec<PathBuf>>,
+}
+
+impl HardlinkIndex {
+    // records `path`, returns false if another link to the same file was already recorded
+    pub fn insert(&mut self, path: &Path, meta: &Metadata) -> bool {
+        let id = match file_id(meta) {
+            Some(id) => id,
+            None => return true,
+        };
+
+        let paths = self.links.entry(id).or_default();
+        paths.push(path.to_path_buf());
+        paths.len() == 1
+    }
+
+    // every set of links found to the same file, sorted for stable output
+    pub fn groups(&self) -> Vec<Vec<PathBuf>> {
+        let mut groups: Vec<Vec<PathBuf>> = self.links.values().filter(|paths| paths.len() > 1).cloned().collect();
+        groups.sort();
+        groups
+    }
+
+    pub fn clear(&mut self) {
+        self.links.clear();
+    }
+}
+
+// (device, inode) of files with more than one link
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    if meta.nlink() > 1 {
+        Some((meta.dev(), meta.ino()))
+    } else {
+        None
+    }
+}
+
+// file indexes are not exposed by stable std outside of unix
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_index_links() {
+        let temp = tempfile::tempdir().unwrap();
+        let original = temp.path().join("a.txt");
+        let link = temp.path().join("link.txt");
+        let other = temp.path().join("b.txt");
+        std::fs::write(&original, "contents").unwrap();
+        std::fs::write(&other, "contents").unwrap();
+        std::fs::hard_link(&original, &link).unwrap();
+
+        let mut index = HardlinkIndex::default();
+        assert!(index.insert(&original, &std::fs::metadata(&original).unwrap()));
+        assert!(index.insert(&other, &std::fs::metadata(&other).unwrap()));
+        assert!(!index.insert(&link, &std::fs::metadata(&link).unwrap()), "second link expected");
+        assert_eq!(index.groups(), vec![vec![original, link]]);
+
+        index.clear();
+        assert!(index.groups().is_empty(), "index should be cleared");
+    }
+}
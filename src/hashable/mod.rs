@@ -1,9 +1,8 @@
-use std::{io, path::PathBuf};
-use std::io::{BufRead, BufReader};
-use xxhash_rust::xxh3::Xxh3;
+use std::{io, path::{Path, PathBuf}};
+use crate::hasher::{FileHasher, Xxh3Hasher};
 
 /// Convenience trait to generate a XXH3 hash of the file contents
-/// located in the path specified by a `String` / `&str` / `PathBuf`.
+/// located in the path specified by a `String` / `&str` / `PathBuf` / `Path`.
 /// # Examples
 /// ```
 /// use dupefinder::Hashable;
@@ -37,45 +36,31 @@ pub trait Hashable {
 
 impl Hashable for String {
     fn get_file_hash(&self) -> Result<String, io::Error> {
-        let path: PathBuf = self.into();
-
-        generate_file_hash(path)
+        generate_file_hash(Path::new(self))
     }
 }
 
 impl Hashable for PathBuf {
     fn get_file_hash(&self) -> Result<String, io::Error> {
-        generate_file_hash(self.to_path_buf())
+        generate_file_hash(self)
     }
 }
 
-impl Hashable for &str {
+impl Hashable for Path {
     fn get_file_hash(&self) -> Result<String, io::Error> {
-        let path: PathBuf = self.into();
-        
-        generate_file_hash(path)
+        generate_file_hash(self)
     }
 }
 
-fn generate_file_hash(path: PathBuf) -> Result<String, io::Error> {
-    let file = std::fs::File::open(path)?;
-    let mut file = BufReader::with_capacity(262144 , file);
-
-    let mut hasher = Xxh3::default();
-    loop {
-        let buf = file.fill_buf()?;
-        let buf_len = buf.len();
-        if buf_len == 0 {
-            break;
-        }
-        hasher.update(buf);
-        file.consume(buf_len);
+impl Hashable for &str {
+    fn get_file_hash(&self) -> Result<String, io::Error> {
+        generate_file_hash(Path::new(self))
     }
-
-    Ok(format!("{:X}", hasher.digest128()))
 }
 
-
+fn generate_file_hash(path: &Path) -> Result<String, io::Error> {
+    Xxh3Hasher.hash_file(path)
+}
 
 #[cfg(test)]
 mod tests {
@@ -85,7 +70,7 @@ mod tests {
     #[test]
     fn test_generate_file_hash() {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
-        let hash = generate_file_hash(path);
+        let hash = generate_file_hash(&path);
         assert!(hash.is_ok(), "no io error should occur");
         assert_eq!(hash.unwrap(), String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
     }
@@ -122,10 +107,19 @@ mod tests {
         assert_eq!(hash.unwrap(), String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
     }
 
+    #[test]
+    fn test_path_hash() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
+
+        let hash = path.as_path().get_file_hash();
+        assert!(hash.is_ok(), "no io error should occur");
+        assert_eq!(hash.unwrap(), String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
+    }
+
     #[test]
     fn test_generate_file_hash_error() {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","doesnotexist.txt"].iter().collect();
-        let hash = generate_file_hash(path);
+        let hash = generate_file_hash(&path);
         assert!(hash.is_err(), "io error should occur");
     }
 
@@ -0,0 +1,204 @@
+use std::{any::Any, fs::File, io::{self, Read}, panic::{self, AssertUnwindSafe}, path::Path};
+use xxhash_rust::xxh3::Xxh3;
+
+// amount of file data read per hashing step
+const READ_BUFFER_SIZE: usize = 262144;
+
+/// Generates the content hash used to match same-size files
+///
+/// `DupeFinder` defaults to `Xxh3Hasher`, other algorithms can be configured via
+/// `DupeFinderBuilder::hasher`. Hashes are returned as uppercase hex strings.
+///
+/// # Examples
+/// ```
+/// use dupefinder::FileHasher;
+///
+/// let hasher = dupefinder::Xxh3Hasher;
+/// if let Ok(hash) = hasher.hash_file(std::path::Path::new("./test.txt")) {
+///     println!("The file hash is: {}", hash);
+/// };
+/// ```
+pub trait FileHasher: Send + Sync {
+    /// Hashes all data produced by `reader`
+    fn hash_reader(&self, reader: &mut dyn Read) -> Result<String, io::Error>;
+
+    /// Hashes the contents of the file at `path`
+    ///
+    /// When file timeouts are configured the `DupeFinder` opens the file itself and
+    /// passes it to `hash_reader`, so overriding this method has no effect then.
+    fn hash_file(&self, path: &Path) -> Result<String, io::Error> {
+        let mut file = File::open(path)?;
+        self.hash_reader(&mut file)
+    }
+
+    /// Tells the digests of this hasher apart from those of others, `DupeFinderBuilder::hash_cache`
+    /// keeps the hashes of each identity separate. Defaults to the type name, hashers whose digests
+    /// depend on their settings should include them.
+    fn identity(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+}
+
+/// 128 bit XXH3 hashing, fast but not cryptographically secure
+#[derive(Clone, Copy, Default)]
+pub struct Xxh3Hasher;
+
+impl FileHasher for Xxh3Hasher {
+    fn hash_reader(&self, reader: &mut dyn Read) -> Result<String, io::Error> {
+        let mut hasher = Xxh3::default();
+        read_chunks(reader, |chunk| hasher.update(chunk))?;
+
+        Ok(format!("{:X}", hasher.digest128()))
+    }
+}
+
+/// BLAKE3 hashing
+#[cfg(feature = "blake3")]
+#[derive(Clone, Copy, Default)]
+pub struct Blake3Hasher;
+
+#[cfg(feature = "blake3")]
+impl FileHasher for Blake3Hasher {
+    fn hash_reader(&self, reader: &mut dyn Read) -> Result<String, io::Error> {
+        let mut hasher = blake3::Hasher::new();
+        read_chunks(reader, |chunk| { hasher.update(chunk); })?;
+
+        Ok(hasher.finalize().to_hex().to_uppercase())
+    }
+}
+
+/// SHA-256 hashing, suited to audit trails requiring a standard digest
+#[cfg(feature = "sha2")]
+#[derive(Clone, Copy, Default)]
+pub struct Sha256Hasher;
+
+#[cfg(feature = "sha2")]
+impl FileHasher for Sha256Hasher {
+    fn hash_reader(&self, reader: &mut dyn Read) -> Result<String, io::Error> {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        read_chunks(reader, |chunk| hasher.update(chunk))?;
+
+        Ok(hasher.finalize().iter().map(|byte| format!("{:02X}", byte)).collect())
+    }
+}
+
+// hashes all data produced by `reader`, returning the number of bytes read with the hash
+pub(crate) fn hash_counted(hasher: &dyn FileHasher, reader: &mut dyn Read) -> Result<(u64, String), io::Error> {
+    let mut counted = Counted { inner: reader, bytes: 0 };
+    let hash = hasher.hash_reader(&mut counted)?;
+
+    Ok((counted.bytes, hash))
+}
+
+// counts the bytes passing through a reader
+struct Counted<'a> {
+    inner: &'a mut dyn Read,
+    bytes: u64,
+}
+
+impl Read for Counted<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, io::Error> {
+        let read = self.inner.read(buffer)?;
+        self.bytes += read as u64;
+        Ok(read)
+    }
+}
+
+// runs `operation`, converting a panic inside it into an io error for the file
+// being worked on so a single pathological file can not take down a whole scan
+pub(crate) fn isolate_panics<T, F: FnOnce() -> Result<T, io::Error>>(operation: F) -> Result<T, io::Error> {
+    match panic::catch_unwind(AssertUnwindSafe(operation)) {
+        Ok(result) => result,
+        Err(payload) => Err(io::Error::other(format!("hashing panicked: {}", panic_message(payload.as_ref())))),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+// feeds `reader` to `update` in buffer sized chunks until it is exhausted
+fn read_chunks<F: FnMut(&[u8])>(reader: &mut dyn Read, mut update: F) -> Result<(), io::Error> {
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        update(&buffer[..read]);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_xxh3_hash_file() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let hash = Xxh3Hasher.hash_file(&path);
+        assert!(hash.is_ok(), "no io error should occur");
+        assert_eq!(hash.unwrap(), String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
+    }
+
+    #[test]
+    fn test_hash_file_error() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "doesnotexist.txt"].iter().collect();
+        assert!(Xxh3Hasher.hash_file(&path).is_err(), "io error should occur");
+    }
+
+    #[test]
+    fn test_hash_counted() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let mut file = File::open(&path).unwrap();
+        let (bytes, hash) = hash_counted(&Xxh3Hasher, &mut file).unwrap();
+        assert_eq!(bytes, 100);
+        assert_eq!(hash, Xxh3Hasher.hash_file(&path).unwrap());
+    }
+
+    #[test]
+    fn test_isolate_panics() {
+        struct PanicHasher;
+        impl FileHasher for PanicHasher {
+            fn hash_reader(&self, _reader: &mut dyn Read) -> Result<String, io::Error> {
+                panic!("bad file");
+            }
+        }
+
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let hash = isolate_panics(|| PanicHasher.hash_file(&path));
+        assert!(hash.is_err(), "panic should become an error");
+        assert_eq!(hash.unwrap_err().to_string(), "hashing panicked: bad file");
+
+        assert!(isolate_panics(|| Xxh3Hasher.hash_file(&path)).is_ok(), "no error expected");
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_hash_reader() {
+        let hash = Blake3Hasher.hash_reader(&mut "".as_bytes());
+        assert_eq!(hash.unwrap(), String::from("AF1349B9F5F9A1A6A0404DEA36DCC9499BCB25C9ADC112B7CC9A93CAE41F3262"));
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_sha256_hash_file() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let hash = Sha256Hasher.hash_file(&path);
+        assert_eq!(hash.unwrap(), String::from("AE040FB6B2256BD5CEADF0CA34262BAB9460B46613C718F86A47D5F657BAEC78"));
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fs::File, io::{self, BufReader}, path::{Path, PathBuf}};
+use exif::{Context, Field, In, Reader, Tag, Value};
+use log::warn;
+use crate::LogCategory;
+
+// Windows / XMP star rating mirrored into IFD0, not named by `kamadak-exif`
+const TAG_RATING: Tag = Tag(Context::Tiff, 0x4746);
+
+// formats a single `ExifSummary` field for comparison
+type FieldFormatter = fn(&ExifSummary) -> Option<String>;
+
+/// Key EXIF fields of an image file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExifSummary {
+    /// Original capture date as stored in the file (`YYYY:MM:DD HH:MM:SS`)
+    pub date_taken: Option<String>,
+    /// Camera manufacturer
+    pub camera_make: Option<String>,
+    /// Camera model
+    pub camera_model: Option<String>,
+    /// GPS position as (latitude, longitude) in decimal degrees
+    pub gps: Option<(f64, f64)>,
+    /// Star rating (0 - 5)
+    pub rating: Option<u32>,
+}
+
+/// A metadata field that does not hold the same value for every compared file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExifDifference {
+    /// Name of the differing field, matching the `ExifSummary` field name
+    pub field: &'static str,
+    /// Full file path and the formatted field value for each compared file
+    pub values: Vec<(PathBuf, Option<String>)>,
+}
+
+/// Reads the EXIF summary of the image at `path`
+///
+/// Returns `Ok(None)` if the file holds no EXIF data or is not a supported container.
+pub fn read_exif_summary<P: AsRef<Path>>(path: P) -> Result<Option<ExifSummary>, io::Error> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    let exif = match Reader::new().read_from_container(&mut reader) {
+        Ok(exif) => exif,
+        Err(exif::Error::Io(e)) => return Err(e),
+        Err(_) => return Ok(None),
+    };
+
+    let ascii = |tag: Tag| exif.get_field(tag, In::PRIMARY).and_then(field_ascii);
+    let degrees = |tag: Tag, reference: Tag| {
+        let value = exif.get_field(tag, In::PRIMARY).and_then(field_degrees)?;
+        match ascii(reference).as_deref() {
+            Some("S") | Some("W") => Some(-value),
+            _ => Some(value),
+        }
+    };
+
+    let gps = match (degrees(Tag::GPSLatitude, Tag::GPSLatitudeRef), degrees(Tag::GPSLongitude, Tag::GPSLongitudeRef)) {
+        (Some(latitude), Some(longitude)) => Some((latitude, longitude)),
+        _ => None,
+    };
+
+    Ok(Some(ExifSummary {
+        date_taken: ascii(Tag::DateTimeOriginal).or_else(|| ascii(Tag::DateTime)),
+        camera_make: ascii(Tag::Make),
+        camera_model: ascii(Tag::Model),
+        gps,
+        rating: exif.get_field(TAG_RATING, In::PRIMARY).and_then(|field| field.value.get_uint(0)),
+    }))
+}
+
+/// Compares the EXIF summaries of `files` and returns every field whose value differs
+///
+/// Files that cannot be read or carry no EXIF data are compared as having no values.
+pub fn compare_exif<P: AsRef<Path>>(files: &[P]) -> Vec<ExifDifference> {
+    let summaries: Vec<ExifSummary> = files.iter().map(|file| {
+        match read_exif_summary(file) {
+            Ok(summary) => summary.unwrap_or_default(),
+            Err(e) => {
+                warn!(target: LogCategory::Metadata.target(), "Error reading exif data for file: {}; error: {}", file.as_ref().display(), e);
+                ExifSummary::default()
+            }
+        }
+    }).collect();
+
+    let fields: [(&'static str, FieldFormatter); 5] = [
+        ("date_taken", |summary| summary.date_taken.clone()),
+        ("camera_make", |summary| summary.camera_make.clone()),
+        ("camera_model", |summary| summary.camera_model.clone()),
+        ("gps", |summary| summary.gps.map(|(latitude, longitude)| format!("{:.6}, {:.6}", latitude, longitude))),
+        ("rating", |summary| summary.rating.map(|rating| rating.to_string())),
+    ];
+
+    let mut differences: Vec<ExifDifference> = Vec::new();
+    for (field, value) in fields {
+        let values: Vec<Option<String>> = summaries.iter().map(value).collect();
+        if values.windows(2).all(|pair| pair[0] == pair[1]) {
+            continue;
+        }
+
+        differences.push(ExifDifference {
+            field,
+            values: files.iter().map(|file| file.as_ref().to_path_buf()).zip(values).collect(),
+        });
+    }
+
+    differences
+}
+
+fn field_ascii(field: &Field) -> Option<String> {
+    match &field.value {
+        Value::Ascii(values) => {
+            let value = values.first()?;
+            let text = String::from_utf8_lossy(value).trim_end_matches('\0').trim().to_string();
+            if text.is_empty() { None } else { Some(text) }
+        },
+        _ => None,
+    }
+}
+
+// converts a degrees / minutes / seconds rational triple into decimal degrees
+fn field_degrees(field: &Field) -> Option<f64> {
+    match &field.value {
+        Value::Rational(values) if values.len() == 3 => {
+            Some(values[0].to_f64() + values[1].to_f64() / 60.0 + values[2].to_f64() / 3600.0)
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_read_exif_summary() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "images", "exif_a.jpg"].iter().collect();
+        let summary = read_exif_summary(path);
+        assert!(summary.is_ok(), "no io error expected");
+
+        let summary = summary.unwrap();
+        assert!(summary.is_some(), "exif data expected");
+        if let Some(summary) = summary {
+            assert_eq!(summary.camera_make, Some(String::from("Canon")));
+            assert_eq!(summary.camera_model, Some(String::from("Canon EOS 5D")));
+            assert_eq!(summary.date_taken, Some(String::from("2021:06:01 10:30:00")));
+
+            let (latitude, longitude) = summary.gps.unwrap();
+            assert!((latitude - 51.5).abs() < 0.0001);
+            assert!((longitude + 0.125).abs() < 0.0001);
+            assert_eq!(summary.rating, Some(4));
+        };
+    }
+
+    #[test]
+    fn test_compare_exif() {
+        let path_a: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "images", "exif_a.jpg"].iter().collect();
+        let path_b: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "images", "exif_b.jpg"].iter().collect();
+        let files = vec![path_a, path_b];
+
+        let differences = compare_exif(&files);
+        let fields: Vec<&str> = differences.iter().map(|difference| difference.field).collect();
+        assert_eq!(fields, vec!["date_taken", "camera_model", "gps", "rating"]);
+
+        let model = &differences[1];
+        assert_eq!(model.values[0], (files[0].clone(), Some(String::from("Canon EOS 5D"))));
+        assert_eq!(model.values[1], (files[1].clone(), Some(String::from("Canon EOS 6D"))));
+
+        let gps = &differences[2];
+        assert_eq!(gps.values[0].1, Some(String::from("51.500000, -0.125000")));
+        assert_eq!(gps.values[1].1, None);
+    }
+
+    #[test]
+    fn test_compare_exif_same() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "images", "exif_a.jpg"].iter().collect();
+        let files = vec![path.clone(), path];
+
+        assert_eq!(compare_exif(&files).len(), 0);
+    }
+
+    #[test]
+    fn test_read_exif_summary_none() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let summary = read_exif_summary(path);
+        assert!(summary.is_ok(), "no io error expected");
+        assert!(summary.unwrap().is_none(), "no exif data expected");
+    }
+
+    #[test]
+    fn test_read_exif_summary_error() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "images", "doesnotexist.jpg"].iter().collect();
+        assert!(read_exif_summary(path).is_err(), "io error expected");
+    }
+}
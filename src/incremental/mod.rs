@@ -0,0 +1,63 @@
+use std::{collections::{HashMap, HashSet}, fs::Metadata, path::{Path, PathBuf}, sync::{Mutex, MutexGuard}, time::SystemTime};
+
+// Match keys produced by earlier runs of a `DupeFinder`, kept with the size and modification
+// time of the file so unchanged files are not hashed again, see `DupeFinderBuilder::incremental`
+#[derive(Default)]
+pub struct HashMemo {
+    entries: Mutex<HashMap<PathBuf, (u64, SystemTime, String)>>,
+}
+
+impl HashMemo {
+    pub fn get(&self, path: &Path, meta: &Metadata) -> Option<String> {
+        let modified = meta.modified().ok()?;
+        match self.lock().get(path) {
+            Some((size, time, hash)) if *size == meta.len() && *time == modified => Some(hash.clone()),
+            _ => None,
+        }
+    }
+
+    // files whose modification time can not be read are never kept
+    pub fn insert(&self, path: &Path, meta: &Metadata, hash: &str) {
+        if let Ok(modified) = meta.modified() {
+            self.lock().insert(path.to_path_buf(), (meta.len(), modified, hash.to_string()));
+        }
+    }
+
+    // forgets files that were removed or are no longer searched
+    pub fn retain(&self, seen: &HashSet<&Path>) {
+        self.lock().retain(|path, _| seen.contains(path.as_path()));
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, (u64, SystemTime, String)>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::{fs, time::Duration};
+
+    #[test]
+    fn test_memo_checks_metadata() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("a.txt");
+        fs::write(&file, "12345").unwrap();
+        let memo = HashMemo::default();
+
+        memo.insert(&file, &fs::metadata(&file).unwrap(), "ABC");
+        assert_eq!(memo.get(&file, &fs::metadata(&file).unwrap()), Some(String::from("ABC")));
+
+        fs::File::options().write(true).open(&file).unwrap().set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        assert_eq!(memo.get(&file, &fs::metadata(&file).unwrap()), None, "modified file should miss");
+
+        memo.retain(&HashSet::new());
+        assert_eq!(memo.len(), 0);
+    }
+}
@@ -9,23 +9,39 @@
 //! folders and grouping together all files with the same size in bytes.
 //! Once sizes with multiple file entries are located, the file contents are 
 //! hashed via XXH3 / xxHash and compared to the hash of other same-size files.
+//! Other algorithms can be plugged in through the `FileHasher` trait.
 //! 
 //! If only a single file of a certain size is found that file is not read and is skipped.
 //! This does read the entire file contents from disk while generating the hash.
+//! `DupeFinderBuilder::partial_hash` hashes only the leading bytes of same-size files first, so files differing early on are not read in full.
 //! 
 //! Hashing makes use of a BufReader to incrementally read large files
 //! which should prevent having to read the entirety of a file into memory at once to generate the hash.
 //! 
 //! If a matching hash is found, a duplicate file has been found and will be returned.
+//! `DupeFinderBuilder::verify_contents` additionally compares the files byte by byte before reporting them.
 //! 
 //! Matching can be run more than once on a single `DupeChecker` via `.run()`, this is a full re-check
 //! of all folders with the assumption file contents may have changed not just the presence of files.
 //! 
 //! Matching will actively skip (continue) past problems. Warnings are emitted via the `log` crate
-//! when such problems arise but they are otherwise not reported. Due to the support for multiple directories
-//! and large file quantities stopping on a specific error was not desired.
+//! when such problems arise and every file that could not be read is listed by `ScanResults::errors()`.
+//! Due to the support for multiple directories and large file quantities stopping on a specific error was not desired
+//! by default, `DupeFinderBuilder::strict` with `.try_run()` stops at the first one instead.
+//! Messages are grouped into categories (`LogCategory`) logged under their own `dupefinder::*` targets,
+//! per category verbosity and collecting messages on the `DupeFinder` can be configured on the builder.
+//! Files left out for a specific reason, such as a stat or read stalling past the configured hard timeout,
+//! are listed by `.skipped_files()`.
 //! 
 //! There is an additional `.run_for_file()` mode that will only search for duplicates of a specific file.
+//!
+//! `DupeFinder::with_reference()` only reports files in the target directories that duplicate a file in the reference directories.
+//! `DupeFinder::contains()` checks whether every file of one tree has a copy in another.
+//!
+//! `.run_iter()` yields duplicates lazily as each group of same-size files is hashed instead of waiting for the full scan.
+//! `.run_with_events()` sends each step of a run as a `ScanEvent` over a channel, for front ends showing their own progress.
+//!
+//! `ScanResults::stats()` summarizes a run: files scanned and hashed, bytes read, duplicate bytes and reclaimable space.
 //! 
 //! # Examples
 //! ```
@@ -38,19 +54,130 @@
 //! let results_recursive = checker_recursive.run();
 //! // find if specific file has duplicates
 //! let results = checker.run_for_file(String::from("./test.txt"));
+//! // composed configuration
+//! let checker_built = dupefinder::DupeFinder::builder(vec![String::from("./")])
+//!     .recursive(true)
+//!     .build();
 //! ```
 
-use std::{collections::{HashMap, HashSet}, fs, io};
+use std::{borrow::Cow, collections::{hash_map::Entry, HashMap, HashSet}, fs::{self, DirEntry}, io::{self, Read}, path::{Path, PathBuf}, sync::Arc, time::Duration};
+use budget::BudgetUsage;
 use dirdata::DirData;
 use findfile::FindFile;
-use log::warn;
+use hardlinks::HardlinkIndex;
+use hasher::{hash_counted, isolate_panics};
+use incremental::HashMemo;
+use log::Level;
+use logging::ScanLogger;
+use options::Options;
+use quarantine::{device_id, DeviceQuarantine};
+use reference::split_references;
+use errors::ErrorList;
+use events::EventSender;
+use skipped::{is_special_file, SkipList};
+use space::SpaceForecast;
+use stats::ScanCounters;
+use strategy::PrefixStrategy;
+pub use action::{Action, ActionReport, Operation, RenameTemplate};
+pub use budget::HashOrder;
+pub use builder::{ConfigError, DupeFinderBuilder};
+pub use config::DupeFinderConfig;
+pub use containment::ContainmentReport;
+pub use hardlinks::HardlinkPolicy;
 pub use hashable::Hashable;
-pub use duplicate::Duplicate;
-
+pub use hasher::{FileHasher, Xxh3Hasher};
+pub use logging::{LogCategory, LogMessage};
+pub use options::SymlinkPolicy;
+#[cfg(feature = "blake3")]
+pub use hasher::Blake3Hasher;
+#[cfg(feature = "sha2")]
+pub use hasher::Sha256Hasher;
+pub use delta::{estimate_delta, DeltaStats};
+#[cfg(feature = "decompress")]
+pub use decompress::DecompressStrategy;
+pub use filter::WalkDecision;
+pub use dupeiter::DupeIter;
+pub use naming::{copy_marker, CopyMarker};
+pub use duplicate::{Confidence, DupeEntry, Duplicate};
+pub use errors::{DupeFinderError, ScanError};
+pub use events::ScanEvent;
+pub use plan::{DedupePlan, DedupePlanBuilder, KeepPolicy, PlannedSet};
+pub use preview::{Preview, PreviewOptions};
+pub use remote::{HashManifest, SizeManifest};
+pub use results::ScanResults;
+pub use risk::{RiskFactor, RiskOptions, RiskScore};
+pub use roots::RootStats;
+pub use strategy::{ContentStrategy, MatchStrategy};
+pub use treemap::TreemapNode;
+pub use skipped::{SkipReason, SkippedFile, SpecialFilePolicy};
+pub use stats::ScanStats;
+#[cfg(feature = "progress")]
+pub use progress::ScanProgress;
+#[cfg(feature = "notify")]
+pub use watch::{DupeWatcher, WatchEvent};
+#[cfg(feature = "exif")]
+pub use imagemeta::{compare_exif, read_exif_summary, ExifDifference, ExifSummary};
+
+pub mod report;
+// entry points of crate private decoders for the cargo-fuzz targets in `fuzz/`
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod fuzzing;
+
+mod action;
+#[cfg(any(feature = "zip", feature = "tar"))]
+mod archive;
+mod budget;
+mod builder;
+mod config;
+#[cfg(feature = "cache")]
+mod cache;
+mod containment;
+#[cfg(feature = "decompress")]
+mod decompress;
+mod hardlinks;
 mod hashable;
+mod hasher;
+mod incremental;
+mod limits;
 mod dirdata;
+mod delta;
+mod dupeiter;
 mod duplicate;
+mod errors;
+mod events;
+mod filter;
 mod findfile;
+mod logging;
+#[cfg(feature = "gitignore")]
+mod gitignore;
+#[cfg(feature = "mail")]
+mod mail;
+mod naming;
+mod options;
+mod plan;
+mod preview;
+#[cfg(feature = "progress")]
+mod progress;
+mod remote;
+mod results;
+mod quarantine;
+mod reference;
+mod risk;
+mod roots;
+mod shard;
+mod skipped;
+mod space;
+mod stats;
+mod strategy;
+mod timeout;
+mod throttle;
+mod treemap;
+mod verify;
+#[cfg(feature = "notify")]
+mod watch;
+#[cfg(feature = "exif")]
+mod imagemeta;
 
 /// Searches for duplicate files in the provided directories / subdirectories
 ///
@@ -63,9 +190,9 @@ mod findfile;
 /// for key in results.keys() {
 ///     let result = results.get(key);
 ///     if let Some(details) = result {
-///         println!("{} files of size {} bytes found with hash {}", details.files.len(), details.size, details.hash);
-///         for file in details.files.iter() {
-///             println!("{}", file);
+///         println!("{} files of size {} bytes found with hash {}", details.entries.len(), details.size, details.hash);
+///         for file in details.paths() {
+///             println!("{}", file.display());
 ///         }
 ///     }
 /// }
@@ -79,9 +206,9 @@ mod findfile;
 /// for key in results.keys() {
 ///     let result = results.get(key);
 ///     if let Some(details) = result {
-///         println!("{} files of size {} bytes found with hash {}", details.files.len(), details.size, details.hash);
-///         for file in details.files.iter() {
-///             println!("{}", file);
+///         println!("{} files of size {} bytes found with hash {}", details.entries.len(), details.size, details.hash);
+///         for file in details.paths() {
+///             println!("{}", file.display());
 ///         }
 ///     }
 /// }
@@ -95,7 +222,7 @@ mod findfile;
 /// if let Ok(results) = results {
 ///     match results {
 ///        Some(duplicate) => {
-///            println!("{} files found", duplicate.files.len());
+///            println!("{} files found", duplicate.entries.len());
 ///         },
 ///        None => {
 ///            println!("no matching files found");
@@ -104,123 +231,696 @@ mod findfile;
 /// };
 /// ```
 pub struct DupeFinder {
-    directories: Vec<String>,
+    directories: Vec<PathBuf>,
+    // files searched without traversal, see `from_files`
+    files: Vec<PathBuf>,
     file_sizes: HashMap<u64, Vec<DirData>>,
-    checked_directories: HashSet<String>,
+    checked_directories: HashSet<PathBuf>,
     duplicate_file_sizes: HashSet<u64>,
-    follow_subdirs: bool,
+    options: Options,
     find_file: Option<FindFile>,
+    logger: ScanLogger,
+    skipped: SkipList,
+    errors: ErrorList,
+    // channel of a running `run_with_events`
+    events: EventSender,
+    quarantine: DeviceQuarantine,
+    hardlinks: HardlinkIndex,
+    // traversed directories whose files are references, see `with_reference`
+    reference_dirs: HashSet<PathBuf>,
+    counters: ScanCounters,
+    // match keys of earlier runs, only kept with `DupeFinderBuilder::incremental`
+    memo: HashMemo,
+    // work counted against `DupeFinderBuilder::max_files` and `max_bytes`
+    budget: BudgetUsage,
+    // rules of the traversed directories, only read with `DupeFinderBuilder::respect_gitignore`
+    #[cfg(feature = "gitignore")]
+    ignore_rules: gitignore::IgnoreRules,
+    // first copy of the searched file, only set by `DupeFinderBuilder::find_first` runs
+    first_match: Option<Duplicate>,
 }
 
 impl DupeFinder {
     /// Initializes DupeFinder and provides the runnable checker
-    pub fn new(directories: Vec<String>) -> DupeFinder {
-        DupeFinder {
-            directories: directories,
-            file_sizes: HashMap::new(),
-            checked_directories: HashSet::<String>::new(),
-            duplicate_file_sizes: HashSet::new(),
-            follow_subdirs: false,
-            find_file: None,
-        }
+    pub fn new<I, P>(directories: I) -> DupeFinder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        DupeFinder::with_options(to_path_bufs(directories), Options::default())
     }
 
     /// Initializes DupeFinder set to recursively traverse all subdirectories
-    pub fn new_recursive(directories: Vec<String>) -> DupeFinder {
+    pub fn new_recursive<I, P>(directories: I) -> DupeFinder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        DupeFinder::with_options(to_path_bufs(directories), Options { follow_subdirs: true, ..Options::default() })
+    }
+
+    /// Initializes DupeFinder to find files in `target_dirs` that duplicate a file in `reference_dirs`
+    ///
+    /// Reference files are never reported as duplicates, each reported `Duplicate` lists only target
+    /// files and `ScanResults::references` lists the reference files it matched. Targets that only
+    /// duplicate other targets are not reported. Files belong to the nearest provided directory
+    /// above them, so a target directory may be nested inside a reference directory and vice versa. Directories are not searched recursively, see
+    /// `DupeFinderBuilder::reference_directories` to combine references with other options.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::with_reference(vec!["./resources/base"], vec!["./resources/dupes"]);
+    /// let results = checker.run();
+    /// for duplicate in results.values() {
+    ///     println!("{} files already exist in the reference set", duplicate.entries.len());
+    /// }
+    /// ```
+    pub fn with_reference<I, P, J, Q>(reference_dirs: I, target_dirs: J) -> DupeFinder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+        J: IntoIterator<Item = Q>,
+        Q: AsRef<Path>,
+    {
+        let references = to_path_bufs(reference_dirs);
+        let mut directories = references.clone();
+        directories.append(&mut to_path_bufs(target_dirs));
+
+        DupeFinder::with_options(directories, Options { references, ..Options::default() })
+    }
+
+    /// Initializes DupeFinder to search only `files`, such as paths piped from `find` or taken from
+    /// a database. No directory is traversed, the files go straight to size grouping and hashing.
+    /// Paths listed more than once are searched once, symlinks are resolved.
+    /// `DupeFinderBuilder::from_files` combines this with other options.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::from_files(["./resources/dupes/a.txt", "./resources/dupes/b.txt", "./resources/base/a.txt"]);
+    /// let results = checker.run();
+    /// assert_eq!(results.len(), 1);
+    /// ```
+    pub fn from_files<I, P>(files: I) -> DupeFinder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        DupeFinder { files: to_path_bufs(files), ..DupeFinder::with_options(Vec::new(), Options::default()) }
+    }
+
+    /// Starts a `DupeFinderBuilder` to compose further options
+    pub fn builder<I, P>(directories: I) -> DupeFinderBuilder
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        DupeFinderBuilder::new(directories)
+    }
+
+    pub(crate) fn with_options(directories: Vec<PathBuf>, options: Options) -> DupeFinder {
         DupeFinder {
-            directories: directories,
+            directories,
+            files: Vec::new(),
             file_sizes: HashMap::new(),
-            checked_directories: HashSet::<String>::new(),
+            checked_directories: HashSet::<PathBuf>::new(),
             duplicate_file_sizes: HashSet::new(),
-            follow_subdirs: true,
+            options,
             find_file: None,
+            logger: ScanLogger::default(),
+            skipped: SkipList::default(),
+            errors: ErrorList::default(),
+            events: EventSender::default(),
+            quarantine: DeviceQuarantine::default(),
+            hardlinks: HardlinkIndex::default(),
+            reference_dirs: HashSet::new(),
+            counters: ScanCounters::default(),
+            memo: HashMemo::default(),
+            budget: BudgetUsage::default(),
+            #[cfg(feature = "gitignore")]
+            ignore_rules: gitignore::IgnoreRules::default(),
+            first_match: None,
         }
     }
 
     // iterates through user provided directories and subdirectories
     // to build `file_sizes` map and mark entries with multiple sizes
     fn build_directories(&mut self) {
-        let mut check_dirs = self.directories.clone();
+        let roots = self.root_directories();
+        let mut check_dirs: Vec<PathBuf> = Vec::new();
+        for directory in self.directories.iter() {
+            if !check_dirs.contains(&roots[directory]) {
+                check_dirs.push(roots[directory].clone());
+            }
+        }
+        let check_roots: HashSet<PathBuf> = check_dirs.iter().cloned().collect();
+        self.reference_dirs = self.options.references.iter().map(|reference| roots.get(reference).unwrap_or(reference).clone()).collect();
+        let mut depth: usize = 0;
+        // resolved directories already searched when following directory links
+        let mut canonical_dirs: HashSet<PathBuf> = HashSet::new();
         
-        while check_dirs.len() > 0 {
-            let mut next_directories: Vec<String> = Vec::new();
+        while !check_dirs.is_empty() {
+            // the requested recursion depth is reached, this is not a skip
+            if self.options.max_depth.is_some_and(|max| depth > max) {
+                break;
+            }
+
+            if self.options.limits.too_deep(depth) {
+                for directory in check_dirs.iter().filter(|directory| !self.checked_directories.contains(*directory)) {
+                    self.skip(directory, SkipReason::TooDeep);
+                }
+                break;
+            }
+
+            let mut next_directories: Vec<PathBuf> = Vec::new();
 
             for directory in check_dirs {
+                if self.traversal_done() {
+                    return;
+                }
+
                 if self.checked_directories.contains(&directory) {
                     continue
                 }
     
-                self.checked_directories.insert(directory.to_string());
+                self.checked_directories.insert(directory.clone());
+
+                if self.options.symlinks == SymlinkPolicy::FollowAll {
+                    if let Ok(canonical) = fs::canonicalize(&directory) {
+                        if is_symlink_loop(&directory, &canonical) {
+                            self.log(LogCategory::Traversal, Level::Warn, format_args!("Symlink loop: {} leads back to {}; skipped.", directory.display(), canonical.display()));
+                            self.skip(&directory, SkipReason::SymlinkLoop);
+                            continue;
+                        }
+
+                        if !canonical_dirs.insert(canonical) {
+                            self.log(LogCategory::Traversal, Level::Debug, format_args!("Directory already searched through another path: {}; skipped.", directory.display()));
+                            continue;
+                        }
+                    }
+                }
     
+                self.events.send(|| ScanEvent::DirEntered(directory.clone()));
                 match self.build_directory_contents(&directory) {
                     Ok(mut next) =>  {
+                        // subdirectories belong to the origin of their parent unless provided themselves
+                        if self.reference_dirs.contains(&directory) {
+                            let inherited: Vec<PathBuf> = next.iter().filter(|subdir| !check_roots.contains(*subdir)).cloned().collect();
+                            self.reference_dirs.extend(inherited);
+                        }
                         next_directories.append(&mut next);
                     },
                     Err(e) => {
-                        warn!("An error building directory contents: {};", e);
+                        self.log(LogCategory::Traversal, Level::Warn, format_args!("An error building directory contents: {};", e));
+                        self.record_error(&directory, LogCategory::Traversal, &e);
                         continue;
                     }
                 }
             }
             
             check_dirs = next_directories;
+            depth += 1;
+        }
+
+        self.insert_files();
+
+        // only a full traversal tells which files are gone, find file mode keeps files of one size only
+        if self.options.incremental && self.find_file.is_none() && !self.traversal_done() {
+            let seen: HashSet<&Path> = self.file_sizes.values().flatten().map(|data| data.path.as_path()).collect();
+            self.memo.retain(&seen);
         }
     }
 
-    fn check_path_duplicates(&self, paths: &Vec<DirData>, results: &mut HashMap<String, Duplicate>,) {
-        // holds Hash -> Path values, if a hash is re-inserted here we know it is a dupe
-        let mut known_hashes: HashMap<String, String> = HashMap::new();
+    // takes in the files provided without a directory, files of traversed directories are already known
+    fn insert_files(&mut self) {
+        let files = std::mem::take(&mut self.files);
+        let mut seen: HashSet<&Path> = HashSet::new();
 
-        // entry @ 0 of paths in a find_file situation will be the original file
-        // we will skip it and insert our known hash to avoid re-reading the file
-        let iterator = match &self.find_file {
-            Some(find_file) => {
-                known_hashes.insert(find_file.hash.clone(), find_file.data.path.display().to_string());
-                paths.iter().skip(1)
-            },
-            None => paths.iter().skip(0)
-        };
+        for path in files.iter() {
+            if self.traversal_done() {
+                break;
+            }
+
+            let traversed = path.parent().is_some_and(|directory| self.checked_directories.contains(directory));
+            if traversed || !seen.insert(path) {
+                continue;
+            }
+
+            let data = match DirData::new_from_path(path) {
+                Ok(data) => data,
+                Err(e) => {
+                    self.log(LogCategory::Metadata, Level::Warn, format_args!("An error getting path / metadata: {}; skipped.", e));
+                    self.record_error(path, LogCategory::Metadata, &e);
+                    continue;
+                }
+            };
+
+            if self.should_insert_size(&data, &mut Vec::new()) {
+                if let Some(key) = self.candidate_key(&data) {
+                    self.insert_size(key, data);
+                }
+            }
+        }
+
+        self.files = files;
+    }
+
+    // Maps every provided directory to the path it is searched under. Directories lying below another
+    // provided directory are spelled out below it, so traversal recognizes them as already searched
+    // whatever way they were written, directories provided twice map to the same path.
+    fn root_directories(&self) -> HashMap<PathBuf, PathBuf> {
+        let canonical: Vec<Option<PathBuf>> = self.directories.iter().map(|directory| fs::canonicalize(directory).ok()).collect();
+
+        self.directories.iter().zip(canonical.iter()).map(|(directory, resolved)| {
+            // the outermost provided directory holding this one, this one itself if there is none
+            let holder = resolved.as_ref().and_then(|resolved| {
+                self.directories.iter().zip(canonical.iter())
+                    .filter_map(|(other, other_resolved)| Some((other, other_resolved.as_ref()?)))
+                    .filter(|(_, other_resolved)| resolved.starts_with(other_resolved))
+                    .min_by_key(|(_, other_resolved)| other_resolved.components().count())
+                    .map(|(other, other_resolved)| (other, resolved.strip_prefix(other_resolved).unwrap_or(Path::new(""))))
+            });
+
+            let root = match holder {
+                Some((other, rest)) if rest.as_os_str().is_empty() => other.clone(),
+                Some((other, rest)) => other.join(rest),
+                None => directory.clone(),
+            };
+            (directory.clone(), root)
+        }).collect()
+    }
+
+    // the number of leading entries of a size group that are not hashed, entry @ 0
+    // of paths in a find_file situation will be the original file
+    fn skipped_entries(&self) -> usize {
+        match &self.find_file {
+            Some(_) => 1,
+            None => 0,
+        }
+    }
+
+    // hashes each candidate with the configured hasher, across the thread pool if one is configured,
+    // returned hashes are in the same order as `candidates`
+    fn hash_candidates(&self, candidates: &[&DirData]) -> Vec<Result<String, io::Error>> {
+        let stored: Vec<Option<String>> = candidates.iter().map(|data| self.stored_hash(data)).collect();
+        // files are taken from the byte budget in order before hashing starts, so the same
+        // files are left out whichever of them a thread pool finishes first
+        let budgeted: Vec<bool> = candidates.iter().zip(stored.iter())
+            .map(|(data, hash)| hash.is_some() || self.budget.take_bytes(&self.options.budget, data.size))
+            .collect();
+        let misses: Vec<&DirData> = candidates.iter().zip(stored.iter()).zip(budgeted.iter())
+            .filter(|((_, hash), budgeted)| hash.is_none() && **budgeted)
+            .map(|((data, _), _)| *data)
+            .collect();
+        let mut hashes = self.hash_candidates_with(&self.options.strategy, &misses, true).into_iter();
+
+        candidates.iter().zip(stored).zip(budgeted).map(|((data, stored), budgeted)| {
+            if let Some(hash) = stored {
+                return Ok(hash);
+            }
+
+            if !budgeted {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "scan budget exhausted"));
+            }
+
+            let hash = hashes.next().unwrap_or_else(|| Err(io::Error::other("candidate was not hashed")));
+            if let Ok(hash) = &hash {
+                self.counters.hashed(data.size);
+                self.store_hash(data, hash);
+            }
+            hash
+        }).collect()
+    }
+
+    // the match key of `data` kept from an earlier run, if the file is unchanged since
+    fn stored_hash(&self, data: &DirData) -> Option<String> {
+        if self.options.incremental {
+            if let Some(hash) = self.memo.get(&data.path, &data.meta) {
+                return Some(hash);
+            }
+        }
+
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.options.hash_cache {
+            let hash = self.cached_hash(cache, data);
+            if let (true, Some(hash)) = (self.options.incremental, &hash) {
+                self.memo.insert(&data.path, &data.meta, hash);
+            }
+            return hash;
+        }
+
+        None
+    }
+
+    fn store_hash(&self, data: &DirData, hash: &str) {
+        if self.options.incremental {
+            self.memo.insert(&data.path, &data.meta, hash);
+        }
+
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.options.hash_cache {
+            self.cache_hash(cache, data, hash);
+        }
+    }
+
+    // `announce` sends a `ScanEvent::FileHashed` for every file hashed, prefix hashes are not announced
+    fn hash_candidates_with(&self, strategy: &Arc<dyn MatchStrategy>, candidates: &[&DirData], announce: bool) -> Vec<Result<String, io::Error>> {
+        #[cfg(feature = "parallel")]
+        if let Some(pool) = &self.options.thread_pool {
+            use rayon::prelude::*;
+            return pool.install(|| candidates.par_iter().map(|data| self.hash_candidate(strategy, data, announce)).collect());
+        }
+
+        candidates.iter().map(|data| self.hash_candidate(strategy, data, announce)).collect()
+    }
+
+    fn hash_candidate(&self, strategy: &Arc<dyn MatchStrategy>, data: &DirData, announce: bool) -> Result<String, io::Error> {
+        if self.errors.stopped() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "scan stopped after an earlier error"));
+        }
+
+        let limit = self.options.device_error_limit;
+        if limit.is_some_and(|limit| self.quarantine.is_quarantined(limit, &data.meta)) {
+            self.skip(&data.path, SkipReason::DeviceQuarantined);
+            return Err(io::Error::other("device quarantined after repeated read errors"));
+        }
+
+        // wait before the first retry after a stale network file handle, growing with each attempt
+        const STALE_HANDLE_BACKOFF: Duration = Duration::from_millis(200);
 
-        for data in iterator {
-            let full_path = data.path.display().to_string();
+        let mut result = self.match_key(strategy, data);
+        for attempt in 1..=self.options.stale_handle_retries {
+            match &result {
+                Err(e) if e.kind() == io::ErrorKind::StaleNetworkFileHandle => {
+                    self.log(LogCategory::Hashing, Level::Info, format_args!("Stale file handle for file: {}; retrying ({}/{}).", data.path.display(), attempt, self.options.stale_handle_retries));
+                    std::thread::sleep(STALE_HANDLE_BACKOFF * attempt);
+                    result = self.match_key(strategy, data);
+                },
+                _ => break,
+            }
+        }
+
+        if let (Some(limit), Err(e)) = (limit, &result) {
+            if self.quarantine.record_error(limit, &data.meta, e) {
+                self.log(LogCategory::Hashing, Level::Error, format_args!("Read errors reached {} on the device of file: {}; skipping its remaining files.", limit, data.path.display()));
+            }
+        }
+
+        if self.options.strict && result.is_err() {
+            self.errors.stop();
+        }
+
+        if announce && result.is_ok() {
+            self.events.send(|| ScanEvent::FileHashed { path: data.path.clone(), size: data.size });
+        }
+
+        result
+    }
+
+    // a single attempt at the match key of `data`, under the configured timeouts if any
+    fn match_key(&self, strategy: &Arc<dyn MatchStrategy>, data: &DirData) -> Result<String, io::Error> {
+        if !self.options.timeouts.enabled() {
+            return isolate_panics(|| strategy.match_key(&data.path, self.options.hasher.as_ref()));
+        }
+
+        let on_slow = |waited| self.log_slow(LogCategory::Hashing, &data.path, waited);
+        let result = timeout::match_key(strategy.clone(), self.options.hasher.clone(), data.path.clone(), &self.options.timeouts, &on_slow);
+        self.record_timeout(&data.path, &result);
+        result
+    }
+
+    // reads the metadata of a directory entry, under the configured timeouts if any
+    fn dir_data(&self, entry: Result<DirEntry, io::Error>) -> Result<DirData, io::Error> {
+        if !self.options.timeouts.enabled() {
+            return DirData::new(entry);
+        }
+
+        let entry = entry?;
+        let path = entry.path();
+        let meta = timeout::metadata(entry, &self.options.timeouts, &|waited| self.log_slow(LogCategory::Metadata, &path, waited));
+        self.record_timeout(&path, &meta);
+        let meta = meta?;
+
+        Ok(DirData { size: meta.len(), path, meta })
+    }
 
-            let file_hash: String = match data.path.get_file_hash() {
+    fn record_timeout<T>(&self, path: &Path, result: &Result<T, io::Error>) {
+        if let Err(e) = result {
+            if e.kind() == io::ErrorKind::TimedOut {
+                self.skip(path, SkipReason::TimedOut);
+            }
+        }
+    }
+
+    // records a file or directory that could not be read, strict runs stop at the first one
+    fn record_error(&self, path: &Path, phase: LogCategory, error: &io::Error) {
+        self.events.send(|| ScanEvent::Error(ScanError::new(path, phase, error)));
+        self.errors.push(path, phase, error);
+        if self.options.strict {
+            self.errors.stop();
+        }
+    }
+
+    // the error of a file left alone because a strict run already stopped or the budget ran out
+    fn is_stopped_error(&self, error: &io::Error) -> bool {
+        (self.errors.stopped() || self.budget.truncated()) && error.kind() == io::ErrorKind::Interrupted
+    }
+
+    fn log_slow(&self, category: LogCategory, path: &Path, waited: Duration) {
+        self.log(category, Level::Warn, format_args!("Slow file operation: {}; no progress for {:?};", path.display(), waited));
+    }
+
+    // returns the hashed files of the group that have no duplicate
+    fn check_path_duplicates(&self, paths: &[DirData], hashes: Vec<Result<String, io::Error>>, results: &mut HashMap<String, Duplicate>,) -> Vec<PathBuf> {
+        // holds Hash -> file values, if a hash is re-inserted here we know it is a dupe
+        let mut known_hashes: HashMap<String, &DirData> = HashMap::new();
+
+        // the skipped original file in a find_file situation gets our known hash
+        // inserted to avoid re-reading the file
+        if let Some(find_file) = &self.find_file {
+            known_hashes.insert(find_file.hash.clone(), &find_file.data);
+        }
+
+        for (data, file_hash) in paths.iter().skip(self.skipped_entries()).zip(hashes) {
+            let file_hash: String = match file_hash {
                 Ok(hash) => hash,
+                Err(e) if self.is_stopped_error(&e) => continue,
                 Err(e) => {
-                    warn!("Error generating file hash for file: {}; error: {}", full_path, e);
+                    self.log(LogCategory::Hashing, Level::Warn, format_args!("Error generating file hash for file: {}; error: {}", data.path.display(), e));
+                    self.record_error(&data.path, LogCategory::Hashing, &e);
                     continue;
                 }
             };
 
             // if the hash already exists we will get a Some() value with the old entry
-            let exists = known_hashes.insert(file_hash.clone(), full_path.clone());
+            let exists = known_hashes.insert(file_hash.clone(), data);
 
             if let Some(existing_file) = exists {
+                let entry = DupeEntry::new(data.path.clone(), &data.meta);
                 if results.contains_key(&file_hash) {
-                    results.entry(file_hash).and_modify(|entry| entry.files.push(full_path.clone()));
+                    results.entry(file_hash).and_modify(|duplicate| duplicate.entries.push(entry));
                 } else {
                     results.insert(file_hash.clone(), Duplicate { 
                         hash: file_hash, 
-                        files: vec![existing_file.clone(), full_path.clone()], 
-                        size: data.meta.len()
+                        entries: vec![DupeEntry::new(existing_file.path.clone(), &existing_file.meta), entry], 
+                        size: data.meta.len(),
+                        confidence: self.options.strategy.confidence(),
                     });
                 }
             }
         }
+
+        let unique = known_hashes.iter()
+            .filter(|(hash, _)| !results.contains_key(*hash))
+            .map(|(_, data)| data.path.clone())
+            .collect();
+
+        if self.options.verify_contents {
+            for hash in known_hashes.keys() {
+                if results.get_mut(hash).is_some_and(|duplicate| !self.verify_duplicate(duplicate)) {
+                    results.remove(hash);
+                }
+            }
+        }
+
+        unique
+    }
+
+    // Compares the files of a set byte by byte and narrows it down to the files identical to each other,
+    // in find file mode the files identical to the searched file, otherwise the largest such group.
+    // Returns false when no two identical files are left.
+    fn verify_duplicate(&self, duplicate: &mut Duplicate) -> bool {
+        let mut groups: Vec<Vec<DupeEntry>> = Vec::new();
+        'files: for file in duplicate.entries.drain(..) {
+            for group in groups.iter_mut() {
+                match verify::same_contents(&group[0].path, &file.path, self.options.read_throttle.as_deref()) {
+                    Ok(true) => {
+                        group.push(file);
+                        continue 'files;
+                    },
+                    Ok(false) => continue,
+                    Err(e) => {
+                        self.log(LogCategory::Hashing, Level::Warn, format_args!("Error comparing file contents for file: {}; error: {}; skipped.", file.path.display(), e));
+                        self.record_error(&file.path, LogCategory::Hashing, &e);
+                        continue 'files;
+                    }
+                }
+            }
+
+            groups.push(vec![file]);
+        }
+
+        let keep = match self.find_file {
+            Some(_) => 0,
+            None => groups.iter().enumerate().max_by_key(|(index, group)| (group.len(), std::cmp::Reverse(*index))).map_or(0, |(index, _)| index),
+        };
+
+        for (index, group) in groups.iter().enumerate() {
+            if index != keep {
+                for file in group.iter() {
+                    self.log(LogCategory::Hashing, Level::Warn, format_args!("Contents differ despite matching hash: {}; skipped.", file.path.display()));
+                }
+            }
+        }
+
+        duplicate.entries = groups.into_iter().nth(keep).unwrap_or_default();
+        duplicate.confidence = Confidence::ByteVerified;
+        duplicate.entries.len() > 1
     }
 
     // iterates through known sizes with multiple entries (`duplicate_file_sizes`)
     // and checks for dupes
-    fn check_duplicates(&mut self, results: &mut HashMap<String, Duplicate>,) {
-        for key in self.duplicate_file_sizes.iter() {
-            let paths_o = self.file_sizes.get(key);
+    fn check_duplicates(&self, results: &mut HashMap<String, Duplicate>,) -> Vec<PathBuf> {
+        let mut groups: Vec<&[DirData]> = Vec::new();
+        for key in self.duplicate_keys() {
+            let paths_o = self.file_sizes.get(&key);
             if let Some(paths) = paths_o {
-                self.check_path_duplicates(paths, results);
+                groups.push(paths);
             } else {
-                warn!("Error getting path data for key: {};", key);
+                self.log(LogCategory::Hashing, Level::Warn, format_args!("Error getting path data for key: {};", key));
+                continue;
+            }
+        }
+
+        let mut unique: Vec<PathBuf> = Vec::new();
+        if let Some(max) = self.options.budget.max_groups {
+            self.check_duplicates_until(groups, max, results, &mut unique);
+            return unique;
+        }
+
+        let groups = self.prefilter(groups, &mut unique);
+
+        // all candidates are hashed in one batch so parallel hashing is not limited
+        // to the size of a single group
+        let skip = self.skipped_entries();
+        let candidates: Vec<&DirData> = groups.iter().flat_map(|paths| paths.iter().skip(skip)).collect();
+        let mut hashes = self.hash_candidates(&candidates).into_iter();
+
+        for paths in groups.iter() {
+            let group_hashes = hashes.by_ref().take(paths.len().saturating_sub(skip)).collect();
+            unique.append(&mut self.check_path_duplicates(paths, group_hashes, results));
+        }
+
+        unique
+    }
+
+    // keys of the groups with more than one file, in the configured `HashOrder`
+    fn duplicate_keys(&self) -> Vec<u64> {
+        let mut keys: Vec<u64> = self.duplicate_file_sizes.iter().copied().collect();
+        self.options.hash_order.sort(&mut keys, &self.file_sizes);
+        keys
+    }
+
+    // hashes one group at a time until `max` duplicate sets are found, sets beyond it are dropped
+    fn check_duplicates_until(&self, groups: Vec<&[DirData]>, max: usize, results: &mut HashMap<String, Duplicate>, unique: &mut Vec<PathBuf>) {
+        let skip = self.skipped_entries();
+        for group in groups {
+            if results.len() >= max {
+                self.log(LogCategory::Hashing, Level::Info, format_args!("Found {} duplicate sets; hashing stopped.", max));
+                self.budget.truncate();
+                break;
+            }
+
+            for paths in self.prefilter(vec![group], unique) {
+                let candidates: Vec<&DirData> = paths.iter().skip(skip).collect();
+                let hashes = self.hash_candidates(&candidates);
+                unique.append(&mut self.check_path_duplicates(&paths, hashes, results));
+            }
+        }
+
+        // a single group can hold several sets
+        if results.len() > max {
+            let mut hashes: Vec<String> = results.keys().cloned().collect();
+            hashes.sort();
+            for hash in hashes.into_iter().skip(max) {
+                results.remove(&hash);
+            }
+            self.budget.truncate();
+        }
+    }
+
+    // With a partial hash configured, hashes the leading bytes of every file in `groups` and leaves
+    // out files whose leading bytes are not shared with another file of their group, these are added
+    // to `unique`. Groups of files no larger than the partial hash are kept as they are, the order
+    // of `groups` is kept. Groups holding a file with a stored full hash are kept as they are as well,
+    // their files are told apart by full hashes without reading any leading bytes.
+    fn prefilter<'a>(&self, groups: Vec<&'a [DirData]>, unique: &mut Vec<PathBuf>) -> Vec<Cow<'a, [DirData]>> {
+        let bytes = match self.options.partial_hash {
+            Some(bytes) => bytes,
+            None => return groups.into_iter().map(Cow::Borrowed).collect(),
+        };
+
+        let filtered: Vec<bool> = groups.iter()
+            .map(|paths| paths.iter().all(|data| data.size > bytes) && paths.iter().all(|data| self.stored_hash(data).is_none()))
+            .collect();
+        let candidates: Vec<&DirData> = groups.iter().zip(filtered.iter()).filter(|(_, filtered)| **filtered).flat_map(|(paths, _)| paths.iter()).collect();
+        let prefixes = self.hash_candidates_with(&(Arc::new(PrefixStrategy { bytes, strategy: self.options.strategy.clone() }) as Arc<dyn MatchStrategy>), &candidates, false);
+        for (data, prefix) in candidates.iter().zip(prefixes.iter()) {
+            if prefix.is_ok() {
+                self.counters.read(data.size.min(bytes));
+            }
+        }
+        let mut prefixes = prefixes.into_iter();
+
+        let skip = self.skipped_entries();
+        let mut kept: Vec<Cow<'a, [DirData]>> = Vec::new();
+        for (paths, filtered) in groups.into_iter().zip(filtered) {
+            if !filtered {
+                kept.push(Cow::Borrowed(paths));
                 continue;
             }
+
+            let group_prefixes: Vec<Option<String>> = prefixes.by_ref().take(paths.len())
+                .zip(paths.iter())
+                .map(|(prefix, data)| match prefix {
+                    Ok(prefix) => Some(prefix),
+                    Err(e) if self.is_stopped_error(&e) => None,
+                    Err(e) => {
+                        self.log(LogCategory::Hashing, Level::Warn, format_args!("Error generating partial hash for file: {}; error: {}", data.path.display(), e));
+                        self.record_error(&data.path, LogCategory::Hashing, &e);
+                        None
+                    }
+                })
+                .collect();
+
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for prefix in group_prefixes.iter().flatten() {
+                *counts.entry(prefix).or_default() += 1;
+            }
+
+            let mut group: Vec<DirData> = Vec::new();
+            for (index, (data, prefix)) in paths.iter().zip(group_prefixes.iter()).enumerate() {
+                match prefix {
+                    // the searched file of find file mode is always kept
+                    _ if index < skip => group.push(data.clone()),
+                    Some(prefix) if counts[prefix.as_str()] > 1 => group.push(data.clone()),
+                    Some(_) => unique.push(data.path.clone()),
+                    None => {},
+                }
+            }
+            kept.push(Cow::Owned(group));
         }
+
+        kept
     }
 
     // If this object has already been .run() we need to reset 
@@ -228,7 +928,18 @@ impl DupeFinder {
     // may have changed between .run()'s, not just the presence of files
     // so we perform a full search again
     fn initialize(&mut self) {
-        if self.checked_directories.len() > 0 {
+        self.logger.clear();
+        self.skipped.clear();
+        self.errors.clear();
+        self.quarantine.clear();
+        self.hardlinks.clear();
+        self.counters.clear();
+        self.budget.clear();
+        self.first_match = None;
+        #[cfg(feature = "gitignore")]
+        self.ignore_rules.clear();
+
+        if !self.checked_directories.is_empty() || !self.file_sizes.is_empty() {
             self.file_sizes = HashMap::new();
             self.checked_directories = HashSet::new();
             self.duplicate_file_sizes = HashSet::new();
@@ -240,19 +951,76 @@ impl DupeFinder {
     // into `duplicate_file_sizes` if any other files with the same size exist
     fn insert_find_file_size(&mut self) {
         if let Some(find_file) = &self.find_file {
-            self.file_sizes.insert(find_file.data.size, vec![find_file.data.clone()]);
+            if self.options.hardlinks != HardlinkPolicy::Keep {
+                self.hardlinks.insert(&find_file.data.path, &find_file.data.meta);
+            }
+            self.file_sizes.insert(find_file.key, vec![find_file.data.clone()]);
         };
     }
 
+    /// Runs the search for files of `size` bytes whose hash is `hash`, e.g. taken from an earlier report
+    /// or a `HashManifest`, without the original file on disk. Hashes are compared case insensitively
+    /// and must come from the same hasher and strategy.
+    ///
+    /// The resulting `Duplicate` lists every matching file, `None` if there is none.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+    /// if let Some(found) = checker.run_for_hash(100, "1577245F909F3D4619DDA56A7B4BA1AF") {
+    ///     println!("{} copies found", found.entries.len());
+    /// }
+    /// ```
+    pub fn run_for_hash(&mut self, size: u64, hash: &str) -> Option<Duplicate> {
+        self.initialize();
+        self.build_directories();
+
+        let entries: Vec<DupeEntry> = self.hash_files_of_size(size).into_iter()
+            .filter(|(file_hash, _)| file_hash.eq_ignore_ascii_case(hash))
+            .map(|(_, entry)| entry)
+            .collect();
+
+        if entries.is_empty() {
+            return None;
+        }
+
+        Some(Duplicate { hash: hash.to_ascii_uppercase(), entries, size, confidence: self.options.strategy.confidence() })
+    }
+
+    /// Runs the search for files with the same contents as everything `reader` produces, e.g. an
+    /// upload that has not been written to disk yet. A byte slice can be passed as is.
+    ///
+    /// Matches by size and hash like `run_for_hash`, so custom match strategies are not applied to the
+    /// content. The resulting `Duplicate` lists every matching file, `None` if there is none.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+    /// let upload: &[u8] = b"uploaded contents";
+    /// if let Ok(Some(found)) = checker.run_for_content(upload) {
+    ///     println!("already stored as {:?}", found.paths().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn run_for_content<R: Read>(&mut self, mut reader: R) -> Result<Option<Duplicate>, io::Error> {
+        let (size, hash) = hash_counted(self.options.hasher.as_ref(), &mut reader)?;
+
+        Ok(self.run_for_hash(size, &hash))
+    }
+
     /// Runs the search to find if any duplicates of a specific file exist
     /// The resulting `Duplicate` will contain the original file if duplicates exist
-    pub fn run_for_file(&mut self, path: String) -> Result<Option<Duplicate>, io::Error> {
+    pub fn run_for_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<Duplicate>, DupeFinderError> {
         self.initialize();
-        self.find_file = Some(FindFile::new(path)?);
+        self.find_file = Some(FindFile::new(path, self.options.strategy.as_ref(), self.options.hasher.as_ref())?);
         self.insert_find_file_size();
 
         self.build_directories();
 
+        // every candidate was already compared while traversing
+        if self.options.find_first {
+            return Ok(self.first_match.take());
+        }
+
         // dupes will be added to this map and returned
         let mut dupes: HashMap<String, Duplicate> = HashMap::new();
         self.check_duplicates(&mut dupes);
@@ -273,8 +1041,100 @@ impl DupeFinder {
         Ok(None)
     }
 
+    /// Runs the search like `run`, with `DupeFinderBuilder::strict` the first file or directory
+    /// that could not be read is returned as the error instead of partial results
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::builder(vec!["./resources/dupes"]).strict(true).build().unwrap();
+    /// match checker.try_run() {
+    ///     Ok(results) => println!("{} duplicate sets", results.len()),
+    ///     Err(e) => eprintln!("scan failed: {}", e),
+    /// }
+    /// ```
+    pub fn try_run(&mut self) -> Result<ScanResults, DupeFinderError> {
+        let results = self.run();
+        match results.errors().first() {
+            Some(error) if self.options.strict => Err(error.clone().into()),
+            _ => Ok(results),
+        }
+    }
+
+    /// Like `run_for_file` for every file of `paths`, traversing the directories only once
+    ///
+    /// Each query gets the result `run_for_file` would return for it, files sharing a size are hashed once.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+    /// for (path, result) in checker.run_for_files(["./resources/base/a.txt", "./resources/base/b.txt"]) {
+    ///     if let Ok(Some(duplicate)) = result {
+    ///         println!("{} has {} copies", path.display(), duplicate.entries.len() - 1);
+    ///     }
+    /// }
+    /// ```
+    pub fn run_for_files<I, P>(&mut self, paths: I) -> HashMap<PathBuf, Result<Option<Duplicate>, DupeFinderError>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.initialize();
+
+        let mut answers: HashMap<PathBuf, Result<Option<Duplicate>, DupeFinderError>> = HashMap::new();
+        let mut queries: Vec<(PathBuf, FindFile)> = Vec::new();
+        for path in to_path_bufs(paths) {
+            match FindFile::new(&path, self.options.strategy.as_ref(), self.options.hasher.as_ref()) {
+                Ok(query) => {
+                    // further links to a query file are left out like in `run_for_file`
+                    if self.options.hardlinks != HardlinkPolicy::Keep {
+                        self.hardlinks.insert(&query.data.path, &query.data.meta);
+                    }
+                    queries.push((path, query));
+                },
+                Err(e) => {
+                    answers.insert(path, Err(e));
+                },
+            }
+        }
+
+        self.build_directories();
+
+        // candidate key -> (hash, entry) of every searched file sharing it
+        let mut hashed: HashMap<u64, Vec<(String, DupeEntry)>> = HashMap::new();
+        for (path, query) in queries {
+            let candidates = hashed.entry(query.key).or_insert_with(|| self.hash_files_of_size(query.key));
+            let mut entries: Vec<DupeEntry> = candidates.iter()
+                .filter(|(hash, entry)| *hash == query.hash && entry.path != query.data.path)
+                .map(|(_, entry)| entry.clone())
+                .collect();
+
+            if self.options.verify_contents {
+                entries.retain(|entry| match verify::same_contents(&query.data.path, &entry.path, self.options.read_throttle.as_deref()) {
+                    Ok(same) => same,
+                    Err(e) => {
+                        self.log(LogCategory::Hashing, Level::Warn, format_args!("Error comparing file contents for file: {}; error: {}; skipped.", entry.path.display(), e));
+                        self.record_error(&entry.path, LogCategory::Hashing, &e);
+                        false
+                    }
+                });
+            }
+
+            let answer = match entries.is_empty() {
+                true => None,
+                false => {
+                    entries.insert(0, DupeEntry::new(query.data.path.clone(), &query.data.meta));
+                    let confidence = if self.options.verify_contents { Confidence::ByteVerified } else { self.options.strategy.confidence() };
+                    Some(Duplicate { hash: query.hash, entries, size: query.data.size, confidence })
+                },
+            };
+            answers.insert(path, Ok(answer));
+        }
+
+        answers
+    }
+
     /// Runs the search for duplicate files and returns the matches
-    pub fn run(&mut self) -> HashMap<String, Duplicate> {
+    pub fn run(&mut self) -> ScanResults {
         self.initialize();
 
         self.build_directories();
@@ -283,13 +1143,132 @@ impl DupeFinder {
         let mut dupes: HashMap<String, Duplicate> = HashMap::new();
         self.check_duplicates(&mut dupes);
 
-        dupes
+        self.scan_results(dupes)
     }
 
-    fn should_insert_size(&self, data: &DirData, subdirs: &mut Vec<String>) -> bool {
+    fn scan_results(&self, dupes: HashMap<String, Duplicate>) -> ScanResults {
+        let mut references: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let dupes = dupes.into_values()
+            .filter_map(|duplicate| self.split_references(duplicate))
+            .map(|(duplicate, reference_files)| {
+                self.events.send(|| ScanEvent::GroupFound(duplicate.clone()));
+                if !reference_files.is_empty() {
+                    references.insert(duplicate.hash.clone(), reference_files);
+                }
+                (duplicate.hash.clone(), duplicate)
+            })
+            .collect();
+        let results = ScanResults::new(dupes).with_references(references);
+
+        // with reference directories every reported file can go, the references stay
+        let kept_per_set: usize = if self.options.references.is_empty() { 1 } else { 0 };
+        let mut forecast = SpaceForecast::default();
+        let stats = ScanStats {
+            duplicate_groups: results.len(),
+            duplicate_bytes: results.values().map(|duplicate| duplicate.size * duplicate.entries.len() as u64).sum(),
+            reclaimable_bytes: results.values()
+                .flat_map(|duplicate| duplicate.paths().skip(kept_per_set).map(|file| (file, duplicate.size)))
+                .map(|(file, size)| forecast.remove(file, size))
+                .sum(),
+            truncated: self.budget.truncated(),
+            ..self.counters.stats()
+        };
+        let results = results.with_stats(stats).with_errors(self.errors.errors());
+
+        match self.options.hardlinks {
+            HardlinkPolicy::Separate => results.with_hardlinks(self.hardlinks.groups()),
+            _ => results,
+        }
+    }
+
+    // leaves only the target files in `duplicate` when reference directories are configured,
+    // sets with fewer copies or less wasted space than configured are dropped
+    fn split_references(&self, duplicate: Duplicate) -> Option<(Duplicate, Vec<PathBuf>)> {
+        let (duplicate, reference_files) = match self.options.references.is_empty() {
+            true => (duplicate, Vec::new()),
+            false => split_references(&self.reference_dirs, duplicate)?,
+        };
+
+        // the references are kept instead of one of the listed files
+        let copies = duplicate.entries.len() + reference_files.len();
+        let removable = if reference_files.is_empty() { duplicate.entries.len().saturating_sub(1) } else { duplicate.entries.len() };
+        if copies < self.options.min_copies || duplicate.size * (removable as u64) < self.options.min_group_waste {
+            return None;
+        }
+
+        Some((duplicate, reference_files))
+    }
+
+    /// Runs the search and additionally returns every file that has no duplicate, sorted by path
+    ///
+    /// Both sides come from the same pass, files that could not be read are in neither of them.
+    /// Files in reference directories are never listed as unique.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+    /// let (duplicates, unique) = checker.run_partitioned();
+    /// println!("{} duplicate sets, {} unique files", duplicates.len(), unique.len());
+    /// ```
+    pub fn run_partitioned(&mut self) -> (ScanResults, Vec<PathBuf>) {
+        self.initialize();
+
+        self.build_directories();
+
+        let mut dupes: HashMap<String, Duplicate> = HashMap::new();
+        let mut unique = self.check_duplicates(&mut dupes);
+
+        // files without a same-size counterpart are never hashed
+        for (size, files) in self.file_sizes.iter() {
+            if !self.duplicate_file_sizes.contains(size) {
+                unique.extend(files.iter().map(|data| data.path.clone()));
+            }
+        }
+        unique.retain(|file| file.parent().is_none_or(|parent| !self.reference_dirs.contains(parent)));
+        unique.sort();
+
+        (self.scan_results(dupes), unique)
+    }
+
+    /// Messages collected during the last run when `DupeFinderBuilder::collect_logs` is enabled
+    pub fn log_messages(&self) -> Vec<LogMessage> {
+        self.logger.messages()
+    }
+
+    /// Files left out of the last run, along with the reason they were skipped
+    pub fn skipped_files(&self) -> Vec<SkippedFile> {
+        self.skipped.files()
+    }
+
+    /// Files and directories the last run could not read, also listed in `ScanResults::errors`
+    pub fn scan_errors(&self) -> Vec<ScanError> {
+        self.errors.errors()
+    }
+
+    fn log(&self, category: LogCategory, level: Level, message: std::fmt::Arguments) {
+        self.logger.log(&self.options.log, category, level, message);
+    }
+
+    /// Runs the search lazily, yielding each set of duplicate files as soon as
+    /// all files sharing its size have been hashed
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+    /// for duplicate in checker.run_iter() {
+    ///     println!("{} files found with hash {}", duplicate.entries.len(), duplicate.hash);
+    /// }
+    /// ```
+    pub fn run_iter(&mut self) -> DupeIter<'_> {
+        DupeIter::new(self)
+    }
+
+    fn should_insert_size(&self, data: &DirData, subdirs: &mut Vec<PathBuf>) -> bool {
         if !data.meta.is_file() {
-            if self.follow_subdirs && data.meta.is_dir() {
-                subdirs.push(data.path.display().to_string());
+            if self.options.follow_subdirs && data.meta.is_dir() {
+                subdirs.push(data.path.clone());
+            } else if is_special_file(&data.meta) {
+                self.skip_special_file(&data.path);
             }
 
             return false;
@@ -300,10 +1279,19 @@ impl DupeFinder {
             return false;
         }
 
+        if !self.options.filter.allows_file(&data.path) {
+            return false;
+        }
+
+        // reference files are matched against whatever their age
+        if !self.options.filter.allows_modified(&data.meta) && data.path.parent().is_none_or(|parent| !self.reference_dirs.contains(parent)) {
+            return false;
+        }
+
         // we are in find file mode
         if let Some(find_file) = &self.find_file {
-            // we only care about things that are the same size as our search file
-            if data.size != find_file.data.size {
+            // we only care about things that share the candidate key (by default the size) of our search file
+            if self.candidate_key(data) != Some(find_file.key) {
                 return false;
             }
 
@@ -316,37 +1304,224 @@ impl DupeFinder {
         true
     }
     
-    fn build_directory_contents(&mut self, directory: &String) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    // replaces the metadata of a followed symlink with that of its target,
+    // links that are not followed or broken are dropped
+    fn resolve_symlink(&self, data: DirData) -> Option<DirData> {
+        if !data.meta.is_symlink() {
+            return Some(data);
+        }
+
+        if self.options.symlinks == SymlinkPolicy::Skip {
+            return None;
+        }
+
+        let meta = match fs::metadata(&data.path) {
+            Ok(meta) => meta,
+            Err(e) => {
+                self.log(LogCategory::Metadata, Level::Warn, format_args!("Unable to resolve symlink: {}; error: {}; skipped.", data.path.display(), e));
+                self.record_error(&data.path, LogCategory::Metadata, &e);
+                return None;
+            }
+        };
+
+        if meta.is_dir() && self.options.symlinks != SymlinkPolicy::FollowAll {
+            return None;
+        }
+
+        Some(DirData { size: meta.len(), path: data.path, meta })
+    }
+
+    fn skip_special_file(&self, path: &Path) {
+        match self.options.special_files {
+            SpecialFilePolicy::Ignore => {},
+            SpecialFilePolicy::Skip => self.skip(path, SkipReason::SpecialFile),
+            SpecialFilePolicy::Report => {
+                self.skip(path, SkipReason::SpecialFile);
+                self.log(LogCategory::Traversal, Level::Warn, format_args!("Special file: {}; skipped.", path.display()));
+            },
+        }
+    }
+
+    fn build_directory_contents(&mut self, directory: &Path) -> Result<Vec<PathBuf>, io::Error> {
         let paths = fs::read_dir(directory)?;
         // holds any found subdirectories if recursive search turned on
-        let mut subdirs: Vec<String> = Vec::new();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
+        // files are only taken in once the walk filter kept the whole directory
+        let mut found: Vec<(u64, DirData)> = Vec::new();
+        // subdirectories on another device are not descended into
+        let device = match self.options.same_filesystem {
+            true => fs::metadata(directory).ok().and_then(|meta| device_id(&meta)),
+            false => None,
+        };
 
-        for path in paths {
-            let data = match DirData::new(path) {
+        #[cfg(feature = "gitignore")]
+        if self.options.respect_gitignore {
+            for e in self.ignore_rules.load(directory) {
+                self.log(LogCategory::Traversal, Level::Warn, format_args!("Unable to read ignore rules of directory: {}; error: {}", directory.display(), e));
+            }
+        }
+
+        for (index, path) in paths.enumerate() {
+            if self.traversal_done() {
+                break;
+            }
+
+            if self.options.limits.too_many_entries(index + 1) {
+                self.skip(directory, SkipReason::TooManyEntries);
+                break;
+            }
+
+            // filtered entries are dropped before their metadata is read
+            if let Ok(entry) = &path {
+                let entry_path = entry.path();
+                if self.options.filter.hides(entry) || self.options.filter.excludes(&entry_path) {
+                    continue;
+                }
+
+                #[cfg(feature = "gitignore")]
+                if self.options.respect_gitignore && self.ignore_rules.ignores(&entry_path, entry.file_type().is_ok_and(|file_type| file_type.is_dir())) {
+                    continue;
+                }
+
+                if self.options.limits.path_too_long(&entry_path) {
+                    self.skip(&entry_path, SkipReason::PathTooLong);
+                    continue;
+                }
+
+                if entry.file_type().is_ok_and(|file_type| file_type.is_file()) && !self.options.filter.allows_file(&entry_path) {
+                    continue;
+                }
+            }
+
+            // entries that could not be read at all are attributed to the directory
+            let entry_path = path.as_ref().map_or_else(|_| directory.to_path_buf(), DirEntry::path);
+            let data = match self.dir_data(path) {
                 Ok(val) => val,
                 Err(e) => {
-                    warn!("An error getting path / metadata: {}; skipped.", e);
+                    self.log(LogCategory::Metadata, Level::Warn, format_args!("An error getting path / metadata: {}; skipped.", e));
+                    self.record_error(&entry_path, LogCategory::Metadata, &e);
                     continue;
                 }
             };
 
+            let data = match self.resolve_symlink(data) {
+                Some(data) => data,
+                None => continue,
+            };
+
+            if let Some(filter) = &self.options.filter.walk {
+                match filter(&data.path, &data.meta) {
+                    WalkDecision::Accept => {},
+                    WalkDecision::Skip => continue,
+                    WalkDecision::SkipDirectory => {
+                        self.log(LogCategory::Traversal, Level::Debug, format_args!("Directory skipped by walk filter at: {}", data.path.display()));
+                        return Ok(Vec::new());
+                    },
+                }
+            }
+
+            if data.meta.is_dir() && device.is_some_and(|device| device_id(&data.meta) != Some(device)) {
+                self.log(LogCategory::Traversal, Level::Debug, format_args!("Directory on another filesystem: {}; skipped.", data.path.display()));
+                continue;
+            }
+
             if self.should_insert_size(&data, &mut subdirs) {
-                self.insert_size(data);
+                if let Some(key) = self.candidate_key(&data) {
+                    found.push((key, data));
+                }
             }
         }
+
+        for (key, data) in found {
+            if self.traversal_done() {
+                break;
+            }
+            self.insert_size(key, data);
+        }
     
         Ok(subdirs)
     }
 
-    fn insert_size(&mut self, data: DirData) {
-        let len = data.meta.len();
-        if self.file_sizes.contains_key(&len) {
-            self.file_sizes.entry(len).and_modify(|list| list.push(data));
-            self.duplicate_file_sizes.insert(len);
-        } else {
-            self.file_sizes.insert(len, vec![data]);
-        }
-    }
+    // a strict run hit an error, the budget ran out or a `find_first` run found its copy
+    fn traversal_done(&self) -> bool {
+        self.errors.stopped() || self.budget.truncated() || self.first_match.is_some()
+    }
+
+    // hashes a candidate as soon as it is found, the set of the searched file and `data` if they match
+    fn check_first(&self, data: &DirData) -> Option<Duplicate> {
+        let find_file = self.find_file.as_ref()?;
+        let hash = match self.hash_candidates(&[data]).pop()? {
+            Ok(hash) => hash,
+            Err(e) if self.is_stopped_error(&e) => return None,
+            Err(e) => {
+                self.log(LogCategory::Hashing, Level::Warn, format_args!("Error generating file hash for file: {}; error: {}", data.path.display(), e));
+                self.record_error(&data.path, LogCategory::Hashing, &e);
+                return None;
+            }
+        };
+
+        if hash != find_file.hash {
+            return None;
+        }
+
+        let entries = vec![DupeEntry::new(find_file.data.path.clone(), &find_file.data.meta), DupeEntry::new(data.path.clone(), &data.meta)];
+        let mut duplicate = Duplicate { hash, entries, size: find_file.data.size, confidence: self.options.strategy.confidence() };
+        if self.options.verify_contents && !self.verify_duplicate(&mut duplicate) {
+            return None;
+        }
+
+        Some(duplicate)
+    }
+
+    fn candidate_key(&self, data: &DirData) -> Option<u64> {
+        self.options.strategy.candidate_key(&data.path, &data.meta)
+    }
+
+    // `file_sizes` is keyed by the candidate key of the match strategy, the file size by default
+    fn insert_size(&mut self, key: u64, data: DirData) {
+        if self.options.shard.is_some_and(|shard| !shard.owns(key)) {
+            return;
+        }
+
+        // further links to an already known file are left out
+        if self.options.hardlinks != HardlinkPolicy::Keep && !self.hardlinks.insert(&data.path, &data.meta) {
+            return;
+        }
+
+        if !self.budget.take_file(&self.options.budget) {
+            self.log(LogCategory::Traversal, Level::Info, format_args!("File limit reached at: {}; traversal stopped.", data.path.display()));
+            return;
+        }
+
+        self.counters.scanned();
+        if self.options.find_first && self.find_file.is_some() {
+            self.first_match = self.check_first(&data);
+        }
+
+        match self.file_sizes.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().push(data);
+                self.duplicate_file_sizes.insert(key);
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(vec![data]);
+            }
+        }
+    }
+}
+
+// whether `directory` is a link to one of the directories holding it, `canonical` being its target
+fn is_symlink_loop(directory: &Path, canonical: &Path) -> bool {
+    fs::symlink_metadata(directory).is_ok_and(|meta| meta.is_symlink())
+        && directory.parent().and_then(|parent| fs::canonicalize(parent).ok()).is_some_and(|parent| parent.starts_with(canonical))
+}
+
+pub(crate) fn to_path_bufs<I, P>(paths: I) -> Vec<PathBuf>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    paths.into_iter().map(|path| path.as_ref().to_path_buf()).collect()
 }
 
 #[cfg(test)]
@@ -424,6 +1599,29 @@ mod tests {
         assert_known_size(&checker, known_size, 2, 1, 1);
     }
 
+    #[test]
+    fn duplicate_entries_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+
+        let mut checker = DupeFinder::new([&path]);
+        let results = checker.run();
+        let duplicate = results.values().next().unwrap();
+        assert_eq!(duplicate.entries.len(), 2);
+
+        for entry in duplicate.entries.iter() {
+            let meta = fs::metadata(&entry.path).unwrap();
+            assert_eq!(entry.size, duplicate.size);
+            assert_eq!(entry.modified, Some(meta.modified().unwrap()));
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+                assert_eq!(entry.file_id, Some((meta.dev(), meta.ino())));
+            }
+        }
+        #[cfg(unix)]
+        assert_ne!(duplicate.entries[0].file_id, duplicate.entries[1].file_id, "copies should be separate files");
+    }
+
     #[test]
     fn duplicate_different_directory_works() {
         let path_a: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories", "dir_a"].iter().collect();
@@ -454,7 +1652,7 @@ mod tests {
 
         let result = checker.run_for_file(ff_path.display().to_string());
         
-        assert!(result.is_err(), "io error expected");
+        assert!(matches!(result, Err(DupeFinderError::Metadata { source, .. }) if source.kind() == io::ErrorKind::NotFound), "metadata error expected");
     }
 
     #[test]
@@ -476,8 +1674,8 @@ mod tests {
         if let Some(duplicate) = duplicate {
             assert_eq!(duplicate.size, known_size);
             assert_eq!(duplicate.hash, String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
-            assert_eq!(duplicate.files.len(), 2);
-            assert!(duplicate.files.contains(&ff_path.display().to_string()));
+            assert_eq!(duplicate.entries.len(), 2);
+            assert!(duplicate.contains(&ff_path));
         };
     }
 
@@ -496,6 +1694,225 @@ mod tests {
         assert!(duplicate.is_none(), "did not expect match");
     }
 
+    #[test]
+    fn run_for_hash_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinder::new(vec![&path]);
+
+        let found = checker.run_for_hash(100, "1577245f909f3d4619dda56a7b4ba1af").unwrap();
+        assert_eq!(found.hash, String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
+        assert_eq!(found.entries.len(), 2);
+        assert_eq!(checker.counters.stats().files_hashed, 2);
+
+        assert!(checker.run_for_hash(100, "0").is_none(), "no match expected");
+        assert!(checker.run_for_hash(99, "1577245F909F3D4619DDA56A7B4BA1AF").is_none(), "size should match too");
+    }
+
+    #[test]
+    fn run_for_content_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let contents = fs::read(path.join("a.txt")).unwrap();
+        let mut checker = DupeFinder::new(vec![&path]);
+
+        let found = checker.run_for_content(contents.as_slice()).unwrap().unwrap();
+        assert_eq!(found.hash, String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
+        assert_eq!(found.entries.len(), 2);
+
+        let found = checker.run_for_content(fs::File::open(path.join("a.txt")).unwrap()).unwrap();
+        assert_eq!(found.unwrap().entries.len(), 2, "streamed content should match too");
+
+        assert!(checker.run_for_content(&contents[1..]).unwrap().is_none(), "no match expected");
+    }
+
+    #[test]
+    fn run_for_files_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let base: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "base"].iter().collect();
+        let unique = tempfile::NamedTempFile::new().unwrap();
+        fs::write(unique.path(), "unique").unwrap();
+        let mut checker = DupeFinder::new(vec![&path]);
+
+        let answers = checker.run_for_files([base.join("a.txt"), path.join("a.txt"), unique.path().to_path_buf(), base.join("missing.txt")]);
+        assert_eq!(answers.len(), 4);
+        assert_eq!(answers[&base.join("a.txt")].as_ref().unwrap().as_ref().unwrap().entries.len(), 3);
+        assert_eq!(answers[&path.join("a.txt")].as_ref().unwrap().as_ref().unwrap().entries.len(), 2, "the query should not match itself");
+        assert!(answers[unique.path()].as_ref().unwrap().is_none(), "no match expected");
+        assert!(answers[&base.join("missing.txt")].is_err(), "missing file should fail");
+        assert_eq!(checker.counters.stats().files_hashed, 2, "files sharing a size should be hashed once");
+
+        let single = checker.run_for_file(base.join("a.txt")).unwrap().unwrap();
+        assert_eq!(single.entries.len(), 3);
+    }
+
+    #[test]
+    fn scan_budget_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+
+        let results = DupeFinder::builder(vec![&path]).max_files(1).build().unwrap().run();
+        assert!(results.is_empty(), "a single file can not be a duplicate");
+        assert_eq!(results.stats().files_scanned, 1);
+        assert!(results.stats().truncated);
+
+        let results = DupeFinder::builder(vec![&path]).max_bytes(150).build().unwrap().run();
+        assert!(results.is_empty(), "only one file fits the budget");
+        assert_eq!(results.stats().files_hashed, 1);
+        assert!(results.stats().truncated);
+        assert!(results.errors().is_empty(), "files left out are not errors");
+
+        let results = DupeFinder::builder(vec![&path]).max_files(2).max_bytes(200).build().unwrap().run();
+        assert_eq!(results.len(), 1);
+        assert!(!results.stats().truncated, "the budget was not exceeded");
+    }
+
+    #[test]
+    fn max_groups_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let all = DupeFinder::new_recursive(vec![&path]).run();
+        assert!(all.len() > 1, "several sets expected");
+
+        let results = DupeFinder::builder(vec![&path]).recursive(true).max_groups(1).build().unwrap().run();
+        assert_eq!(results.len(), 1);
+        assert!(results.stats().truncated);
+        let duplicate = results.values().next().unwrap();
+        assert_eq!(duplicate.entries, all[&duplicate.hash].entries, "found sets should be complete");
+
+        let results = DupeFinder::builder(vec![&path]).recursive(true).max_groups(all.len()).build().unwrap().run();
+        assert_eq!(results.len(), all.len());
+    }
+
+    #[test]
+    fn min_group_thresholds_works() {
+        let dupes: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let base: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "base"].iter().collect();
+        let run = |builder: DupeFinderBuilder| builder.build().unwrap().run().len();
+
+        assert_eq!(run(DupeFinder::builder([&dupes, &base]).min_copies(3)), 1);
+        assert_eq!(run(DupeFinder::builder([&dupes, &base]).min_copies(4)), 0);
+        assert_eq!(run(DupeFinder::builder([&dupes]).min_copies(3)), 0, "pairs should be left out");
+        assert_eq!(run(DupeFinder::builder([&dupes, &base]).min_group_waste_bytes(200)), 1);
+        assert_eq!(run(DupeFinder::builder([&dupes, &base]).min_group_waste_bytes(201)), 0);
+
+        // the reference file counts as a copy, both listed files can go
+        assert_eq!(run(DupeFinder::builder([&dupes]).reference_directories([&base]).min_copies(3).min_group_waste_bytes(200)), 1);
+        let mut checker = DupeFinder::builder([&dupes]).min_copies(3).build().unwrap();
+        assert_eq!(checker.run_iter().count(), 0, "run_iter should apply the thresholds");
+    }
+
+    #[cfg(feature = "gitignore")]
+    #[test]
+    fn respect_gitignore_works() {
+        let temp = tempfile::tempdir().unwrap();
+        let target = temp.path().join("target");
+        fs::create_dir(&target).unwrap();
+        fs::write(temp.path().join(".gitignore"), "target/\n").unwrap();
+        fs::write(temp.path().join("a.txt"), "12345").unwrap();
+        fs::write(target.join("a.txt"), "12345").unwrap();
+        fs::write(target.join("b.txt"), "12345").unwrap();
+
+        assert_eq!(DupeFinder::new_recursive([temp.path()]).run().len(), 1, "ignored copies should be found by default");
+
+        let results = DupeFinder::builder([temp.path()]).recursive(true).respect_gitignore(true).build().unwrap().run();
+        assert!(results.is_empty(), "ignored directory should be left out");
+    }
+
+    #[cfg(feature = "decompress")]
+    #[test]
+    fn decompress_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "compressed"].iter().collect();
+        assert!(DupeFinder::new([&path]).run().is_empty(), "compressed copies should differ by default");
+
+        let results = DupeFinder::builder([&path]).decompress(true).partial_hash(64).build().unwrap().run();
+        assert_eq!(results.len(), 1);
+
+        let mut found: Vec<&Path> = results.values().next().unwrap().paths().collect();
+        found.sort();
+        let expected: Vec<PathBuf> = ["report.log", "report.log.gz", "report.log.xz", "report.log.zst"].iter().map(|name| path.join(name)).collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn walk_filter_works() {
+        let temp = tempfile::tempdir().unwrap();
+        let (kept, marked) = (temp.path().join("kept"), temp.path().join("marked"));
+        for directory in [&kept, &marked] {
+            fs::create_dir(directory).unwrap();
+            fs::write(directory.join("a.txt"), "12345").unwrap();
+            fs::write(directory.join("b.txt"), "12345").unwrap();
+        }
+        fs::write(kept.join("c.skip"), "12345").unwrap();
+        fs::write(marked.join("CACHEDIR.TAG"), "").unwrap();
+
+        let results = DupeFinder::builder([temp.path()])
+            .recursive(true)
+            .walk_filter(|path, _| match path.file_name().and_then(|name| name.to_str()) {
+                Some("CACHEDIR.TAG") => WalkDecision::SkipDirectory,
+                Some(name) if name.ends_with(".skip") => WalkDecision::Skip,
+                _ => WalkDecision::Accept,
+            })
+            .build().unwrap()
+            .run();
+
+        assert_eq!(results.len(), 1);
+        let mut files: Vec<&Path> = results.values().next().unwrap().paths().collect();
+        files.sort();
+        assert_eq!(files, vec![kept.join("a.txt"), kept.join("b.txt")]);
+    }
+
+    #[test]
+    fn overlapping_directories_work() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let dupes = path.join("dupes");
+        let respelled = path.join(".").join("dupes").join("..").join("dupes");
+        let expected = DupeFinder::new_recursive([&path]).run();
+
+        for directories in [vec![&path, &respelled], vec![&respelled, &path], vec![&dupes, &respelled]] {
+            let results = DupeFinder::new_recursive(directories.clone()).run();
+            let found = if directories.contains(&&path) { &expected } else { &DupeFinder::new([&dupes]).run() };
+            assert_eq!(results.len(), found.len());
+            for duplicate in results.values() {
+                assert_eq!(duplicate.entries.len(), found[&duplicate.hash].entries.len(), "files should be found once: {:?}", duplicate.entries);
+            }
+        }
+    }
+
+    #[test]
+    fn from_files_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let files = [path.join("dupes").join("a.txt"), path.join("base").join("a.txt"), path.join("dupes").join("a.txt"), path.join("missing.txt")];
+
+        let mut checker = DupeFinder::from_files(&files);
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.values().next().unwrap().entries.len(), 2, "files listed twice should be searched once");
+        assert_eq!(results.errors().len(), 1, "missing file should be recorded");
+        assert!(checker.checked_directories.is_empty(), "no directory should be traversed");
+        assert_eq!(checker.run().values().next().unwrap().entries.len(), 2, "re-runs should start over");
+
+        let results = DupeFinderBuilder::from_files(&files[..2]).files([path.join("dupes").join("b.txt")]).build().unwrap().run();
+        assert_eq!(results.values().next().unwrap().entries.len(), 3);
+
+        let results = DupeFinder::builder([path.join("dupes")]).files(&files[..2]).build().unwrap().run();
+        assert_eq!(results.values().next().unwrap().entries.len(), 3, "files of searched directories should be searched once");
+    }
+
+    #[test]
+    fn find_first_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let ff_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "base", "a.txt"].iter().collect();
+        let mut checker = DupeFinder::builder(vec![&path]).find_first(true).verify_contents(true).build().unwrap();
+
+        let duplicate = checker.run_for_file(&ff_path).unwrap().unwrap();
+        assert_eq!(duplicate.hash, String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
+        assert_eq!(duplicate.entries.len(), 2, "only the first copy expected");
+        assert_eq!(duplicate.entries[0].path, ff_path);
+        assert_eq!(duplicate.confidence, Confidence::ByteVerified);
+        assert_eq!(checker.counters.stats().files_hashed, 1, "hashing should stop at the first copy");
+
+        let unique = tempfile::NamedTempFile::new().unwrap();
+        fs::write(unique.path(), "unique").unwrap();
+        assert!(checker.run_for_file(unique.path()).unwrap().is_none(), "no match expected");
+    }
+
     #[test]
     fn findfile_works_dupes() {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
@@ -514,8 +1931,8 @@ mod tests {
         if let Some(duplicate) = duplicate {
             assert_eq!(duplicate.size, known_size);
             assert_eq!(duplicate.hash, String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
-            assert_eq!(duplicate.files.len(), 3);
-            assert!(duplicate.files.contains(&ff_path.display().to_string()));
+            assert_eq!(duplicate.entries.len(), 3);
+            assert!(duplicate.contains(&ff_path));
         };
     }
 
@@ -558,7 +1975,7 @@ mod tests {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
         let path_string: String = path.display().to_string();
         let data = DirData::new_from_path(path_string);
-        let mut subdirs: Vec<String> = Vec::new();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
         if let Ok(data) = data {
             assert!(!checker.should_insert_size(&data, &mut subdirs), "folder should be inserted");
             assert_eq!(subdirs.len(), 1);
@@ -573,7 +1990,7 @@ mod tests {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
         let path_string: String = path.display().to_string();
         let data = DirData::new_from_path(path_string);
-        let mut subdirs: Vec<String> = Vec::new();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
         if let Ok(data) = data {
             assert!(!checker.should_insert_size(&data, &mut subdirs), "folder should be inserted");
             assert_eq!(subdirs.len(), 0);
@@ -588,7 +2005,7 @@ mod tests {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
         let path_string: String = path.display().to_string();
         let data = DirData::new_from_path(path_string);
-        let mut subdirs: Vec<String> = Vec::new();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
         if let Ok(data) = data {
             assert!(checker.should_insert_size(&data, &mut subdirs), "file should be inserted");
         };
@@ -602,7 +2019,7 @@ mod tests {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_with_empty","empty.txt"].iter().collect();
         let path_string: String = path.display().to_string();
         let data = DirData::new_from_path(path_string);
-        let mut subdirs: Vec<String> = Vec::new();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
         if let Ok(data) = data {
             assert!(!checker.should_insert_size(&data, &mut subdirs), "file should not be inserted");
         };
@@ -615,12 +2032,12 @@ mod tests {
 
         let ff_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
         let ff_path_string: String = ff_path.display().to_string();
-        checker.find_file = Some(FindFile::new(ff_path_string).unwrap());
+        checker.find_file = Some(FindFile::new(ff_path_string, &ContentStrategy, &Xxh3Hasher).unwrap());
 
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
         let path_string: String = path.display().to_string();
         let data = DirData::new_from_path(path_string);
-        let mut subdirs: Vec<String> = Vec::new();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
         if let Ok(data) = data {
             assert!(!checker.should_insert_size(&data, &mut subdirs), "file should be inserted");
         };
@@ -633,12 +2050,12 @@ mod tests {
 
         let ff_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "insert_size","test.txt"].iter().collect();
         let ff_path_string: String = ff_path.display().to_string();
-        checker.find_file = Some(FindFile::new(ff_path_string).unwrap());
+        checker.find_file = Some(FindFile::new(ff_path_string, &ContentStrategy, &Xxh3Hasher).unwrap());
 
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","b.txt"].iter().collect();
         let path_string: String = path.display().to_string();
         let data = DirData::new_from_path(path_string);
-        let mut subdirs: Vec<String> = Vec::new();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
         if let Ok(data) = data {
             assert!(!checker.should_insert_size(&data, &mut subdirs), "file should be inserted");
         };
@@ -651,17 +2068,28 @@ mod tests {
 
         let ff_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
         let ff_path_string: String = ff_path.display().to_string();
-        checker.find_file = Some(FindFile::new(ff_path_string).unwrap());
+        checker.find_file = Some(FindFile::new(ff_path_string, &ContentStrategy, &Xxh3Hasher).unwrap());
 
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","b.txt"].iter().collect();
         let path_string: String = path.display().to_string();
         let data = DirData::new_from_path(path_string);
-        let mut subdirs: Vec<String> = Vec::new();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
         if let Ok(data) = data {
             assert!(checker.should_insert_size(&data, &mut subdirs), "file should be inserted");
         };
     }
 
+    #[test]
+    fn should_not_insert_filtered_file() {
+        let dir_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let checker = DupeFinder::builder([&dir_path]).exclude_extensions(["TXT"]).build().unwrap();
+
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
+        let data = DirData::new_from_path(path).unwrap();
+        let mut subdirs: Vec<PathBuf> = Vec::new();
+        assert!(!checker.should_insert_size(&data, &mut subdirs), "file should not be inserted");
+    }
+
     #[test]
     fn insert_size_works() {
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "insert_size"].iter().collect();
@@ -682,7 +2110,7 @@ mod tests {
 
         let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes","a.txt"].iter().collect();
         let path_string: String = path.display().to_string();
-        checker.find_file = Some(FindFile::new(path_string).unwrap());
+        checker.find_file = Some(FindFile::new(path_string, &ContentStrategy, &Xxh3Hasher).unwrap());
 
         checker.insert_find_file_size();
         assert_eq!(checker.file_sizes.len(), 1);
@@ -692,6 +2120,597 @@ mod tests {
         assert_eq!(dir_data.len(), 1);
     }
 
+    #[test]
+    fn hashing_panic_is_isolated() {
+        struct PanicHasher;
+        impl FileHasher for PanicHasher {
+            fn hash_reader(&self, reader: &mut dyn std::io::Read) -> Result<String, io::Error> {
+                Xxh3Hasher.hash_reader(reader)
+            }
+
+            fn hash_file(&self, path: &Path) -> Result<String, io::Error> {
+                if path.ends_with("b.txt") {
+                    panic!("pathological file");
+                }
+                Xxh3Hasher.hash_file(path)
+            }
+        }
+
+        let path_a: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let path_b: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let mut checker = DupeFinder::builder([&path_a, &path_b])
+            .recursive(true)
+            .hasher(PanicHasher)
+            .collect_logs(true)
+            .build()
+            .unwrap();
+
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results["1577245F909F3D4619DDA56A7B4BA1AF"].entries.len(), 2);
+
+        let messages = checker.log_messages();
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().all(|message| message.category == LogCategory::Hashing && message.message.contains("pathological file")));
+
+        #[cfg(feature = "parallel")]
+        {
+            let mut checker = DupeFinder::builder([&path_a, &path_b]).recursive(true).hasher(PanicHasher).parallel(2).build().unwrap();
+            assert_eq!(checker.run().len(), 1);
+        }
+    }
+
+    #[test]
+    fn read_errors_are_collected() {
+        struct DeniedHasher;
+        impl FileHasher for DeniedHasher {
+            fn hash_reader(&self, reader: &mut dyn std::io::Read) -> Result<String, io::Error> {
+                Xxh3Hasher.hash_reader(reader)
+            }
+
+            fn hash_file(&self, path: &Path) -> Result<String, io::Error> {
+                match path.file_name().is_some_and(|name| name == "c.txt") {
+                    true => Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+                    false => Xxh3Hasher.hash_file(path),
+                }
+            }
+        }
+
+        let temp = tempfile::tempdir().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(temp.path().join(name), "12345").unwrap();
+        }
+
+        let mut checker = DupeFinder::builder([temp.path(), &temp.path().join("missing")]).hasher(DeniedHasher).build().unwrap();
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+
+        let errors = results.errors();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|error| error.path == temp.path().join("missing") && error.kind == io::ErrorKind::NotFound && error.phase == LogCategory::Traversal));
+        assert!(errors.iter().any(|error| error.path == temp.path().join("c.txt") && error.kind == io::ErrorKind::PermissionDenied && error.phase == LogCategory::Hashing));
+        assert_eq!(checker.scan_errors(), errors);
+        assert!(checker.try_run().is_ok(), "errors should only fail strict runs");
+
+        let mut checker = DupeFinder::builder([temp.path()]).hasher(DeniedHasher).strict(true).build().unwrap();
+        let error = checker.try_run().err().unwrap();
+        assert!(matches!(&error, DupeFinderError::Hashing { source, .. } if source.kind() == io::ErrorKind::PermissionDenied));
+        assert_eq!(error.path(), Some(temp.path().join("c.txt").as_path()));
+        assert_eq!(checker.scan_errors().len(), 1);
+
+        let mut checker = DupeFinder::builder([&temp.path().join("missing"), temp.path()]).strict(true).build().unwrap();
+        checker.run();
+        assert_eq!(checker.checked_directories.len(), 1, "traversal should stop at the first error");
+    }
+
+    #[test]
+    fn run_partitioned_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_with_empty"].iter().collect();
+        let base: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "insert_size"].iter().collect();
+        // same size as the duplicates but different contents
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("c.txt"), [b'c'; 100]).unwrap();
+        let mut checker = DupeFinder::new([&path, &base, &temp.path().to_path_buf()]);
+
+        let (results, unique) = checker.run_partitioned();
+        assert_eq!(results.len(), 1);
+        let mut expected = vec![base.join("test.txt"), temp.path().join("c.txt")];
+        expected.sort();
+        assert_eq!(unique, expected);
+        assert!(unique.iter().all(|file| results.is_duplicate(file).is_none()), "unique files should not be duplicates");
+
+        let mut checker = DupeFinder::with_reference([&base], [&path, &temp.path().to_path_buf()]);
+        let (_, unique) = checker.run_partitioned();
+        assert_eq!(unique, vec![temp.path().join("c.txt")], "reference files should not be listed");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_device_is_quarantined() {
+        use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+
+        struct FailingHasher(Arc<AtomicUsize>);
+        impl FileHasher for FailingHasher {
+            fn hash_reader(&self, _reader: &mut dyn std::io::Read) -> Result<String, io::Error> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Err(io::Error::other("input/output error"))
+            }
+        }
+
+        let reads = Arc::new(AtomicUsize::new(0));
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let mut checker = DupeFinder::builder([&path])
+            .recursive(true)
+            .hasher(FailingHasher(reads.clone()))
+            .device_error_limit(1)
+            .collect_logs(true)
+            .build()
+            .unwrap();
+
+        assert!(checker.run().is_empty(), "no duplicates expected");
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+        assert_eq!(checker.skipped_files().len(), 1);
+        assert_eq!(checker.skipped_files()[0].reason, SkipReason::DeviceQuarantined);
+        assert!(checker.log_messages().iter().any(|message| message.level == Level::Error));
+    }
+
+    #[test]
+    fn partial_hash_skips_full_reads() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct CountingHasher(Arc<AtomicUsize>);
+        impl FileHasher for CountingHasher {
+            fn hash_reader(&self, reader: &mut dyn std::io::Read) -> Result<String, io::Error> {
+                Xxh3Hasher.hash_reader(reader)
+            }
+
+            fn hash_file(&self, path: &Path) -> Result<String, io::Error> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Xxh3Hasher.hash_file(path)
+            }
+        }
+
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.bin"), "same prefix, same tail").unwrap();
+        fs::write(temp.path().join("b.bin"), "same prefix, same tail").unwrap();
+        fs::write(temp.path().join("c.bin"), "same prefix, diff tail").unwrap();
+        fs::write(temp.path().join("d.bin"), "other prefix,same tail").unwrap();
+
+        let full_reads = Arc::new(AtomicUsize::new(0));
+        let mut checker = DupeFinder::builder([temp.path()]).hasher(CountingHasher(full_reads.clone())).partial_hash(11).build().unwrap();
+        let (results, unique) = checker.run_partitioned();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.values().next().unwrap().entries.len(), 2);
+        assert_eq!(unique, vec![temp.path().join("c.bin"), temp.path().join("d.bin")]);
+        assert_eq!(full_reads.load(Ordering::SeqCst), 3, "d.bin should not be read in full");
+        assert_eq!(results.stats().bytes_read, 4 * 11 + 3 * 22);
+
+        assert_eq!(checker.run_iter().count(), 1);
+        assert_eq!(checker.run_for_file(temp.path().join("a.bin")).unwrap().unwrap().entries.len(), 2);
+        assert!(checker.run_for_file(temp.path().join("d.bin")).unwrap().is_none(), "no duplicate expected");
+    }
+
+    #[test]
+    fn verify_contents_drops_collisions() {
+        struct CollidingHasher;
+        impl FileHasher for CollidingHasher {
+            fn hash_reader(&self, _reader: &mut dyn std::io::Read) -> Result<String, io::Error> {
+                Ok(String::from("0"))
+            }
+        }
+
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.txt"), "odd one").unwrap();
+        fs::write(temp.path().join("b.txt"), "same ab").unwrap();
+        fs::write(temp.path().join("c.txt"), "same ab").unwrap();
+
+        let mut checker = DupeFinder::builder([temp.path()]).hasher(CollidingHasher).build().unwrap();
+        assert_eq!(checker.run()["0"].entries.len(), 3);
+        assert_eq!(checker.run()["0"].confidence, Confidence::FullHash);
+
+        let mut checker = DupeFinder::builder([temp.path()]).hasher(CollidingHasher).verify_contents(true).collect_logs(true).build().unwrap();
+        let results = checker.run();
+        let mut files: Vec<&Path> = results["0"].paths().collect();
+        files.sort();
+        assert_eq!(files, vec![temp.path().join("b.txt"), temp.path().join("c.txt")]);
+        assert!(checker.log_messages().iter().any(|message| message.message.contains("a.txt")), "dropped file should be logged");
+        assert_eq!(results["0"].confidence, Confidence::ByteVerified);
+
+        let result = checker.run_for_file(temp.path().join("a.txt"));
+        assert!(result.unwrap().is_none(), "no identical file expected");
+    }
+
+    #[test]
+    fn stale_handles_are_retried() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // the first match key fails like a stale NFS handle
+        struct StaleOnce(AtomicUsize);
+
+        impl MatchStrategy for StaleOnce {
+            fn match_key(&self, path: &Path, hasher: &dyn FileHasher) -> Result<String, io::Error> {
+                match self.0.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(io::Error::from(io::ErrorKind::StaleNetworkFileHandle)),
+                    _ => hasher.hash_file(path),
+                }
+            }
+        }
+
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinder::builder([&path]).strategy(StaleOnce(AtomicUsize::new(0))).build().unwrap();
+        assert_eq!(checker.run().len(), 0, "the failing file should be dropped");
+
+        let mut checker = DupeFinder::builder([&path]).strategy(StaleOnce(AtomicUsize::new(0))).retry_stale_handles(1).build().unwrap();
+        assert_eq!(checker.run().len(), 1);
+    }
+
+    #[test]
+    fn incremental_runs_only_hash_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.txt"), "12345").unwrap();
+        fs::write(temp.path().join("b.txt"), "12345").unwrap();
+        fs::write(temp.path().join("c.txt"), "67890").unwrap();
+        fs::write(temp.path().join("d.txt"), "67890").unwrap();
+
+        let mut checker = DupeFinder::builder([temp.path()]).incremental(true).build().unwrap();
+        assert_eq!(checker.run().stats().files_hashed, 4);
+
+        let unchanged = checker.run();
+        assert_eq!(unchanged.len(), 2);
+        assert_eq!(unchanged.stats().files_hashed, 0, "unchanged files should not be hashed again");
+
+        fs::write(temp.path().join("b.txt"), "67890").unwrap();
+        fs::File::options().write(true).open(temp.path().join("b.txt")).unwrap()
+            .set_modified(std::time::SystemTime::now() + Duration::from_secs(60)).unwrap();
+        fs::remove_file(temp.path().join("d.txt")).unwrap();
+        fs::write(temp.path().join("e.txt"), "12345").unwrap();
+
+        let changed = checker.run();
+        assert_eq!(changed.stats().files_hashed, 2, "only the modified and the added file should be hashed");
+        assert_eq!(changed.is_duplicate(temp.path().join("b.txt").to_str().unwrap()).unwrap().entries.len(), 2);
+        assert_eq!(changed.is_duplicate(temp.path().join("e.txt").to_str().unwrap()).unwrap().entries.len(), 2);
+        assert_eq!(checker.memo.len(), 4, "removed files should be forgotten");
+
+        let mut checker = DupeFinder::builder([temp.path()]).build().unwrap();
+        checker.run();
+        assert_eq!(checker.run().stats().files_hashed, 4, "runs should be independent by default");
+    }
+
+    #[test]
+    fn incremental_runs_skip_partial_hashes() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.txt"), "1234567890".repeat(10)).unwrap();
+        fs::write(temp.path().join("b.txt"), "1234567890".repeat(10)).unwrap();
+        fs::write(temp.path().join("c.txt"), "0987654321".repeat(11)).unwrap();
+
+        let mut checker = DupeFinder::builder([temp.path()]).incremental(true).partial_hash(10).build().unwrap();
+        assert_eq!(checker.run().stats().bytes_read, 2 * 10 + 2 * 100);
+
+        let unchanged = checker.run();
+        assert_eq!(unchanged.len(), 1);
+        assert_eq!(unchanged.stats().bytes_read, 0, "files with a stored hash should not be read");
+
+        // the group holds files with a stored hash, so the new file is compared by its full hash
+        fs::write(temp.path().join("d.txt"), "1234567899".repeat(10)).unwrap();
+        let added = checker.run();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added.stats().files_hashed, 1, "only the added file should be hashed");
+        assert_eq!(added.stats().bytes_read, 100);
+    }
+
+    #[test]
+    fn incremental_run_for_file_keeps_memo() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.txt"), "12345").unwrap();
+        fs::write(temp.path().join("b.txt"), "12345").unwrap();
+        fs::write(temp.path().join("c.txt"), "678901").unwrap();
+        fs::write(temp.path().join("d.txt"), "678901").unwrap();
+
+        let mut checker = DupeFinder::builder([temp.path()]).incremental(true).build().unwrap();
+        assert_eq!(checker.run().stats().files_hashed, 4);
+        assert!(checker.run_for_file(temp.path().join("a.txt")).unwrap().is_some());
+        assert_eq!(checker.memo.len(), 4, "a search for one file should not forget the others");
+        assert_eq!(checker.run().stats().files_hashed, 0);
+    }
+
+    #[test]
+    fn verify_contents_is_throttled() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("a.txt"), vec![7u8; 100_000]).unwrap();
+        fs::write(temp.path().join("b.txt"), vec![7u8; 100_000]).unwrap();
+
+        // hashing takes the full bucket, comparing the files reads as much again
+        let mut checker = DupeFinder::builder([temp.path()]).verify_contents(true).max_read_rate(200_000).build().unwrap();
+        let start = std::time::Instant::now();
+        assert_eq!(checker.run().len(), 1);
+        assert!(start.elapsed() >= Duration::from_millis(700), "comparisons should be throttled like hashing");
+    }
+
+    #[test]
+    fn max_depth_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+
+        let mut checker = DupeFinder::builder([&path]).max_depth(1).build().unwrap();
+        checker.run();
+        assert!(checker.options.follow_subdirs, "max_depth should turn on recursion");
+        assert!(checker.checked_directories.contains(&path.join("dupes_directories")));
+        assert!(!checker.checked_directories.contains(&path.join("dupes_directories").join("dir_a")));
+        assert!(checker.skipped_files().is_empty(), "the recursion limit is not a skip");
+
+        let mut checker = DupeFinder::builder([&path]).max_depth(0).build().unwrap();
+        checker.run();
+        assert_eq!(checker.checked_directories.len(), 1);
+    }
+
+    #[test]
+    fn path_limits_are_enforced() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let dir_a = path.join("dir_a");
+        let dir_b = path.join("dir_b");
+
+        let mut checker = DupeFinder::builder([&path]).recursive(true).limit_depth(0).build().unwrap();
+        assert!(checker.run().is_empty(), "subdirectories should not be searched");
+        let mut skipped = checker.skipped_files();
+        skipped.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(skipped, vec![
+            SkippedFile { path: dir_a.clone(), reason: SkipReason::TooDeep },
+            SkippedFile { path: dir_b.clone(), reason: SkipReason::TooDeep },
+        ]);
+
+        let mut checker = DupeFinder::builder([&path]).recursive(true).limit_depth(1).build().unwrap();
+        assert_eq!(checker.run().len(), 1);
+        assert!(checker.skipped_files().is_empty(), "nothing should be skipped");
+
+        let temp = tempfile::tempdir().unwrap();
+        fs::copy(dir_a.join("a.txt"), temp.path().join("a.txt")).unwrap();
+        fs::copy(dir_a.join("a.txt"), temp.path().join("longer.txt")).unwrap();
+        let mut checker = DupeFinder::builder([temp.path()]).limit_path_length(temp.path().join("a.txt").as_os_str().len()).build().unwrap();
+        assert!(checker.run().is_empty(), "longer.txt should be skipped");
+        assert_eq!(checker.skipped_files(), vec![SkippedFile { path: temp.path().join("longer.txt"), reason: SkipReason::PathTooLong }]);
+
+        let source: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinder::builder([&source]).limit_directory_entries(1).build().unwrap();
+        assert!(checker.run().is_empty(), "only one entry should be read");
+        assert_eq!(checker.skipped_files(), vec![SkippedFile { path: source, reason: SkipReason::TooManyEntries }]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_policy_works() {
+        use std::os::unix::fs::symlink;
+
+        let temp = tempfile::tempdir().unwrap();
+        let source: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let linked: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories", "dir_a"].iter().collect();
+        let root = temp.path().join("root");
+        fs::create_dir(&root).unwrap();
+        fs::copy(&source, root.join("a.txt")).unwrap();
+        symlink(&source, root.join("file_link")).unwrap();
+        symlink(&linked, root.join("dir_link")).unwrap();
+        symlink(&root, root.join("cycle")).unwrap();
+        symlink(temp.path().join("missing"), root.join("broken")).unwrap();
+
+        let file_count = |policy: SymlinkPolicy| {
+            let mut checker = DupeFinder::builder([&root]).recursive(true).symlinks(policy).build().unwrap();
+            checker.run().values().map(|duplicate| duplicate.entries.len()).sum::<usize>()
+        };
+
+        assert_eq!(file_count(SymlinkPolicy::Skip), 0);
+        assert_eq!(file_count(SymlinkPolicy::FollowFiles), 2);
+        assert_eq!(file_count(SymlinkPolicy::FollowAll), 3);
+
+        let mut checker = DupeFinder::builder([&root]).recursive(true).symlinks(SymlinkPolicy::FollowAll).build().unwrap();
+        checker.run();
+        assert_eq!(checker.skipped_files(), vec![SkippedFile { path: root.join("cycle"), reason: SkipReason::SymlinkLoop }]);
+
+        // a loop further down the tree is caught the same way
+        let nested = root.join("nested").join("deeper");
+        fs::create_dir_all(&nested).unwrap();
+        symlink(root.join("nested"), nested.join("up")).unwrap();
+        checker.run();
+        assert_eq!(checker.skipped_files().len(), 2);
+        assert!(checker.skipped_files().iter().all(|file| file.reason == SkipReason::SymlinkLoop));
+    }
+
+    #[test]
+    fn modified_window_works() {
+        use std::time::SystemTime;
+
+        let temp = tempfile::tempdir().unwrap();
+        let (archive, import) = (temp.path().join("archive"), temp.path().join("import"));
+        fs::create_dir(&archive).unwrap();
+        fs::create_dir(&import).unwrap();
+
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(30 * 24 * 60 * 60);
+        let write = |path: PathBuf, contents: &str, modified: SystemTime| {
+            fs::write(&path, contents).unwrap();
+            fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        };
+        write(archive.join("a.txt"), "12345", old);
+        write(archive.join("b.txt"), "abcde", old);
+        write(import.join("a.txt"), "12345", now);
+        write(import.join("b.txt"), "abcde", old);
+        write(import.join("c.txt"), "abcde", old);
+
+        let week_ago = now - Duration::from_secs(7 * 24 * 60 * 60);
+        let results = DupeFinder::builder([&archive, &import]).modified_after(week_ago).build().unwrap().run();
+        assert!(results.is_empty(), "older files should be left out");
+
+        let results = DupeFinder::builder([&import]).reference_directories([&archive]).modified_after(week_ago).build().unwrap().run();
+        assert_eq!(results.len(), 1, "reference files should be matched whatever their age");
+        assert_eq!(results.values().next().unwrap().paths().collect::<Vec<&Path>>(), vec![import.join("a.txt")]);
+
+        let results = DupeFinder::builder([&import]).modified_before(week_ago).build().unwrap().run();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.values().next().unwrap().entries.len(), 2, "only the older copies should be found");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn same_filesystem_works() {
+        use std::os::unix::{fs::symlink, fs::MetadataExt};
+
+        // a tmpfs stands in for a mount below the searched directory
+        let other = match tempfile::tempdir_in("/dev/shm") {
+            Ok(other) => other,
+            Err(_) => return,
+        };
+        let temp = tempfile::tempdir().unwrap();
+        if fs::metadata(other.path()).unwrap().dev() == fs::metadata(temp.path()).unwrap().dev() {
+            return;
+        }
+        fs::write(temp.path().join("a.txt"), "12345").unwrap();
+        fs::write(other.path().join("b.txt"), "12345").unwrap();
+        symlink(other.path(), temp.path().join("mount")).unwrap();
+
+        let run = |same: bool| DupeFinder::builder([temp.path()]).recursive(true).symlinks(SymlinkPolicy::FollowAll).same_filesystem(same).build().unwrap().run();
+        assert_eq!(run(false).len(), 1);
+        assert!(run(true).is_empty(), "other filesystem should not be searched");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hardlink_policy_works() {
+        let temp = tempfile::tempdir().unwrap();
+        let source: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let original = temp.path().join("a.txt");
+        let link = temp.path().join("link.txt");
+        fs::copy(&source, &original).unwrap();
+        fs::hard_link(&original, &link).unwrap();
+
+        let mut checker = DupeFinder::new([temp.path()]);
+        assert_eq!(checker.run().len(), 1);
+
+        let mut checker = DupeFinder::builder([temp.path()]).hardlinks(HardlinkPolicy::Collapse).build().unwrap();
+        let results = checker.run();
+        assert!(results.is_empty(), "links should not be duplicates");
+        assert!(results.hardlinks().is_empty(), "links are only listed when separated");
+
+        let mut checker = DupeFinder::builder([temp.path()]).hardlinks(HardlinkPolicy::Separate).build().unwrap();
+        let results = checker.run();
+        assert!(results.is_empty(), "links should not be duplicates");
+        assert_eq!(results.hardlinks().len(), 1);
+        assert_eq!(results.hardlinks()[0].len(), 2);
+
+        assert!(checker.run_for_file(&original).unwrap().is_none(), "a link is not a duplicate of the searched file");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn special_files_are_skipped() {
+        use std::os::unix::net::UnixListener;
+
+        let temp = tempfile::tempdir().unwrap();
+        let source: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        fs::copy(&source, temp.path().join("a.txt")).unwrap();
+        let socket = temp.path().join("socket");
+        let _listener = UnixListener::bind(&socket).unwrap();
+
+        let mut checker = DupeFinder::new([temp.path()]);
+        checker.run();
+        assert_eq!(checker.skipped_files(), vec![SkippedFile { path: socket.clone(), reason: SkipReason::SpecialFile }]);
+
+        let mut checker = DupeFinder::builder([temp.path()]).special_files(SpecialFilePolicy::Ignore).build().unwrap();
+        checker.run();
+        assert!(checker.skipped_files().is_empty(), "special files should be ignored");
+
+        assert!(checker.run_for_file(&socket).is_err(), "special files can not be searched for");
+
+        // a followed link is judged by its target, a listed file by itself
+        let linked = tempfile::tempdir().unwrap();
+        let link = linked.path().join("link");
+        std::os::unix::fs::symlink(&socket, &link).unwrap();
+        let mut checker = DupeFinder::builder([linked.path()]).symlinks(SymlinkPolicy::FollowFiles).build().unwrap();
+        checker.run();
+        assert_eq!(checker.skipped_files(), vec![SkippedFile { path: link, reason: SkipReason::SpecialFile }]);
+
+        let mut checker = DupeFinder::from_files([&socket]);
+        checker.run();
+        assert_eq!(checker.skipped_files(), vec![SkippedFile { path: socket, reason: SkipReason::SpecialFile }]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_directory_works() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join(std::ffi::OsStr::from_bytes(b"dupes_\xff"));
+        fs::create_dir(&dir).unwrap();
+        let source: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        fs::copy(&source, dir.join("a.txt")).unwrap();
+        fs::copy(&source, dir.join("b.txt")).unwrap();
+
+        let mut checker = DupeFinder::new([&dir]);
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+        assert!(checker.checked_directories.contains(&dir));
+
+        let result = checker.run_for_file(dir.join("a.txt"));
+        assert!(result.is_ok(), "no io error expected");
+        assert_eq!(result.unwrap().unwrap().entries.len(), 2);
+
+        // names told apart only by their invalid bytes are kept as they are
+        let (first, second) = (dir.join(std::ffi::OsStr::from_bytes(b"\xfe.txt")), dir.join(std::ffi::OsStr::from_bytes(b"\xfd.txt")));
+        fs::copy(&source, &first).unwrap();
+        fs::copy(&source, &second).unwrap();
+        let results = checker.run();
+        let duplicate = results.values().next().unwrap();
+        assert_eq!(duplicate.entries.len(), 4);
+        assert!(duplicate.contains(&first) && duplicate.contains(&second));
+        assert!(results.is_duplicate(&second).is_some());
+    }
+
+    #[test]
+    fn with_reference_reports_targets_only() {
+        let reference: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "base"].iter().collect();
+        let target: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+
+        let mut checker = DupeFinder::with_reference([&reference], [&target]);
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+        let duplicate = results.values().next().unwrap();
+        let mut files: Vec<&Path> = duplicate.paths().collect();
+        files.sort();
+        assert_eq!(files, vec![target.join("a.txt"), target.join("b.txt")]);
+        assert_eq!(results.references(&duplicate.hash), [reference.join("a.txt")]);
+        assert_eq!(checker.run_iter().next().unwrap().entries.len(), 2);
+
+        // targets duplicating each other only are not reported
+        let mut checker = DupeFinder::with_reference([&target.join("doesnotexist")], [&target]);
+        assert_eq!(checker.run().len(), 0);
+        assert_eq!(checker.run_iter().count(), 0);
+    }
+
+    #[test]
+    fn with_reference_nested_target() {
+        let reference: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let target = reference.join("dir_b");
+
+        let mut checker = DupeFinder::builder([&target]).reference_directories([&reference]).recursive(true).build().unwrap();
+        let results = checker.run();
+        assert_eq!(results.len(), 1);
+        let duplicate = results.values().next().unwrap();
+        assert_eq!(duplicate.paths().collect::<Vec<&Path>>(), vec![target.join("b.txt")]);
+        assert_eq!(results.references(&duplicate.hash), [reference.join("dir_a").join("a.txt")]);
+    }
+
+    #[test]
+    fn scan_stats_works() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let other: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "folders"].iter().collect();
+
+        let mut checker = DupeFinder::new_recursive([&path, &other]);
+        let results = checker.run();
+        let reclaimable = SpaceForecast::default().remove(&results.values().next().unwrap().entries[1].path, 100);
+        assert_eq!(results.stats(), &ScanStats { files_scanned: 3, files_hashed: 2, bytes_read: 200, duplicate_groups: 1, duplicate_bytes: 200, reclaimable_bytes: reclaimable, truncated: false });
+        assert_eq!(checker.run_partitioned().0.stats(), results.stats(), "counters should be reset between runs");
+
+        let mut checker = DupeFinder::with_reference([&path.join("dir_a")], [&path.join("dir_b")]);
+        assert_eq!(checker.run().stats().reclaimable_bytes, reclaimable, "every target file can be reclaimed");
+    }
+
     fn assert_known_size(checker: &DupeFinder, known_size: u64, expected_files_known: usize, expected_total_sizes: usize, expected_duplicate_sizes: usize) {
         assert_eq!(checker.file_sizes.len(), expected_total_sizes);
         assert_eq!(checker.duplicate_file_sizes.len(), expected_duplicate_sizes);
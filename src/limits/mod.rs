@@ -0,0 +1,52 @@
+use std::path::Path;
+
+// Sanity limits protecting automated scans against corrupted or maliciously
+// constructed filesystems, entries breaking them are recorded as skipped
+#[derive(Clone, Copy, Default)]
+pub struct PathLimits {
+    // longest path, in bytes, of any traversed entry
+    pub max_path_length: Option<usize>,
+    // deepest directory below a provided directory, which sits at depth 0
+    pub max_depth: Option<usize>,
+    // most entries read from a single directory
+    pub max_entries: Option<usize>,
+}
+
+impl PathLimits {
+    pub fn path_too_long(&self, path: &Path) -> bool {
+        self.max_path_length.is_some_and(|max| path.as_os_str().len() > max)
+    }
+
+    pub fn too_deep(&self, depth: usize) -> bool {
+        self.max_depth.is_some_and(|max| depth > max)
+    }
+
+    pub fn too_many_entries(&self, entries: usize) -> bool {
+        self.max_entries.is_some_and(|max| entries > max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_default_unlimited() {
+        let limits = PathLimits::default();
+        assert!(!limits.path_too_long(Path::new(&"a".repeat(10000))));
+        assert!(!limits.too_deep(usize::MAX));
+        assert!(!limits.too_many_entries(usize::MAX));
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = PathLimits { max_path_length: Some(5), max_depth: Some(1), max_entries: Some(2) };
+        assert!(!limits.path_too_long(Path::new("a/b/c")));
+        assert!(limits.path_too_long(Path::new("a/b/cd")));
+        assert!(!limits.too_deep(1));
+        assert!(limits.too_deep(2));
+        assert!(!limits.too_many_entries(2));
+        assert!(limits.too_many_entries(3));
+    }
+}
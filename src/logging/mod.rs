@@ -0,0 +1,141 @@
+use std::{fmt, sync::Mutex};
+use log::{Level, LevelFilter};
+
+/// Part of the scan a diagnostic message originates from
+///
+/// Messages sent to the global logger use `target()` as their log target,
+/// so they can also be filtered by logger implementations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LogCategory {
+    /// Reading directories and walking subdirectories
+    Traversal,
+    /// Reading file metadata
+    Metadata,
+    /// Reading file contents to generate hashes
+    Hashing,
+    /// Acting on found duplicates
+    Actions,
+}
+
+impl LogCategory {
+    /// Log target used for messages of this category
+    pub fn target(&self) -> &'static str {
+        match self {
+            LogCategory::Traversal => "dupefinder::traversal",
+            LogCategory::Metadata => "dupefinder::metadata",
+            LogCategory::Hashing => "dupefinder::hashing",
+            LogCategory::Actions => "dupefinder::actions",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            LogCategory::Traversal => 0,
+            LogCategory::Metadata => 1,
+            LogCategory::Hashing => 2,
+            LogCategory::Actions => 3,
+        }
+    }
+}
+
+/// A diagnostic message collected during a scan
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogMessage {
+    pub category: LogCategory,
+    pub level: Level,
+    pub message: String,
+}
+
+// Per category verbosity and destination of scan messages
+#[derive(Clone)]
+pub struct LogConfig {
+    levels: [LevelFilter; 4],
+    // keep messages on the `DupeFinder` instead of sending them to the global logger
+    pub collect: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> LogConfig {
+        LogConfig {
+            levels: [LevelFilter::Trace; 4],
+            collect: false,
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn set_level(&mut self, category: LogCategory, level: LevelFilter) {
+        self.levels[category.index()] = level;
+    }
+
+    pub fn level(&self, category: LogCategory) -> LevelFilter {
+        self.levels[category.index()]
+    }
+}
+
+// Routes scan messages to the global logger or into a collected list
+#[derive(Default)]
+pub struct ScanLogger {
+    messages: Mutex<Vec<LogMessage>>,
+}
+
+impl ScanLogger {
+    pub fn log(&self, config: &LogConfig, category: LogCategory, level: Level, message: fmt::Arguments) {
+        if level > config.level(category) {
+            return;
+        }
+
+        if config.collect {
+            self.lock().push(LogMessage { category, level, message: message.to_string() });
+        } else {
+            log::log!(target: category.target(), level, "{}", message);
+        }
+    }
+
+    pub fn messages(&self) -> Vec<LogMessage> {
+        self.lock().clone()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<LogMessage>> {
+        self.messages.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_collect_respects_levels() {
+        let mut config = LogConfig { collect: true, ..LogConfig::default() };
+        config.set_level(LogCategory::Hashing, LevelFilter::Error);
+        let logger = ScanLogger::default();
+
+        logger.log(&config, LogCategory::Hashing, Level::Warn, format_args!("filtered"));
+        logger.log(&config, LogCategory::Hashing, Level::Error, format_args!("kept {}", 1));
+        logger.log(&config, LogCategory::Traversal, Level::Debug, format_args!("kept {}", 2));
+
+        let messages = logger.messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0], LogMessage { category: LogCategory::Hashing, level: Level::Error, message: String::from("kept 1") });
+        assert_eq!(messages[1].category, LogCategory::Traversal);
+
+        logger.clear();
+        assert!(logger.messages().is_empty(), "messages should be cleared");
+    }
+
+    #[test]
+    fn test_off_category() {
+        let mut config = LogConfig { collect: true, ..LogConfig::default() };
+        config.set_level(LogCategory::Metadata, LevelFilter::Off);
+        let logger = ScanLogger::default();
+
+        logger.log(&config, LogCategory::Metadata, Level::Error, format_args!("filtered"));
+        assert!(logger.messages().is_empty(), "category is turned off");
+    }
+}
@@ -0,0 +1,156 @@
+use std::{collections::HashMap, fs::File, io::BufReader, path::{Path, PathBuf}};
+use log::Level;
+use mail_parser::{mailbox::{maildir, mbox}, MessageParser, MimeHeaders};
+use crate::{remote::virtual_path, to_path_bufs, DupeEntry, DupeFinder, LogCategory, ScanResults};
+
+// an attachment found in a mailbox, `location` names the message it is part of
+struct Attachment {
+    location: PathBuf,
+    size: u64,
+    hash: String,
+}
+
+impl DupeFinder {
+    /// Runs the search and additionally matches the attachments of the messages in `mailboxes`
+    /// against each other and against the searched files
+    ///
+    /// A mailbox is either a Maildir directory (holding `cur` and `new`) or an mbox file.
+    /// Attachments are listed as `<mbox>#<message number>/<name>` or `<message file>#<name>`,
+    /// which are not real paths. They are hashed in full with the configured hasher, a custom
+    /// `MatchStrategy` only applies to the searched files.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./documents"]);
+    /// for duplicate in checker.run_with_mailboxes(["./mail/archive.mbox", "./Maildir"]).values() {
+    ///     println!("{:?}", duplicate.paths().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn run_with_mailboxes<I, P>(&mut self, mailboxes: I) -> ScanResults
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let duplicates = self.run().into_inner();
+
+        // hash -> (size, attachment entries)
+        let mut found: HashMap<String, (u64, Vec<DupeEntry>)> = HashMap::new();
+        for attachment in to_path_bufs(mailboxes).iter().flat_map(|mailbox| self.mailbox_attachments(mailbox)) {
+            found.entry(attachment.hash).or_insert_with(|| (attachment.size, Vec::new())).1.push(DupeEntry::detached(attachment.location, attachment.size));
+        }
+
+        self.merge_virtual_files(duplicates, found)
+    }
+
+    fn mailbox_attachments(&self, mailbox: &Path) -> Vec<Attachment> {
+        let parser = MessageParser::default();
+        let mut attachments: Vec<Attachment> = Vec::new();
+
+        if mailbox.join("cur").is_dir() {
+            let messages = match maildir::MessageIterator::new(mailbox) {
+                Ok(messages) => messages,
+                Err(e) => {
+                    self.log(LogCategory::Traversal, Level::Warn, format_args!("Error reading Maildir: {}; error: {}", mailbox.display(), e));
+                    return attachments;
+                }
+            };
+
+            for message in messages {
+                match message {
+                    Ok(message) => self.push_attachments(&parser, message.contents(), &virtual_path(message.path(), "#"), &mut attachments),
+                    Err(e) => self.log(LogCategory::Traversal, Level::Warn, format_args!("Error reading message in Maildir: {}; error: {}", mailbox.display(), e)),
+                }
+            }
+        } else {
+            let file = match File::open(mailbox) {
+                Ok(file) => file,
+                Err(e) => {
+                    self.log(LogCategory::Traversal, Level::Warn, format_args!("Error opening mbox: {}; error: {}", mailbox.display(), e));
+                    return attachments;
+                }
+            };
+
+            for (index, message) in mbox::MessageIterator::new(BufReader::new(file)).enumerate() {
+                match message {
+                    Ok(message) => self.push_attachments(&parser, message.contents(), &virtual_path(mailbox, &format!("#{}/", index + 1)), &mut attachments),
+                    Err(e) => self.log(LogCategory::Traversal, Level::Warn, format_args!("Error reading mbox: {}; error: {}", mailbox.display(), e)),
+                }
+            }
+        }
+
+        attachments
+    }
+
+    // hashes every non-empty attachment of the raw message, naming them `<prefix><name>`
+    fn push_attachments(&self, parser: &MessageParser, raw: &[u8], prefix: &Path, attachments: &mut Vec<Attachment>) {
+        let message = match parser.parse(raw) {
+            Some(message) => message,
+            None => {
+                self.log(LogCategory::Metadata, Level::Warn, format_args!("Unable to parse message: {}", prefix.to_string_lossy().trim_end_matches(['#', '/'])));
+                return;
+            }
+        };
+
+        for (position, part) in message.attachments().enumerate() {
+            let mut contents = part.contents();
+            if contents.is_empty() {
+                continue;
+            }
+
+            let location = match part.attachment_name() {
+                Some(name) => virtual_path(prefix, name),
+                None => virtual_path(prefix, &format!("attachment-{}", position + 1)),
+            };
+            let size = contents.len() as u64;
+
+            match self.options.hasher.hash_reader(&mut contents) {
+                Ok(hash) => attachments.push(Attachment { location, size, hash }),
+                Err(e) => self.log(LogCategory::Hashing, Level::Warn, format_args!("Error generating hash for attachment: {}; error: {}", location.display(), e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::fs;
+
+    fn message(attachment: &str) -> String {
+        format!("From: a@example.com\r\nSubject: report\r\nMIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=\"b\"\r\n\r\n--b\r\nContent-Type: text/plain\r\n\r\nsee attached\r\n--b\r\nContent-Type: application/octet-stream\r\nContent-Disposition: attachment; filename=\"report.txt\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n--b--\r\n", attachment)
+    }
+
+    #[test]
+    fn test_attachments_match_files_and_each_other() {
+        let temp = tempfile::tempdir().unwrap();
+        let files = temp.path().join("files");
+        fs::create_dir(&files).unwrap();
+        fs::write(files.join("report.txt"), "attached report").unwrap();
+        fs::write(files.join("other.txt"), "something else!").unwrap();
+
+        // "attached report" in base64
+        let mbox = temp.path().join("archive.mbox");
+        fs::write(&mbox, format!("From a@example.com Mon Jan  1 00:00:00 2024\n{}\nFrom a@example.com Tue Jan  2 00:00:00 2024\n{}", message("YXR0YWNoZWQgcmVwb3J0"), message("b3RoZXIgY29udGVudHMh"))).unwrap();
+
+        let maildir = temp.path().join("Maildir");
+        for folder in ["cur", "new", "tmp"] {
+            fs::create_dir_all(maildir.join(folder)).unwrap();
+        }
+        fs::write(maildir.join("cur").join("1.host"), message("YXR0YWNoZWQgcmVwb3J0")).unwrap();
+
+        let mut checker = DupeFinder::new([&files]);
+        let results = checker.run_with_mailboxes([&mbox, &maildir]);
+        assert_eq!(results.len(), 1);
+
+        let mut found: Vec<&Path> = results.values().next().unwrap().paths().collect();
+        found.sort();
+        let mut expected = vec![
+            files.join("report.txt"),
+            virtual_path(&mbox, "#1/report.txt"),
+            virtual_path(&maildir.join("cur").join("1.host"), "#report.txt"),
+        ];
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+use crate::Duplicate;
+
+/// Marker in a file name suggesting the file is a copy of another
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CopyMarker {
+    /// The name starts with "Copy of" or a translation of it
+    CopyOf,
+    /// The name ends with a number in parentheses, such as "report (1)"
+    Numbered,
+    /// The name ends with a copy word, such as "report - Copy" or "report-kopie"
+    CopySuffix,
+    /// The name ends with a revision tag, such as "report_final" or "report_v2"
+    Revision,
+}
+
+const COPY_PREFIXES: [&str; 4] = ["copy of ", "kopie van ", "kopie von ", "copie de "];
+const COPY_WORDS: [&str; 4] = ["copy", "kopie", "kopia", "copie"];
+const SEPARATORS: [char; 3] = [' ', '-', '_'];
+
+/// Looks for a copy marker in the file name of `path`, the extension is ignored
+///
+/// # Examples
+/// ```
+/// use dupefinder::{copy_marker, CopyMarker};
+///
+/// assert_eq!(copy_marker("photos/Copy of beach.jpg"), Some(CopyMarker::CopyOf));
+/// assert_eq!(copy_marker("photos/beach (1).jpg"), Some(CopyMarker::Numbered));
+/// assert_eq!(copy_marker("photos/beach.jpg"), None);
+/// ```
+pub fn copy_marker<P: AsRef<Path>>(path: P) -> Option<CopyMarker> {
+    let stem = path.as_ref().file_stem()?.to_string_lossy().trim().to_lowercase();
+
+    if COPY_PREFIXES.iter().any(|prefix| stem.starts_with(prefix)) {
+        return Some(CopyMarker::CopyOf);
+    }
+
+    // "report - Copy (2)" is a copy suffix, "report (2)" only numbered
+    let numbered = strip_number(&stem);
+    let base = numbered.unwrap_or(&stem);
+    if COPY_WORDS.iter().any(|word| strip_word(base, word).is_some()) {
+        return Some(CopyMarker::CopySuffix);
+    }
+    if numbered.is_some() {
+        return Some(CopyMarker::Numbered);
+    }
+
+    if strip_word(&stem, "final").is_some() || is_version_tag(&stem) {
+        return Some(CopyMarker::Revision);
+    }
+
+    None
+}
+
+impl Duplicate {
+    /// Files of the set named like a copy of another, see `copy_marker`
+    ///
+    /// Empty when every file or no file carries a marker, the names then give no hint which one is the original.
+    pub fn likely_copies(&self) -> Vec<PathBuf> {
+        let copies: Vec<PathBuf> = self.paths()
+            .filter(|file| copy_marker(file).is_some())
+            .map(Path::to_path_buf)
+            .collect();
+
+        if copies.len() == self.entries.len() {
+            return Vec::new();
+        }
+
+        copies
+    }
+}
+
+// `stem` without a trailing "(n)"
+fn strip_number(stem: &str) -> Option<&str> {
+    let inner = stem.strip_suffix(')')?;
+    let open = inner.rfind('(')?;
+    let digits = &inner[open + 1..];
+
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(inner[..open].trim_end()).filter(|rest| !rest.is_empty())
+}
+
+// `stem` without a trailing `word` that is separated from a non-empty name
+fn strip_word<'a>(stem: &'a str, word: &str) -> Option<&'a str> {
+    let rest = stem.strip_suffix(word)?;
+    if !rest.ends_with(SEPARATORS) {
+        return None;
+    }
+
+    Some(rest.trim_end_matches(SEPARATORS)).filter(|rest| !rest.is_empty())
+}
+
+// a trailing "v2" style tag separated from the rest of the name
+fn is_version_tag(stem: &str) -> bool {
+    match stem.rsplit_once(SEPARATORS) {
+        Some((rest, tag)) => !rest.is_empty() && tag.len() > 1 && tag.starts_with('v') && tag[1..].chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Confidence;
+
+    #[test]
+    fn test_copy_marker() {
+        assert_eq!(copy_marker("Copy of report.doc"), Some(CopyMarker::CopyOf));
+        assert_eq!(copy_marker("/docs/report (12).doc"), Some(CopyMarker::Numbered));
+        assert_eq!(copy_marker("report - Copy.doc"), Some(CopyMarker::CopySuffix));
+        assert_eq!(copy_marker("report - Copy (2).doc"), Some(CopyMarker::CopySuffix));
+        assert_eq!(copy_marker("report-kopie.doc"), Some(CopyMarker::CopySuffix));
+        assert_eq!(copy_marker("report_final.doc"), Some(CopyMarker::Revision));
+        assert_eq!(copy_marker("report_final_v2.doc"), Some(CopyMarker::Revision));
+
+        for original in ["report.doc", "copy.doc", "(1).doc", "photocopy.doc", "ev2.doc", "report_v.doc", "report (a).doc"] {
+            assert_eq!(copy_marker(original), None, "{} should not be a copy", original);
+        }
+    }
+
+    #[test]
+    fn test_likely_copies() {
+        let duplicate = |files: &[&str]| Duplicate { hash: String::from("12345"), entries: crate::duplicate::entries(files), size: 100, confidence: Confidence::FullHash };
+
+        assert_eq!(duplicate(&["/a/report.doc", "/a/report (1).doc"]).likely_copies(), vec![PathBuf::from("/a/report (1).doc")]);
+        assert!(duplicate(&["/a/report (2).doc", "/a/report (1).doc"]).likely_copies().is_empty(), "no original to tell apart");
+        assert!(duplicate(&["/a/report.doc", "/b/report.doc"]).likely_copies().is_empty(), "no markers");
+    }
+}
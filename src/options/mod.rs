@@ -0,0 +1,121 @@
+use std::{path::PathBuf, sync::Arc};
+use crate::{budget::{HashOrder, ScanBudget}, filter::PathFilter, hardlinks::HardlinkPolicy, hasher::{FileHasher, Xxh3Hasher}, strategy::{ContentStrategy, MatchStrategy}, limits::PathLimits, logging::LogConfig, shard::Shard, throttle::ReadThrottle, timeout::Timeouts, SpecialFilePolicy};
+
+/// How symbolic links found while traversing are handled
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SymlinkPolicy {
+    /// Symlinks are left out
+    #[default]
+    Skip,
+    /// Symlinks to files are searched like the file itself, symlinks to directories are left out
+    FollowFiles,
+    /// Symlinks to files and directories are followed, directories reached through more than
+    /// one path are only searched once. Links leading back to a directory holding them are
+    /// skipped with `SkipReason::SymlinkLoop`.
+    FollowAll,
+}
+
+// Holds the configurable behavior of a `DupeFinder`, composed via `DupeFinderBuilder`
+#[derive(Clone)]
+pub struct Options {
+    // recursively traverse all subdirectories
+    pub follow_subdirs: bool,
+    // deepest level of subdirectories searched below a provided directory
+    pub max_depth: Option<usize>,
+    // do not descend into directories on another filesystem than their parent
+    pub same_filesystem: bool,
+    // files below these directories are only matched against, never reported
+    pub references: Vec<PathBuf>,
+    // decides which traversed files are considered
+    pub filter: PathFilter,
+    // handling of FIFOs, sockets and device files
+    pub special_files: SpecialFilePolicy,
+    // handling of symbolic links
+    pub symlinks: SymlinkPolicy,
+    // handling of multiple hard links to the same file
+    pub hardlinks: HardlinkPolicy,
+    // sanity limits on paths, nesting and directory sizes
+    pub limits: PathLimits,
+    // caps on files taken in and bytes hashed per run
+    pub budget: ScanBudget,
+    // order same-size groups are hashed in
+    pub hash_order: HashOrder,
+    // leave out entries matched by `.gitignore` / `.ignore` files while traversing
+    #[cfg(feature = "gitignore")]
+    pub respect_gitignore: bool,
+    // generates the content hash of same-size files
+    pub hasher: Arc<dyn FileHasher>,
+    // groups candidates and decides which of them match
+    pub strategy: Arc<dyn MatchStrategy>,
+    // leading bytes hashed first, only candidates sharing them are hashed in full
+    pub partial_hash: Option<u64>,
+    // compare matching files byte by byte before reporting them
+    pub verify_contents: bool,
+    // sets with fewer files, reference files included, are not reported
+    pub min_copies: usize,
+    // sets whose removable files take up fewer bytes are not reported
+    pub min_group_waste: u64,
+    // keep match keys between runs and only hash files whose size or modification time changed
+    pub incremental: bool,
+    // stop the run at the first file or directory that could not be read
+    pub strict: bool,
+    // `run_for_file` stops at the first confirmed copy of the searched file
+    pub find_first: bool,
+    // per category verbosity and destination of scan messages
+    pub log: LogConfig,
+    // limits on stalled stat / open / read operations of a single file
+    pub timeouts: Timeouts,
+    // read errors tolerated per device before its remaining files are skipped
+    pub device_error_limit: Option<usize>,
+    // further attempts at hashing a file failing with a stale network file handle
+    pub stale_handle_retries: u32,
+    // only files whose candidate key falls into this shard are searched
+    pub shard: Option<Shard>,
+    // shared by the hasher and byte by byte comparisons when reads are rate limited
+    pub read_throttle: Option<Arc<ReadThrottle>>,
+    // hashes candidates across this pool instead of the calling thread
+    #[cfg(feature = "parallel")]
+    pub thread_pool: Option<Arc<rayon::ThreadPool>>,
+    // match keys of unchanged files kept between runs
+    #[cfg(feature = "cache")]
+    pub hash_cache: Option<Arc<crate::cache::HashCache>>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            follow_subdirs: false,
+            max_depth: None,
+            same_filesystem: false,
+            references: Vec::new(),
+            filter: PathFilter::default(),
+            special_files: SpecialFilePolicy::default(),
+            symlinks: SymlinkPolicy::default(),
+            hardlinks: HardlinkPolicy::default(),
+            limits: PathLimits::default(),
+            budget: ScanBudget::default(),
+            hash_order: HashOrder::default(),
+            #[cfg(feature = "gitignore")]
+            respect_gitignore: false,
+            hasher: Arc::new(Xxh3Hasher),
+            strategy: Arc::new(ContentStrategy),
+            partial_hash: None,
+            verify_contents: false,
+            min_copies: 2,
+            min_group_waste: 0,
+            incremental: false,
+            strict: false,
+            find_first: false,
+            log: LogConfig::default(),
+            timeouts: Timeouts::default(),
+            device_error_limit: None,
+            stale_handle_retries: 0,
+            shard: None,
+            read_throttle: None,
+            #[cfg(feature = "parallel")]
+            thread_pool: None,
+            #[cfg(feature = "cache")]
+            hash_cache: None,
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::{cmp::Ordering, fs::Metadata, path::{Path, PathBuf}, time::SystemTime};
+use crate::{copy_marker, space::SpaceForecast, Confidence, DupeEntry, Duplicate, ScanResults};
+
+/// Decides which file of a duplicate set is kept
+///
+/// Ties, and files whose modification time can not be read, fall back to files not named
+/// like a copy (see `copy_marker`), then to the shortest path, then to the path sorting first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum KeepPolicy {
+    /// Keep the file modified longest ago
+    Oldest,
+    /// Keep the most recently modified file
+    Newest,
+    /// Keep the file with the shortest path, preferring files not named like a copy
+    #[default]
+    ShortestPath,
+    /// Keep a file under the first of these directories holding one, sets without
+    /// a file under any of them fall back to the shortest path
+    PriorityRoots(Vec<PathBuf>),
+}
+
+/// Files to retain and to remove for a single duplicate set
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedSet {
+    /// Content hash of the set
+    pub hash: String,
+    /// Size of each file in bytes
+    pub size: u64,
+    /// Files that stay, the reference files for scans with reference directories
+    pub retain: Vec<PathBuf>,
+    /// Files that can be removed without losing their contents
+    pub remove: Vec<PathBuf>,
+    /// Files of the set as the scan found them, actions refuse to touch a set whose files
+    /// changed since. Reference files are not listed.
+    pub scanned: Vec<DupeEntry>,
+}
+
+impl PlannedSet {
+    // `path` still has the size and modification time read by the scan, files the scan did
+    // not record only need the size of the set
+    pub(crate) fn unchanged(&self, path: &Path, meta: &Metadata) -> bool {
+        match self.scanned.iter().find(|entry| entry.path == path) {
+            Some(entry) => meta.len() == entry.size && (entry.modified.is_none() || meta.modified().ok() == entry.modified),
+            None => meta.len() == self.size,
+        }
+    }
+}
+
+/// Explicit list of files to retain and to remove, built from `ScanResults` and a `KeepPolicy`
+///
+/// A plan only lists files, nothing is touched on disk.
+///
+/// # Examples
+/// ```
+/// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+/// let results = checker.run();
+/// let plan = dupefinder::DedupePlan::builder(&results)
+///     .keep(dupefinder::KeepPolicy::Oldest)
+///     .build();
+///
+/// for file in plan.remove() {
+///     println!("remove {}", file.display());
+/// }
+/// println!("{} bytes can be reclaimed", plan.reclaimable_bytes());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DedupePlan {
+    /// Every duplicate set, largest files first
+    pub sets: Vec<PlannedSet>,
+}
+
+/// Composes a `DedupePlan`, see `DedupePlan::builder`
+pub struct DedupePlanBuilder<'a> {
+    results: &'a ScanResults,
+    policy: KeepPolicy,
+    min_confidence: Confidence,
+}
+
+impl DedupePlan {
+    /// Starts a plan for `results`, keeping the file with the shortest path unless configured otherwise
+    pub fn builder(results: &ScanResults) -> DedupePlanBuilder<'_> {
+        DedupePlanBuilder { results, policy: KeepPolicy::default(), min_confidence: Confidence::FullHash }
+    }
+
+    /// All files to retain
+    pub fn retain(&self) -> Vec<&Path> {
+        self.sets.iter().flat_map(|set| set.retain.iter().map(PathBuf::as_path)).collect()
+    }
+
+    /// All files to remove
+    pub fn remove(&self) -> Vec<&Path> {
+        self.sets.iter().flat_map(|set| set.remove.iter().map(PathBuf::as_path)).collect()
+    }
+
+    /// Disk space freed by removing every file in `remove`
+    ///
+    /// Counts allocated blocks rather than file sizes, hard linked files only count once their
+    /// last link is removed. With the `reflink` feature on Linux, files sharing extents with a
+    /// clone count as freeing nothing. Files that can not be inspected count with their size.
+    pub fn reclaimable_bytes(&self) -> u64 {
+        let mut forecast = SpaceForecast::default();
+        self.sets.iter()
+            .flat_map(|set| set.remove.iter().map(|file| (file, set.size)))
+            .map(|(file, size)| forecast.remove(file, size))
+            .sum()
+    }
+}
+
+impl DedupePlanBuilder<'_> {
+    /// Sets the policy deciding which file of each set is kept
+    pub fn keep(mut self, policy: KeepPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Leaves sets whose files were compared less thoroughly than `confidence` out of the plan,
+    /// `Confidence::FullHash` by default so sets matched by size or leading bytes only are never acted on
+    pub fn min_confidence(mut self, confidence: Confidence) -> Self {
+        self.min_confidence = confidence;
+        self
+    }
+
+    /// Decides the files to retain and remove for every set
+    pub fn build(self) -> DedupePlan {
+        let mut sets: Vec<PlannedSet> = self.results.values()
+            .filter(|duplicate| duplicate.confidence >= self.min_confidence)
+            .map(|duplicate| self.plan_set(duplicate))
+            .collect();
+        sets.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.hash.cmp(&b.hash)));
+
+        DedupePlan { sets }
+    }
+
+    fn plan_set(&self, duplicate: &Duplicate) -> PlannedSet {
+        let mut entries: Vec<&DupeEntry> = duplicate.entries.iter().collect();
+        let references = self.results.references(&duplicate.hash);
+
+        // reported files of a reference scan are copies of the reference files
+        let retain = if !references.is_empty() {
+            references.to_vec()
+        } else {
+            let keep = self.keeper(&entries);
+            vec![entries.remove(keep).path.clone()]
+        };
+        let mut files: Vec<PathBuf> = entries.into_iter().map(|entry| entry.path.clone()).collect();
+        files.sort();
+
+        PlannedSet { hash: duplicate.hash.clone(), size: duplicate.size, retain, remove: files, scanned: duplicate.entries.clone() }
+    }
+
+    // index of the file to keep, modification times are those read while scanning
+    fn keeper(&self, entries: &[&DupeEntry]) -> usize {
+        let files: Vec<&Path> = entries.iter().map(|entry| entry.path.as_path()).collect();
+        let copies: Vec<bool> = files.iter().map(|file| copy_marker(file).is_some()).collect();
+
+        let rank = |index: usize| -> (usize, Option<SystemTime>) {
+            match &self.policy {
+                KeepPolicy::PriorityRoots(roots) => (roots.iter().position(|root| files[index].starts_with(root)).unwrap_or(roots.len()), None),
+                KeepPolicy::Oldest | KeepPolicy::Newest => (0, entries[index].modified),
+                _ => (0, None),
+            }
+        };
+
+        (0..files.len())
+            .min_by(|a, b| {
+                let (rank_a, time_a) = rank(*a);
+                let (rank_b, time_b) = rank(*b);
+                rank_a.cmp(&rank_b)
+                    .then_with(|| compare_times(&self.policy, time_a, time_b))
+                    .then_with(|| copies[*a].cmp(&copies[*b]))
+                    .then_with(|| files[*a].as_os_str().len().cmp(&files[*b].as_os_str().len()))
+                    .then_with(|| files[*a].cmp(files[*b]))
+            })
+            .unwrap_or(0)
+    }
+}
+
+// orders the file to keep first, unknown times last
+fn compare_times(policy: &KeepPolicy, a: Option<SystemTime>, b: Option<SystemTime>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if *policy == KeepPolicy::Newest => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::{collections::HashMap, time::Duration};
+
+    // entries of files that do not exist carry no metadata
+    fn results(files: &[&Path]) -> ScanResults {
+        let entries = files.iter()
+            .map(|file| match std::fs::metadata(file) {
+                Ok(meta) => DupeEntry::new(file.to_path_buf(), &meta),
+                Err(_) => crate::duplicate::entries(&[file]).remove(0),
+            })
+            .collect();
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        duplicates.insert(String::from("12345"), Duplicate{hash: String::from("12345"), entries, size: 5, confidence: Confidence::FullHash});
+        ScanResults::new(duplicates)
+    }
+
+    #[test]
+    fn test_keep_policies() {
+        let temp = tempfile::tempdir().unwrap();
+        let old = temp.path().join("archive").join("old_name.txt");
+        let new = temp.path().join("new.txt");
+        std::fs::create_dir(temp.path().join("archive")).unwrap();
+        std::fs::write(&old, "12345").unwrap();
+        std::fs::write(&new, "12345").unwrap();
+        std::fs::File::options().write(true).open(&old).unwrap().set_modified(SystemTime::now() - Duration::from_secs(3600)).unwrap();
+        let results = results(&[&new, &old]);
+
+        let plan = DedupePlan::builder(&results).keep(KeepPolicy::Oldest).build();
+        assert_eq!(plan.retain(), vec![old.as_path()]);
+        assert_eq!(plan.remove(), vec![new.as_path()]);
+        assert_eq!(plan.reclaimable_bytes(), SpaceForecast::default().remove(&new, 5));
+
+        let plan = DedupePlan::builder(&results).keep(KeepPolicy::Newest).build();
+        assert_eq!(plan.retain(), vec![new.as_path()]);
+
+        let plan = DedupePlan::builder(&results).build();
+        assert_eq!(plan.retain(), vec![new.as_path()], "shortest path is the default");
+
+        let plan = DedupePlan::builder(&results).keep(KeepPolicy::PriorityRoots(vec![PathBuf::from("/elsewhere"), temp.path().join("archive")])).build();
+        assert_eq!(plan.retain(), vec![old.as_path()]);
+    }
+
+    #[test]
+    fn test_copies_are_not_kept() {
+        let results = results(&[Path::new("/photos/beach (1).jpg"), Path::new("/photos/beach_2019.jpg")]);
+
+        let plan = DedupePlan::builder(&results).build();
+        assert_eq!(plan.retain(), vec![Path::new("/photos/beach_2019.jpg")]);
+    }
+
+    #[test]
+    fn test_unreadable_times_fall_back() {
+        let results = results(&[Path::new("/missing/longer.txt"), Path::new("/missing/a.txt")]);
+
+        let plan = DedupePlan::builder(&results).keep(KeepPolicy::Oldest).build();
+        assert_eq!(plan.retain(), vec![Path::new("/missing/a.txt")]);
+        assert_eq!(plan.remove(), vec![Path::new("/missing/longer.txt")]);
+        assert_eq!(plan.reclaimable_bytes(), 5, "missing files count with their size");
+
+        let plan = DedupePlan::builder(&results).min_confidence(Confidence::ByteVerified).build();
+        assert!(plan.sets.is_empty(), "hashed sets should be left out");
+    }
+
+    #[test]
+    fn test_unhashed_sets_are_left_out() {
+        let mut duplicate = results(&[Path::new("/missing/a.txt"), Path::new("/missing/b.txt")]).values().next().unwrap().clone();
+        duplicate.confidence = Confidence::SizeOnly;
+        let results = ScanResults::new(HashMap::from([(duplicate.hash.clone(), duplicate)]));
+
+        assert!(DedupePlan::builder(&results).build().sets.is_empty(), "sets matched by size only should be left out by default");
+        assert_eq!(DedupePlan::builder(&results).min_confidence(Confidence::SizeOnly).build().sets.len(), 1);
+    }
+
+    #[test]
+    fn test_references_are_retained() {
+        let reference: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "base"].iter().collect();
+        let target: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let results = crate::DupeFinder::with_reference([&reference], [&target]).run();
+
+        let plan = DedupePlan::builder(&results).build();
+        assert_eq!(plan.retain(), vec![reference.join("a.txt").as_path()]);
+        assert_eq!(plan.remove(), vec![target.join("a.txt").as_path(), target.join("b.txt").as_path()]);
+    }
+}
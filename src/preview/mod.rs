@@ -0,0 +1,143 @@
+use std::{fs::File, io::{self, Read}, path::{Path, PathBuf}};
+#[cfg(feature = "exif")]
+use crate::imagemeta::{read_exif_summary, ExifSummary};
+
+/// Controls how much of a file is extracted into a `Preview`
+#[derive(Clone, Debug)]
+pub struct PreviewOptions {
+    /// Maximum number of leading bytes read for the text preview
+    pub text_bytes: usize,
+    /// Longest edge in pixels of generated thumbnails
+    #[cfg(feature = "thumbnails")]
+    pub thumbnail_size: u32,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> PreviewOptions {
+        PreviewOptions {
+            text_bytes: 256,
+            #[cfg(feature = "thumbnails")]
+            thumbnail_size: 128,
+        }
+    }
+}
+
+/// Small preview of a file's contents so review interfaces can show
+/// what a set of duplicates actually is
+///
+/// # Examples
+/// ```
+/// let options = dupefinder::PreviewOptions::default();
+/// if let Ok(preview) = dupefinder::Preview::new("./test.txt", &options) {
+///     if let Some(text) = preview.text {
+///         println!("{}", text);
+///     }
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preview {
+    /// Full path of the previewed file
+    pub path: PathBuf,
+    /// Leading bytes of the file if they are readable as UTF-8 text
+    pub text: Option<String>,
+    /// PNG encoded thumbnail if the file is a decodable image
+    #[cfg(feature = "thumbnails")]
+    pub thumbnail: Option<Vec<u8>>,
+    /// EXIF summary if the file carries EXIF data
+    #[cfg(feature = "exif")]
+    pub exif: Option<ExifSummary>,
+}
+
+impl Preview {
+    pub fn new<P: AsRef<Path>>(path: P, options: &PreviewOptions) -> Result<Preview, io::Error> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let mut bytes: Vec<u8> = Vec::new();
+        file.take(options.text_bytes as u64).read_to_end(&mut bytes)?;
+
+        Ok(Preview {
+            text: text_preview(&bytes),
+            #[cfg(feature = "thumbnails")]
+            thumbnail: thumbnail_preview(path, options.thumbnail_size),
+            #[cfg(feature = "exif")]
+            exif: read_exif_summary(path)?,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+fn text_preview(bytes: &[u8]) -> Option<String> {
+    // NUL bytes are a strong sign of binary content
+    if bytes.contains(&0) {
+        return None;
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text.to_string()),
+        // the read limit may have cut a multi-byte character in half
+        Err(e) if e.error_len().is_none() => Some(String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned()),
+        Err(_) => None,
+    }
+}
+
+#[cfg(feature = "thumbnails")]
+fn thumbnail_preview(path: &Path, size: u32) -> Option<Vec<u8>> {
+    let image = image::open(path).ok()?;
+    let mut bytes: Vec<u8> = Vec::new();
+    image.thumbnail(size, size).write_to(&mut io::Cursor::new(&mut bytes), image::ImageFormat::Png).ok()?;
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_text_preview() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "insert_size", "test.txt"].iter().collect();
+        let options = PreviewOptions {
+            text_bytes: 9,
+            #[cfg(feature = "thumbnails")]
+            thumbnail_size: 128,
+        };
+
+        let preview = Preview::new(&path, &options);
+        assert!(preview.is_ok(), "no io error expected");
+        assert_eq!(preview.unwrap().text, Some(String::from("this is a")));
+    }
+
+    #[test]
+    fn test_text_preview_binary() {
+        assert_eq!(text_preview(&[0x89, 0x50, 0x00, 0x47]), None);
+        assert_eq!(text_preview(&[0xff, 0xfe, 0x41]), None);
+    }
+
+    #[test]
+    fn test_text_preview_split_character() {
+        let bytes = "café".as_bytes();
+        assert_eq!(text_preview(&bytes[..bytes.len() - 1]), Some(String::from("caf")));
+    }
+
+    #[test]
+    fn test_preview_error() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "doesnotexist.txt"].iter().collect();
+        let preview = Preview::new(&path, &PreviewOptions::default());
+        assert!(preview.is_err(), "io error expected");
+    }
+
+    #[cfg(feature = "thumbnails")]
+    #[test]
+    fn test_thumbnail_preview() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "images", "pixels.png"].iter().collect();
+        let options = PreviewOptions { text_bytes: 256, thumbnail_size: 4 };
+
+        let preview = Preview::new(&path, &options).unwrap();
+        assert!(preview.text.is_none(), "png should not produce a text preview");
+
+        let thumbnail = image::load_from_memory(&preview.thumbnail.unwrap()).unwrap();
+        assert_eq!(thumbnail.width(), 4);
+        assert_eq!(thumbnail.height(), 4);
+    }
+}
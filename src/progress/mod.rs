@@ -0,0 +1,119 @@
+use std::{sync::mpsc, thread, time::Duration};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use crate::{DupeFinder, ScanEvent, ScanResults};
+
+// redraw interval of the spinners while no event arrives
+const TICK: Duration = Duration::from_millis(100);
+
+/// Progress bars fed by the `ScanEvent`s of a run, one counting the directories searched
+/// and one counting the bytes hashed
+///
+/// `DupeFinder::run_with_progress` sets one up on stderr, build one yourself to place the
+/// bars in your own `MultiProgress`.
+///
+/// # Examples
+/// ```
+/// use std::sync::mpsc;
+///
+/// let progress = dupefinder::ScanProgress::new(&indicatif::MultiProgress::new());
+/// let (sender, receiver) = mpsc::channel();
+/// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+/// let results = checker.run_with_events(sender);
+/// for event in receiver {
+///     progress.handle(&event);
+/// }
+/// progress.finish();
+/// ```
+pub struct ScanProgress {
+    discovery: ProgressBar,
+    hashing: ProgressBar,
+}
+
+impl ScanProgress {
+    /// Adds the bars to `multi`
+    pub fn new(multi: &MultiProgress) -> ScanProgress {
+        let discovery = multi.add(spinner("{spinner} {pos} directories searched {msg}"));
+        let hashing = multi.add(spinner("{spinner} {bytes} hashed ({binary_bytes_per_sec}) {msg}"));
+
+        ScanProgress { discovery, hashing }
+    }
+
+    /// Advances the bars by a single event
+    pub fn handle(&self, event: &ScanEvent) {
+        match event {
+            ScanEvent::DirEntered(_) => self.discovery.inc(1),
+            ScanEvent::FileHashed { size, .. } => self.hashing.inc(*size),
+            ScanEvent::FileSkipped { .. } | ScanEvent::Error(_) | ScanEvent::GroupFound(_) => {},
+        }
+    }
+
+    /// Stops the spinners, leaving the final counts on screen
+    pub fn finish(&self) {
+        self.discovery.finish();
+        self.hashing.finish();
+    }
+}
+
+fn spinner(template: &str) -> ProgressBar {
+    let style = ProgressStyle::with_template(template).unwrap_or_else(|_| ProgressStyle::default_spinner());
+    let bar = ProgressBar::new_spinner().with_style(style);
+    bar.enable_steady_tick(TICK);
+    bar
+}
+
+impl DupeFinder {
+    /// Runs the search like `run` while showing its progress on stderr, see `ScanProgress`
+    ///
+    /// Nothing is drawn when stderr is not a terminal.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+    /// let results = checker.run_with_progress();
+    /// ```
+    pub fn run_with_progress(&mut self) -> ScanResults {
+        let progress = ScanProgress::new(&MultiProgress::new());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for event in receiver {
+                    progress.handle(&event);
+                }
+                progress.finish();
+            });
+
+            self.run_with_events(sender)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::path::PathBuf;
+    use indicatif::ProgressDrawTarget;
+
+    #[test]
+    fn test_handle() {
+        let progress = ScanProgress::new(&MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+        progress.handle(&ScanEvent::DirEntered(PathBuf::from("photos")));
+        progress.handle(&ScanEvent::DirEntered(PathBuf::from("photos/2023")));
+        progress.handle(&ScanEvent::FileHashed { path: PathBuf::from("photos/a.jpg"), size: 100 });
+        progress.handle(&ScanEvent::FileHashed { path: PathBuf::from("photos/b.jpg"), size: 50 });
+        progress.finish();
+
+        assert_eq!(progress.discovery.position(), 2);
+        assert_eq!(progress.hashing.position(), 150);
+        assert!(progress.hashing.is_finished());
+    }
+
+    #[test]
+    fn test_run_with_progress() {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinder::new([&path]);
+
+        assert_eq!(checker.run_with_progress().len(), checker.run().len());
+    }
+}
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, fs::Metadata, io, sync::Mutex};
+
+// Counts read errors per device so a failing device can be left alone for the rest of a run
+#[derive(Default)]
+pub struct DeviceQuarantine {
+    errors: Mutex<HashMap<u64, usize>>,
+}
+
+impl DeviceQuarantine {
+    // true once the device holding the file reached `limit` errors
+    pub fn is_quarantined(&self, limit: usize, meta: &Metadata) -> bool {
+        match device_id(meta) {
+            Some(device) => self.lock().get(&device).is_some_and(|errors| *errors >= limit),
+            None => false,
+        }
+    }
+
+    // counts `error` against the device holding the file, returns true when this
+    // error puts the device into quarantine
+    pub fn record_error(&self, limit: usize, meta: &Metadata, error: &io::Error) -> bool {
+        // problems with the file itself say nothing about the health of the device
+        if matches!(error.kind(), io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied) {
+            return false;
+        }
+
+        let device = match device_id(meta) {
+            Some(device) => device,
+            None => return false,
+        };
+
+        let mut errors = self.lock();
+        let count = errors.entry(device).or_default();
+        *count += 1;
+        *count == limit
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, usize>> {
+        self.errors.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn device_id(meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+// the volume of a file is not exposed by stable std outside of unix
+#[cfg(not(unix))]
+pub(crate) fn device_id(_meta: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_quarantine_after_limit() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let meta = std::fs::metadata(path).unwrap();
+        let quarantine = DeviceQuarantine::default();
+
+        assert!(!quarantine.record_error(2, &meta, &io::Error::from(io::ErrorKind::NotFound)), "missing files should not count");
+        assert!(!quarantine.record_error(2, &meta, &io::Error::other("io error")));
+        assert!(!quarantine.is_quarantined(2, &meta), "limit not reached yet");
+        assert!(quarantine.record_error(2, &meta, &io::Error::other("io error")));
+        assert!(quarantine.is_quarantined(2, &meta), "limit reached");
+
+        quarantine.clear();
+        assert!(!quarantine.is_quarantined(2, &meta), "errors should be cleared");
+    }
+}
@@ -0,0 +1,46 @@
+use std::{collections::HashSet, path::{Path, PathBuf}};
+use crate::{DupeEntry, Duplicate};
+
+// files found in a reference directory are matched against but never reported,
+// `reference_dirs` holds every traversed directory tagged as a reference
+pub fn is_reference(reference_dirs: &HashSet<PathBuf>, path: &Path) -> bool {
+    path.parent().is_some_and(|directory| reference_dirs.contains(directory))
+}
+
+// splits the reference files off `duplicate`, sets without a reference or without
+// a target file are dropped
+pub fn split_references(reference_dirs: &HashSet<PathBuf>, duplicate: Duplicate) -> Option<(Duplicate, Vec<PathBuf>)> {
+    let (reference_files, entries): (Vec<DupeEntry>, Vec<DupeEntry>) = duplicate.entries.into_iter()
+        .partition(|entry| is_reference(reference_dirs, &entry.path));
+
+    if reference_files.is_empty() || entries.is_empty() {
+        return None;
+    }
+
+    Some((Duplicate { entries, ..duplicate }, reference_files.into_iter().map(|entry| entry.path).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Confidence;
+
+    fn duplicate(files: &[&str]) -> Duplicate {
+        Duplicate { hash: String::from("12345"), entries: crate::duplicate::entries(files), size: 100, confidence: Confidence::FullHash }
+    }
+
+    #[test]
+    fn test_split_references() {
+        let references: HashSet<PathBuf> = [PathBuf::from("/backup")].into_iter().collect();
+
+        let (targets, reference_files) = split_references(&references, duplicate(&["/backup/a.txt", "/inbox/a.txt", "/inbox/b.txt"])).unwrap();
+        assert_eq!(targets.paths().collect::<Vec<&Path>>(), vec![Path::new("/inbox/a.txt"), Path::new("/inbox/b.txt")]);
+        assert_eq!(reference_files, vec![PathBuf::from("/backup/a.txt")]);
+
+        assert!(split_references(&references, duplicate(&["/inbox/a.txt", "/inbox/b.txt"])).is_none(), "targets only should be dropped");
+        assert!(split_references(&references, duplicate(&["/backup/a.txt", "/backup/b.txt"])).is_none(), "references only should be dropped");
+        assert!(!is_reference(&references, Path::new("/backup2/a.txt")), "only files of tagged directories are references");
+        assert!(!is_reference(&references, Path::new("/backup/nested/a.txt")), "only files of tagged directories are references");
+    }
+}
@@ -0,0 +1,295 @@
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, io::{self, BufRead, Write}};
+use log::Level;
+use crate::{dirdata::DirData, DupeEntry, DupeFinder, Duplicate, LogCategory, ScanResults};
+
+const SIZE_HEADER: &str = "dupefinder-sizes 1";
+const HASH_HEADER: &str = "dupefinder-hashes 1";
+
+/// File sizes present on the local side, the first message of a remote comparison
+///
+/// A remote comparison exchanges only metadata:
+/// 1. the local side sends its `SizeManifest` (`DupeFinder::size_manifest`)
+/// 2. the remote side answers with the hashes of its files sharing those sizes (`DupeFinder::hash_manifest`)
+/// 3. the local side hashes only its files matching a returned size and reports matches (`DupeFinder::run_against`)
+///
+/// Both manifests can be sent over any byte stream via `write_to` / `read_from`.
+///
+/// # Examples
+/// ```
+/// let mut local = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+/// let mut remote = dupefinder::DupeFinder::new(vec!["./resources/base"]);
+///
+/// let sizes = local.size_manifest();
+/// let hashes = remote.hash_manifest(&sizes);
+/// let results = local.run_against(&hashes);
+/// for duplicate in results.values() {
+///     println!("{} local files also exist remotely", duplicate.entries.len());
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SizeManifest {
+    /// Distinct sizes in bytes of the local files
+    pub sizes: BTreeSet<u64>,
+}
+
+/// Content hashes of remote files whose size was requested by a `SizeManifest`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HashManifest {
+    /// Size in bytes -> distinct content hashes of files with that size
+    pub hashes: BTreeMap<u64, BTreeSet<String>>,
+}
+
+impl SizeManifest {
+    /// Writes the manifest as a header line followed by one size per line
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        writeln!(writer, "{}", SIZE_HEADER)?;
+        for size in self.sizes.iter() {
+            writeln!(writer, "{}", size)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a manifest previously written by `write_to`
+    pub fn read_from<R: BufRead>(reader: R) -> Result<SizeManifest, io::Error> {
+        let mut manifest = SizeManifest::default();
+
+        for line in read_body(reader, SIZE_HEADER)? {
+            manifest.sizes.insert(parse_size(&line)?);
+        }
+
+        Ok(manifest)
+    }
+}
+
+impl HashManifest {
+    /// Writes the manifest as a header line followed by one `size hash` pair per line
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        writeln!(writer, "{}", HASH_HEADER)?;
+        for (size, hashes) in self.hashes.iter() {
+            for hash in hashes.iter() {
+                writeln!(writer, "{} {}", size, hash)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a manifest previously written by `write_to`
+    pub fn read_from<R: BufRead>(reader: R) -> Result<HashManifest, io::Error> {
+        let mut manifest = HashManifest::default();
+
+        for line in read_body(reader, HASH_HEADER)? {
+            let (size, hash) = match line.split_once(' ') {
+                Some(pair) => pair,
+                None => return Err(invalid_data(&line)),
+            };
+
+            manifest.hashes.entry(parse_size(size)?).or_default().insert(hash.to_string());
+        }
+
+        Ok(manifest)
+    }
+}
+
+impl DupeFinder {
+    /// Traverses the directories and returns the sizes of all files found
+    pub fn size_manifest(&mut self) -> SizeManifest {
+        self.initialize();
+        self.build_directories();
+
+        SizeManifest { sizes: self.file_sizes.keys().copied().collect() }
+    }
+
+    /// Traverses the directories and hashes only the files sharing a size with `requested`
+    pub fn hash_manifest(&mut self, requested: &SizeManifest) -> HashManifest {
+        self.initialize();
+        self.build_directories();
+
+        let mut manifest = HashManifest::default();
+        for size in requested.sizes.iter() {
+            for (hash, _) in self.hash_files_of_size(*size) {
+                manifest.hashes.entry(*size).or_default().insert(hash);
+            }
+        }
+
+        manifest
+    }
+
+    /// Traverses the directories and returns the local files whose contents exist in `remote`
+    ///
+    /// Only files sharing a size with a remote file are hashed. Each `Duplicate` lists the
+    /// local files only, so it may hold a single file.
+    pub fn run_against(&mut self, remote: &HashManifest) -> ScanResults {
+        self.initialize();
+        self.build_directories();
+
+        let mut results: HashMap<String, Duplicate> = HashMap::new();
+        for (size, remote_hashes) in remote.hashes.iter() {
+            for (hash, entry) in self.hash_files_of_size(*size) {
+                if !remote_hashes.contains(&hash) {
+                    continue;
+                }
+
+                results.entry(hash.clone())
+                    .or_insert_with(|| Duplicate { hash, entries: Vec::new(), size: *size, confidence: self.options.strategy.confidence() })
+                    .entries.push(entry);
+            }
+        }
+
+        ScanResults::new(results)
+    }
+
+    // hashes every known file of `size`, returning (hash, entry) pairs
+    pub(crate) fn hash_files_of_size(&self, size: u64) -> Vec<(String, DupeEntry)> {
+        let candidates: Vec<&DirData> = match self.file_sizes.get(&size) {
+            Some(paths) => paths.iter().collect(),
+            None => return Vec::new(),
+        };
+
+        let mut hashes: Vec<(String, DupeEntry)> = Vec::new();
+        for (data, hash) in candidates.iter().zip(self.hash_candidates(&candidates)) {
+            match hash {
+                Ok(hash) => hashes.push((hash, DupeEntry::new(data.path.clone(), &data.meta))),
+                Err(e) if self.is_stopped_error(&e) => continue,
+                Err(e) => {
+                    self.log(LogCategory::Hashing, Level::Warn, format_args!("Error generating file hash for file: {}; error: {}", data.path.display(), e));
+                    self.record_error(&data.path, LogCategory::Hashing, &e);
+                    continue;
+                }
+            }
+        }
+
+        hashes
+    }
+
+    // adds files that are not on disk (mail attachments, archive members) to the results of a run,
+    // `found` maps their hash to their size and entries. They are matched against each other
+    // and against the searched files of the same size
+    #[cfg(any(feature = "mail", feature = "zip", feature = "tar"))]
+    pub(crate) fn merge_virtual_files(&self, mut duplicates: HashMap<String, Duplicate>, mut found: HashMap<String, (u64, Vec<DupeEntry>)>) -> ScanResults {
+        let sizes: BTreeSet<u64> = found.values().map(|(size, _)| *size).collect();
+        for size in sizes {
+            for (hash, entry) in self.hash_files_of_size(size) {
+                if let Some((_, entries)) = found.get_mut(&hash) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        for (hash, (size, entries)) in found {
+            match duplicates.get_mut(&hash) {
+                Some(duplicate) => {
+                    for entry in entries {
+                        if !duplicate.contains(&entry.path) {
+                            duplicate.entries.push(entry);
+                        }
+                    }
+                    duplicate.confidence = duplicate.confidence.min(crate::Confidence::FullHash);
+                },
+                None if entries.len() > 1 => {
+                    duplicates.insert(hash.clone(), Duplicate { hash, entries, size, confidence: crate::Confidence::FullHash });
+                },
+                None => {},
+            }
+        }
+
+        ScanResults::new(duplicates)
+    }
+}
+
+// the location of a file that is not on disk, `suffix` appended to the path holding it without
+// going through a string, so paths that are not valid UTF-8 are kept as they are
+#[cfg(any(feature = "mail", feature = "zip", feature = "tar"))]
+pub(crate) fn virtual_path(path: &std::path::Path, suffix: &str) -> std::path::PathBuf {
+    let mut location = path.as_os_str().to_owned();
+    location.push(suffix);
+    std::path::PathBuf::from(location)
+}
+
+// checks the header line and returns the remaining non-empty lines
+fn read_body<R: BufRead>(reader: R, header: &str) -> Result<Vec<String>, io::Error> {
+    let mut lines = reader.lines();
+
+    let first = lines.next().transpose()?;
+    if first.as_deref().map(str::trim_end) != Some(header) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("expected header: {}", header)));
+    }
+
+    let mut body: Vec<String> = Vec::new();
+    for line in lines {
+        let line = line?;
+        let line = line.trim_end();
+        if !line.is_empty() {
+            body.push(line.to_string());
+        }
+    }
+
+    Ok(body)
+}
+
+fn parse_size(value: &str) -> Result<u64, io::Error> {
+    value.parse::<u64>().map_err(|_| invalid_data(value))
+}
+
+fn invalid_data(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("malformed manifest line: {}", line))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_remote_compare() {
+        let local_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_with_empty"].iter().collect();
+        let remote_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let mut local = DupeFinder::new([&local_path]);
+        let mut remote = DupeFinder::new_recursive([&remote_path]);
+
+        let sizes = local.size_manifest();
+        assert_eq!(sizes.sizes, BTreeSet::from([100]));
+
+        let hashes = remote.hash_manifest(&sizes);
+        assert_eq!(hashes.hashes[&100], BTreeSet::from([String::from("1577245F909F3D4619DDA56A7B4BA1AF")]));
+
+        let results = local.run_against(&hashes);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results["1577245F909F3D4619DDA56A7B4BA1AF"].entries.len(), 2);
+    }
+
+    #[test]
+    fn test_remote_compare_no_match() {
+        let local_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "insert_size"].iter().collect();
+        let remote_path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut local = DupeFinder::new([&local_path]);
+        let mut remote = DupeFinder::new([&remote_path]);
+
+        let hashes = remote.hash_manifest(&local.size_manifest());
+        assert!(hashes.hashes.is_empty(), "no remote file shares a size");
+        assert_eq!(local.run_against(&hashes).len(), 0);
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let sizes = SizeManifest { sizes: BTreeSet::from([44, 100]) };
+        let mut buffer: Vec<u8> = Vec::new();
+        sizes.write_to(&mut buffer).unwrap();
+        assert_eq!(SizeManifest::read_from(buffer.as_slice()).unwrap(), sizes);
+
+        let mut hashes = HashManifest::default();
+        hashes.hashes.insert(100, BTreeSet::from([String::from("AB"), String::from("CD")]));
+        let mut buffer: Vec<u8> = Vec::new();
+        hashes.write_to(&mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer.clone()).unwrap(), "dupefinder-hashes 1\n100 AB\n100 CD\n");
+        assert_eq!(HashManifest::read_from(buffer.as_slice()).unwrap(), hashes);
+    }
+
+    #[test]
+    fn test_manifest_malformed() {
+        assert!(SizeManifest::read_from("100\n".as_bytes()).is_err(), "missing header");
+        assert!(SizeManifest::read_from("dupefinder-sizes 1\nabc\n".as_bytes()).is_err(), "invalid size");
+        assert!(HashManifest::read_from("dupefinder-hashes 1\n100\n".as_bytes()).is_err(), "missing hash");
+    }
+}
@@ -0,0 +1,152 @@
+//! CSV export with one row per file of every duplicate set
+//!
+//! Files of the same set share the `hash` and `size` columns:
+//!
+//! ```text
+//! hash,size,path
+//! 1577245F909F3D4619DDA56A7B4BA1AF,100,./a.txt
+//! 1577245F909F3D4619DDA56A7B4BA1AF,100,./b.txt
+//! ```
+//!
+//! Fields holding a comma, quote or line break are quoted as described by RFC 4180. With
+//! `PathEncoding::Base64` a `path_base64` column holds the base64 bytes of invalid paths
+//! and is empty for valid ones.
+//!
+//! `CsvWriter::with_summary` ends the export with the `ScanStats` of the scan as a `#` comment line,
+//! which readers need to be told to skip:
+//!
+//! ```text
+//! # 5 files scanned, 2 hashed, 1 duplicate sets of 200 bytes, 100 bytes reclaimable
+//! ```
+
+use std::io::{self, Write};
+use crate::{report::{PathEncoding, ReportWriter}, Duplicate, ScanStats};
+
+/// `ReportWriter` of the CSV export, the header row is written with the first set
+///
+/// # Examples
+/// ```
+/// let mut checker = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+/// let mut report = dupefinder::report::csv::CsvWriter::new(std::io::stdout());
+/// dupefinder::report::write_results(&checker.run(), &mut report).unwrap();
+/// ```
+pub struct CsvWriter<W: Write> {
+    writer: W,
+    encoding: PathEncoding,
+    header_written: bool,
+    summary: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// A writer writing paths that are not valid UTF-8 lossily
+    pub fn new(writer: W) -> CsvWriter<W> {
+        CsvWriter::with_encoding(writer, PathEncoding::default())
+    }
+
+    /// A writer representing paths that are not valid UTF-8 according to `encoding`
+    pub fn with_encoding(writer: W, encoding: PathEncoding) -> CsvWriter<W> {
+        CsvWriter { writer, encoding, header_written: false, summary: false }
+    }
+
+    /// Ends the export with a comment line summarizing the scan
+    pub fn with_summary(self) -> CsvWriter<W> {
+        CsvWriter { summary: true, ..self }
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_header(&mut self) -> Result<(), io::Error> {
+        if self.header_written {
+            return Ok(());
+        }
+
+        self.header_written = true;
+        match self.encoding {
+            PathEncoding::Base64 => writeln!(self.writer, "hash,size,path,path_base64"),
+            _ => writeln!(self.writer, "hash,size,path"),
+        }
+    }
+}
+
+impl<W: Write> ReportWriter for CsvWriter<W> {
+    fn write_group(&mut self, duplicate: &Duplicate) -> Result<(), io::Error> {
+        self.write_header()?;
+
+        for file in duplicate.paths() {
+            let (path, raw) = self.encoding.encode(file);
+            match self.encoding {
+                PathEncoding::Base64 => writeln!(self.writer, "{},{},{},{}", quote(&duplicate.hash), duplicate.size, quote(&path), raw.unwrap_or_default())?,
+                _ => writeln!(self.writer, "{},{},{}", quote(&duplicate.hash), duplicate.size, quote(&path))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self, stats: &ScanStats) -> Result<(), io::Error> {
+        self.write_header()?;
+        if self.summary {
+            writeln!(self.writer, "# {}", stats)?;
+        }
+        self.writer.flush()
+    }
+}
+
+// quotes `value` when it holds a separator, quote or line break
+fn quote(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Confidence;
+
+    #[test]
+    fn test_write_group() {
+        let duplicate = Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b, \"quoted\".txt"]), size: 100, confidence: Confidence::FullHash};
+
+        let mut report = CsvWriter::new(Vec::new());
+        report.write_group(&duplicate).unwrap();
+        report.finish(&ScanStats::default()).unwrap();
+
+        assert_eq!(String::from_utf8(report.into_inner()).unwrap(), "hash,size,path\n12345,100,a.txt\n12345,100,\"b, \"\"quoted\"\".txt\"\n");
+    }
+
+    #[test]
+    fn test_write_summary() {
+        let mut report = CsvWriter::new(Vec::new()).with_summary();
+        report.finish(&ScanStats { files_scanned: 2, ..ScanStats::default() }).unwrap();
+
+        assert_eq!(String::from_utf8(report.into_inner()).unwrap(), "hash,size,path\n# 2 files scanned, 0 hashed, 0 duplicate sets of 0 bytes, 0 bytes reclaimable\n");
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let mut report = CsvWriter::with_encoding(Vec::new(), PathEncoding::Base64);
+        report.finish(&ScanStats::default()).unwrap();
+
+        assert_eq!(String::from_utf8(report.into_inner()).unwrap(), "hash,size,path,path_base64\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_encoded() {
+        use std::os::unix::ffi::OsStrExt;
+        let invalid = std::path::PathBuf::from(std::ffi::OsStr::from_bytes(b"a\xff.txt"));
+        let duplicate = Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&[invalid, std::path::PathBuf::from("b.txt")]), size: 100, confidence: Confidence::FullHash};
+
+        let mut report = CsvWriter::with_encoding(Vec::new(), PathEncoding::Base64);
+        report.write_group(&duplicate).unwrap();
+
+        assert_eq!(String::from_utf8(report.into_inner()).unwrap(), "hash,size,path,path_base64\n12345,100,a\u{FFFD}.txt,Yf8udHh0\n12345,100,b.txt,\n");
+    }
+}
@@ -0,0 +1,310 @@
+//! JSON export of a full result set
+//!
+//! The document holds a `summary`, every duplicate set in `groups` ordered by size,
+//! largest first, and the `hardlinks` sets of the scan:
+//!
+//! ```text
+//! {
+//!   "summary": {"groups": 1, "files": 2, "files_scanned": 5, "files_hashed": 2, "bytes_read": 200, "duplicate_bytes": 200, "reclaimable_bytes": 100, "truncated": false},
+//!   "groups": [
+//!     {"hash": "1577245F909F3D4619DDA56A7B4BA1AF", "size": 100, "files": ["./a.txt", "./b.txt"], "references": []}
+//!   ],
+//!   "hardlinks": []
+//! }
+//! ```
+//!
+//! `files` counts the files of the written sets, every other summary field is taken from the
+//! `ScanStats` of the scan, see there for how they are counted.
+//!
+//! `write_encoded` configures how paths that are not valid UTF-8 are written, see `PathEncoding`.
+//! Both write through `JsonWriter`, which can also be fed sets as they are found.
+//!
+//! `write_treemap` exports the per directory totals of `DupeFinder::run_treemap` as an array of
+//! `{"path", "files", "unique_bytes", "duplicate_bytes"}` objects.
+
+use std::{io::{self, Write}, path::Path};
+use crate::{report::{write_results, PathEncoding, ReportWriter}, Duplicate, ScanResults, ScanStats, TreemapNode};
+
+/// Writes `results` as a JSON document to `writer`, paths that are not valid UTF-8 are written lossily
+pub fn write<W: Write>(results: &ScanResults, writer: &mut W) -> Result<(), io::Error> {
+    write_encoded(results, PathEncoding::default(), writer)
+}
+
+/// Writes `results` as a JSON document to `writer`, representing paths that are not valid UTF-8
+/// according to `encoding`. With `PathEncoding::Base64` every path field or array `name` gets a
+/// `name_base64` sibling holding the base64 bytes of invalid paths and `null` for valid ones.
+pub fn write_encoded<W: Write>(results: &ScanResults, encoding: PathEncoding, writer: &mut W) -> Result<(), io::Error> {
+    write_results(results, &mut JsonWriter::with_encoding(writer, encoding).with_results(results))
+}
+
+/// `ReportWriter` of the JSON document, the sets are kept in memory until the report is finished
+/// as the summary leads the document
+///
+/// The summary holds the `ScanStats` handed to `finish`.
+/// Without `with_results` the `references` and `hardlinks` arrays are empty.
+///
+/// # Examples
+/// ```
+/// use dupefinder::report::{json::JsonWriter, ReportWriter};
+///
+/// let mut checker = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+/// let mut report = JsonWriter::new(std::io::stdout());
+/// for duplicate in checker.run_iter() {
+///     report.write_group(&duplicate).unwrap();
+/// }
+/// report.finish(&dupefinder::ScanStats::default()).unwrap();
+/// ```
+pub struct JsonWriter<'a, W: Write> {
+    writer: W,
+    encoding: PathEncoding,
+    results: Option<&'a ScanResults>,
+    groups: Vec<String>,
+    files: usize,
+}
+
+impl<'a, W: Write> JsonWriter<'a, W> {
+    /// A writer writing paths that are not valid UTF-8 lossily
+    pub fn new(writer: W) -> JsonWriter<'a, W> {
+        JsonWriter::with_encoding(writer, PathEncoding::default())
+    }
+
+    /// A writer representing paths that are not valid UTF-8 according to `encoding`
+    pub fn with_encoding(writer: W, encoding: PathEncoding) -> JsonWriter<'a, W> {
+        JsonWriter { writer, encoding, results: None, groups: Vec::new(), files: 0 }
+    }
+
+    /// Takes the reference files of each set and the hard link sets from `results`
+    pub fn with_results(self, results: &'a ScanResults) -> JsonWriter<'a, W> {
+        JsonWriter { results: Some(results), ..self }
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> ReportWriter for JsonWriter<'_, W> {
+    fn write_group(&mut self, duplicate: &Duplicate) -> Result<(), io::Error> {
+        let files: Vec<&Path> = duplicate.paths().collect();
+        let references = self.results.map_or(&[][..], |results| results.references(&duplicate.hash));
+        self.groups.push(format!("{{\"hash\": {}, \"size\": {}, {}, {}}}", string(&duplicate.hash), duplicate.size,
+            field("files", path_array(&files, self.encoding)), field("references", path_array(references, self.encoding))));
+
+        self.files += duplicate.entries.len();
+        Ok(())
+    }
+
+    fn finish(&mut self, stats: &ScanStats) -> Result<(), io::Error> {
+        let writer = &mut self.writer;
+        writeln!(writer, "{{")?;
+        writeln!(writer, "  \"summary\": {{\"groups\": {}, \"files\": {}, \"files_scanned\": {}, \"files_hashed\": {}, \"bytes_read\": {}, \"duplicate_bytes\": {}, \"reclaimable_bytes\": {}, \"truncated\": {}}},",
+            stats.duplicate_groups, self.files, stats.files_scanned, stats.files_hashed, stats.bytes_read, stats.duplicate_bytes, stats.reclaimable_bytes, stats.truncated)?;
+
+        writeln!(writer, "  \"groups\": [")?;
+        write_lines(writer, &self.groups)?;
+        writeln!(writer, "  ],")?;
+
+        let hardlinks = self.results.map_or(&[][..], |results| results.hardlinks());
+        let (hardlinks, hardlinks_raw): (Vec<String>, Vec<Option<String>>) = hardlinks.iter().map(|links| path_array(links, self.encoding)).unzip();
+        writeln!(writer, "  \"hardlinks\": [")?;
+        write_lines(writer, &hardlinks)?;
+        if self.encoding == PathEncoding::Base64 {
+            writeln!(writer, "  ],")?;
+            writeln!(writer, "  \"hardlinks_base64\": [")?;
+            write_lines(writer, &hardlinks_raw.into_iter().flatten().collect::<Vec<String>>())?;
+        }
+        writeln!(writer, "  ]")?;
+
+        writeln!(writer, "}}")?;
+        writer.flush()
+    }
+}
+
+/// Writes the directory totals of `DupeFinder::run_treemap` as a JSON array to `writer`
+pub fn write_treemap<W: Write>(nodes: &[TreemapNode], writer: &mut W) -> Result<(), io::Error> {
+    write_treemap_encoded(nodes, PathEncoding::default(), writer)
+}
+
+/// Like `write_treemap`, representing paths that are not valid UTF-8 according to `encoding`
+pub fn write_treemap_encoded<W: Write>(nodes: &[TreemapNode], encoding: PathEncoding, writer: &mut W) -> Result<(), io::Error> {
+    let nodes: Vec<String> = nodes.iter()
+        .map(|node| format!("{{{}, \"files\": {}, \"unique_bytes\": {}, \"duplicate_bytes\": {}}}", field("path", path(&node.path, encoding)), node.files, node.unique_bytes, node.duplicate_bytes))
+        .collect();
+
+    writeln!(writer, "[")?;
+    write_lines(writer, &nodes)?;
+    writeln!(writer, "]")
+}
+
+// one array element per line
+fn write_lines<W: Write>(writer: &mut W, elements: &[String]) -> Result<(), io::Error> {
+    for (index, element) in elements.iter().enumerate() {
+        writeln!(writer, "    {}{}", element, if index + 1 < elements.len() { "," } else { "" })?;
+    }
+
+    Ok(())
+}
+
+// `"name": value`, followed by `"name_base64": raw` when there is a raw value
+pub(super) fn field(name: &str, (value, raw): (String, Option<String>)) -> String {
+    match raw {
+        Some(raw) => format!("{}: {}, {}: {}", string(name), value, string(&format!("{}_base64", name)), raw),
+        None => format!("{}: {}", string(name), value),
+    }
+}
+
+// the JSON string of `path` and, with `PathEncoding::Base64`, its base64 bytes or null
+fn path(path: &Path, encoding: PathEncoding) -> (String, Option<String>) {
+    let (text, raw) = encoding.encode(path);
+    let raw = match encoding {
+        PathEncoding::Base64 => Some(raw.map_or(String::from("null"), |raw| string(&raw))),
+        _ => None,
+    };
+
+    (string(&text), raw)
+}
+
+pub(super) fn path_array<P: AsRef<Path>>(paths: &[P], encoding: PathEncoding) -> (String, Option<String>) {
+    let (values, raw): (Vec<String>, Vec<Option<String>>) = paths.iter().map(|value| path(value.as_ref(), encoding)).unzip();
+    let raw: Option<Vec<String>> = raw.into_iter().collect();
+
+    (format!("[{}]", values.join(", ")), raw.map(|raw| format!("[{}]", raw.join(", "))))
+}
+
+// quotes `value` as a JSON string
+pub(super) fn string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Confidence;
+    use std::{collections::HashMap, path::PathBuf};
+
+    #[test]
+    fn test_write() {
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        duplicates.insert(String::from("12345"), Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b \"quoted\"\n.txt"]), size: 100, confidence: Confidence::FullHash});
+        duplicates.insert(String::from("67890"), Duplicate{hash: String::from("67890"), entries: crate::duplicate::entries(&["c.txt", "d.txt", "e.txt"]), size: 200, confidence: Confidence::FullHash});
+        let stats = ScanStats { files_scanned: 6, files_hashed: 5, bytes_read: 800, duplicate_groups: 2, duplicate_bytes: 800, reclaimable_bytes: 500, truncated: true };
+        let results = ScanResults::new(duplicates).with_hardlinks(vec![vec![PathBuf::from("f.txt"), PathBuf::from("g.txt")]]).with_stats(stats);
+
+        let mut report: Vec<u8> = Vec::new();
+        write(&results, &mut report).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&report).unwrap();
+
+        assert_eq!(json["summary"]["groups"], 2);
+        assert_eq!(json["summary"]["files"], 5);
+        assert_eq!(json["summary"]["files_scanned"], 6);
+        assert_eq!(json["summary"]["files_hashed"], 5);
+        assert_eq!(json["summary"]["bytes_read"], 800);
+        assert_eq!(json["summary"]["duplicate_bytes"], 800);
+        assert_eq!(json["summary"]["reclaimable_bytes"], 500);
+        assert_eq!(json["summary"]["truncated"], true);
+        assert_eq!(json["groups"][0]["hash"], "67890");
+        assert_eq!(json["groups"][1]["files"][1], "b \"quoted\"\n.txt");
+        assert_eq!(json["hardlinks"][0][1], "g.txt");
+    }
+
+    #[test]
+    fn test_json_writer_without_results() {
+        let duplicate = Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b.txt"]), size: 100, confidence: Confidence::FullHash};
+
+        let mut report = JsonWriter::new(Vec::new());
+        report.write_group(&duplicate).unwrap();
+        report.finish(&ScanStats { duplicate_groups: 1, reclaimable_bytes: 100, ..ScanStats::default() }).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&report.into_inner()).unwrap();
+
+        assert_eq!(json["summary"]["groups"], 1);
+        assert_eq!(json["summary"]["files"], 2);
+        assert_eq!(json["summary"]["reclaimable_bytes"], 100);
+        assert_eq!(json["groups"][0]["files"][1], "b.txt");
+        assert!(json["groups"][0]["references"].as_array().unwrap().is_empty());
+        assert!(json["hardlinks"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_write_references() {
+        let base: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let mut checker = crate::DupeFinder::builder([base.join("dupes")]).reference_directories([base.join("base")]).build().unwrap();
+        let results = checker.run();
+        assert!(!results.is_empty(), "sets expected");
+
+        let mut report: Vec<u8> = Vec::new();
+        write(&results, &mut report).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&report).unwrap();
+
+        let stats = results.stats();
+        assert_eq!(json["summary"]["groups"], stats.duplicate_groups);
+        assert_eq!(json["summary"]["files_scanned"], stats.files_scanned);
+        assert_eq!(json["summary"]["files_hashed"], stats.files_hashed);
+        assert_eq!(json["summary"]["duplicate_bytes"], stats.duplicate_bytes);
+        assert_eq!(json["summary"]["reclaimable_bytes"], stats.reclaimable_bytes);
+        assert!(stats.files_scanned > 0 && stats.reclaimable_bytes > 0, "scan stats expected");
+        assert!(!json["groups"][0]["references"].as_array().unwrap().is_empty(), "reference files expected");
+    }
+
+    #[test]
+    fn test_write_treemap() {
+        let nodes = vec![TreemapNode { path: PathBuf::from("photos"), files: 3, unique_bytes: 100, duplicate_bytes: 200 }];
+
+        let mut report: Vec<u8> = Vec::new();
+        write_treemap(&nodes, &mut report).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&report).unwrap();
+
+        assert_eq!(json[0]["path"], "photos");
+        assert_eq!(json[0]["files"], 3);
+        assert_eq!(json[0]["duplicate_bytes"], 200);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_encoded() {
+        use std::os::unix::ffi::OsStrExt;
+        let invalid = std::path::PathBuf::from(std::ffi::OsStr::from_bytes(b"a\xff.txt"));
+
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        duplicates.insert(String::from("12345"), Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b.txt"]), size: 100, confidence: Confidence::FullHash});
+        let results = ScanResults::new(duplicates).with_hardlinks(vec![vec![invalid.clone(), PathBuf::from("c.txt")]]);
+
+        let mut report: Vec<u8> = Vec::new();
+        write_encoded(&results, PathEncoding::Base64, &mut report).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&report).unwrap();
+        assert_eq!(json["groups"][0]["files_base64"], serde_json::json!([null, null]));
+        assert_eq!(json["hardlinks"][0][0], "a\u{FFFD}.txt");
+        assert_eq!(json["hardlinks_base64"][0], serde_json::json!(["Yf8udHh0", null]));
+
+        let mut report: Vec<u8> = Vec::new();
+        write_treemap_encoded(&[TreemapNode { path: invalid, ..TreemapNode::default() }], PathEncoding::Percent, &mut report).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&report).unwrap();
+        assert_eq!(json[0]["path"], "a%FF.txt");
+        assert!(json[0].get("path_base64").is_none(), "base64 only with PathEncoding::Base64");
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let mut report: Vec<u8> = Vec::new();
+        write(&ScanResults::default(), &mut report).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&report).unwrap();
+
+        assert_eq!(json["summary"]["groups"], 0);
+        assert!(json["groups"].as_array().unwrap().is_empty());
+    }
+}
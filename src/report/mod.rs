@@ -0,0 +1,214 @@
+//! Writers exporting scan results in formats other tools can read
+//!
+//! Paths that are not valid UTF-8 can not be written as text as they are, `PathEncoding`
+//! decides how exports represent them.
+//!
+//! # Examples
+//! ```
+//! let mut checker = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+//! let results = checker.run();
+//!
+//! let mut report: Vec<u8> = Vec::new();
+//! if dupefinder::report::json::write(&results, &mut report).is_ok() {
+//!     println!("{}", String::from_utf8_lossy(&report));
+//! }
+//! ```
+//!
+//! Every format is written by a `ReportWriter`, which custom formats can implement as well.
+//! `write_results` feeds a finished scan to any of them.
+
+use std::{io, path::Path};
+use crate::{Duplicate, ScanResults, ScanStats};
+
+pub mod csv;
+pub mod json;
+pub mod ndjson;
+pub mod text;
+
+/// Prefixed to paths written lossily by `PathEncoding::LossyMarked`
+pub const LOSSY_MARKER: &str = "\u{FFFD}lossy:";
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How exports write paths that are not valid UTF-8, valid paths are written as they are
+/// unless noted otherwise
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PathEncoding {
+    /// Invalid sequences are replaced with U+FFFD, distinct paths may end up identical
+    #[default]
+    Lossy,
+    /// Like `Lossy`, additionally prefixed with `LOSSY_MARKER` so lossy paths can be told apart
+    LossyMarked,
+    /// Invalid bytes are written as `%XX`, every `%` of every path is written as `%25`
+    /// so the original bytes can always be restored
+    Percent,
+    /// Like `Lossy`, additionally the exact bytes of invalid paths are written base64 encoded
+    /// next to the text, how depends on the format
+    Base64,
+}
+
+/// Output format receiving duplicate sets one at a time
+///
+/// # Examples
+/// ```
+/// use std::io;
+/// use dupefinder::{report::ReportWriter, Duplicate, ScanStats};
+///
+/// // one line per set holding its size and file count
+/// struct Sizes;
+///
+/// impl ReportWriter for Sizes {
+///     fn write_group(&mut self, duplicate: &Duplicate) -> Result<(), io::Error> {
+///         println!("{} {}", duplicate.size, duplicate.entries.len());
+///         Ok(())
+///     }
+///
+///     fn finish(&mut self, stats: &ScanStats) -> Result<(), io::Error> {
+///         println!("{} bytes reclaimable", stats.reclaimable_bytes);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut checker = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+/// dupefinder::report::write_results(&checker.run(), &mut Sizes).unwrap();
+/// ```
+pub trait ReportWriter {
+    /// Writes a single duplicate set
+    fn write_group(&mut self, duplicate: &Duplicate) -> Result<(), io::Error>;
+
+    /// Completes the report once every set was written, `stats` summarizes the scan
+    fn finish(&mut self, stats: &ScanStats) -> Result<(), io::Error>;
+}
+
+/// Writes every set of `results` to `report`, the largest files first with ties ordered by hash,
+/// then finishes it with the stats of the scan
+pub fn write_results<R: ReportWriter + ?Sized>(results: &ScanResults, report: &mut R) -> Result<(), io::Error> {
+    let mut groups: Vec<&Duplicate> = results.values().collect();
+    groups.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.hash.cmp(&b.hash)));
+
+    for duplicate in groups {
+        report.write_group(duplicate)?;
+    }
+
+    report.finish(results.stats())
+}
+
+impl PathEncoding {
+    // the text written for `path` and, for `Base64`, the base64 of invalid paths
+    pub(crate) fn encode(&self, path: &Path) -> (String, Option<String>) {
+        let bytes = path.as_os_str().as_encoded_bytes();
+        let valid = path.to_str();
+
+        match (self, valid) {
+            (PathEncoding::Percent, _) => (percent_encode(bytes), None),
+            (_, Some(valid)) => (valid.to_string(), None),
+            (PathEncoding::Lossy, None) => (path.to_string_lossy().into_owned(), None),
+            (PathEncoding::LossyMarked, None) => (format!("{}{}", LOSSY_MARKER, path.to_string_lossy()), None),
+            (PathEncoding::Base64, None) => (path.to_string_lossy().into_owned(), Some(base64_encode(bytes))),
+        }
+    }
+}
+
+fn percent_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '%' => encoded.push_str("%25"),
+                c => encoded.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    encoded
+}
+
+// the path written as `text` by `PathEncoding::Percent`, `None` for malformed escapes and, outside
+// of unix, for bytes that are not valid UTF-8
+#[cfg(feature = "serde")]
+pub(crate) fn percent_decode(text: &str) -> Option<std::path::PathBuf> {
+    let mut decoded: Vec<u8> = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'%' {
+            decoded.push(byte);
+            continue;
+        }
+
+        let hex = std::str::from_utf8(rest.get(..2)?).ok().filter(|hex| hex.bytes().all(|digit| digit.is_ascii_hexdigit()))?;
+        decoded.push(u8::from_str_radix(hex, 16).ok()?);
+        rest = &rest[2..];
+    }
+
+    #[cfg(unix)]
+    return Some(std::path::PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(decoded)));
+    #[cfg(not(unix))]
+    return String::from_utf8(decoded).ok().map(std::path::PathBuf::from);
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(value >> (18 - index * 6)) as usize & 0x3F] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_valid_paths() {
+        let path = Path::new("photos/100% done.jpg");
+        assert_eq!(PathEncoding::Lossy.encode(path), (String::from("photos/100% done.jpg"), None));
+        assert_eq!(PathEncoding::Base64.encode(path), (String::from("photos/100% done.jpg"), None));
+        assert_eq!(PathEncoding::Percent.encode(path), (String::from("photos/100%25 done.jpg"), None));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_invalid_paths() {
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"dir/a\xffb"));
+
+        assert_eq!(PathEncoding::Lossy.encode(path), (String::from("dir/a\u{FFFD}b"), None));
+        assert_eq!(PathEncoding::LossyMarked.encode(path), (format!("{}dir/a\u{FFFD}b", LOSSY_MARKER), None));
+        assert_eq!(PathEncoding::Percent.encode(path), (String::from("dir/a%FFb"), None));
+        assert_eq!(PathEncoding::Base64.encode(path), (String::from("dir/a\u{FFFD}b"), Some(String::from("ZGlyL2H/Yg=="))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_percent_decode() {
+        use std::path::PathBuf;
+        #[cfg(unix)]
+        assert_eq!(percent_decode("dir/a%FFb"), Some(PathBuf::from(<std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(b"dir/a\xffb"))));
+        assert_eq!(percent_decode("photos/100%25 done.jpg"), Some(PathBuf::from("photos/100% done.jpg")));
+        assert_eq!(percent_decode("a%2"), None);
+        assert_eq!(percent_decode("a%+1"), None);
+        assert_eq!(percent_decode("a%é"), None);
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,113 @@
+//! Newline delimited JSON stream of duplicate sets
+//!
+//! Every set is written as one JSON object on its own line as soon as it is handed to the sink,
+//! so other processes can read the sets over a pipe while the scan is still running:
+//!
+//! ```text
+//! {"hash": "1577245F909F3D4619DDA56A7B4BA1AF", "size": 100, "files": ["./a.txt", "./b.txt"]}
+//! ```
+//!
+//! Paths that are not valid UTF-8 are written according to the `PathEncoding` of the sink,
+//! with `PathEncoding::Base64` `files` gets a `files_base64` sibling like in the JSON report.
+
+use std::{io::{self, Write}, path::Path};
+use crate::{report::{json::{field, path_array, string}, PathEncoding, ReportWriter}, Duplicate, ScanStats};
+
+/// `ReportWriter` writing duplicate sets as newline delimited JSON, flushing after every set
+///
+/// # Examples
+/// ```
+/// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+/// let mut sink = dupefinder::report::ndjson::NdjsonSink::new(std::io::stdout());
+/// if let Err(e) = sink.write_groups(checker.run_iter()) {
+///     eprintln!("unable to write: {}", e);
+/// }
+/// ```
+pub struct NdjsonSink<W: Write> {
+    writer: W,
+    encoding: PathEncoding,
+}
+
+impl<W: Write> NdjsonSink<W> {
+    /// A sink writing paths that are not valid UTF-8 lossily
+    pub fn new(writer: W) -> NdjsonSink<W> {
+        NdjsonSink::with_encoding(writer, PathEncoding::default())
+    }
+
+    /// A sink representing paths that are not valid UTF-8 according to `encoding`
+    pub fn with_encoding(writer: W, encoding: PathEncoding) -> NdjsonSink<W> {
+        NdjsonSink { writer, encoding }
+    }
+
+    /// Writes every set of `groups` as it is produced, such as the sets of `DupeFinder::run_iter`,
+    /// and returns how many were written
+    pub fn write_groups<I: IntoIterator<Item = Duplicate>>(&mut self, groups: I) -> Result<usize, io::Error> {
+        let mut written: usize = 0;
+        for duplicate in groups {
+            self.write_group(&duplicate)?;
+            written += 1;
+        }
+
+        Ok(written)
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> ReportWriter for NdjsonSink<W> {
+    // one line per set, flushed right away so readers of a pipe see it
+    fn write_group(&mut self, duplicate: &Duplicate) -> Result<(), io::Error> {
+        let files: Vec<&Path> = duplicate.paths().collect();
+        writeln!(self.writer, "{{\"hash\": {}, \"size\": {}, {}}}", string(&duplicate.hash), duplicate.size, field("files", path_array(&files, self.encoding)))?;
+        self.writer.flush()
+    }
+
+    fn finish(&mut self, _stats: &ScanStats) -> Result<(), io::Error> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{Confidence, DupeFinder};
+
+    #[test]
+    fn test_write_group() {
+        let duplicate = Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b \"quoted\"\n.txt"]), size: 100, confidence: Confidence::FullHash};
+
+        let mut sink = NdjsonSink::new(Vec::new());
+        sink.write_group(&duplicate).unwrap();
+        sink.write_group(&duplicate).unwrap();
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2, "one line per set expected");
+        let json: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(json["hash"], "12345");
+        assert_eq!(json["size"], 100);
+        assert_eq!(json["files"][1], "b \"quoted\"\n.txt");
+    }
+
+    #[test]
+    fn test_write_groups() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let mut checker = DupeFinder::new_recursive([&path]);
+        let expected = checker.run();
+
+        let mut sink = NdjsonSink::with_encoding(Vec::new(), PathEncoding::Base64);
+        assert_eq!(sink.write_groups(checker.run_iter()).unwrap(), expected.len());
+        let output = sink.into_inner();
+
+        for line in output.split(|byte| *byte == b'\n').filter(|line| !line.is_empty()) {
+            let json: serde_json::Value = serde_json::from_slice(line).unwrap();
+            let duplicate = &expected[json["hash"].as_str().unwrap()];
+            assert_eq!(json["files"].as_array().unwrap().len(), duplicate.entries.len());
+            assert!(json["files_base64"].is_array(), "base64 siblings expected");
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Plain text listing of duplicate sets, as printed by the command line tool
+//!
+//! Every set is written as its `Display` form, a header line followed by one indented line per file,
+//! the listing ends with the `Display` form of the `ScanStats` of the scan:
+//!
+//! ```text
+//! 2 files of size 100 bytes found with hash 1577245F909F3D4619DDA56A7B4BA1AF
+//!   ./a.txt
+//!   ./b.txt
+//! 5 files scanned, 2 hashed, 1 duplicate sets of 200 bytes, 100 bytes reclaimable
+//! ```
+//!
+//! Paths that are not valid UTF-8 are written lossily.
+
+use std::io::{self, Write};
+use crate::{report::ReportWriter, Duplicate, ScanStats};
+
+/// `ReportWriter` of the plain text listing
+pub struct TextWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> TextWriter<W> {
+    /// A writer listing the sets to `writer`
+    pub fn new(writer: W) -> TextWriter<W> {
+        TextWriter { writer }
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> ReportWriter for TextWriter<W> {
+    fn write_group(&mut self, duplicate: &Duplicate) -> Result<(), io::Error> {
+        writeln!(self.writer, "{}", duplicate)
+    }
+
+    fn finish(&mut self, stats: &ScanStats) -> Result<(), io::Error> {
+        writeln!(self.writer, "{}", stats)?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Confidence;
+
+    #[test]
+    fn test_write_group() {
+        let duplicate = Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b.txt"]), size: 100, confidence: Confidence::FullHash};
+
+        let mut report = TextWriter::new(Vec::new());
+        report.write_group(&duplicate).unwrap();
+        report.finish(&ScanStats { files_scanned: 3, files_hashed: 2, duplicate_groups: 1, duplicate_bytes: 200, reclaimable_bytes: 100, truncated: true, ..ScanStats::default() }).unwrap();
+
+        assert_eq!(String::from_utf8(report.into_inner()).unwrap(), "2 files of size 100 bytes found with hash 12345\n  a.txt\n  b.txt\n\
+            3 files scanned, 2 hashed, 1 duplicate sets of 200 bytes, 100 bytes reclaimable, scan limit reached\n");
+    }
+}
@@ -0,0 +1,428 @@
+use std::{collections::{hash_map, HashMap}, ops::Deref, path::{Path, PathBuf}};
+use crate::{Duplicate, ScanError, ScanStats};
+
+/// Duplicate sets found by a run, keyed by their content hash
+///
+/// Dereferences to the underlying `HashMap<String, Duplicate>` and additionally
+/// indexes every file so its sets can be looked up without scanning all results.
+/// With the `serde` feature it can be serialized, the index is rebuilt on deserialization.
+///
+/// # Examples
+/// ```
+/// let mut checker = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+/// let results = checker.run();
+/// if let Some(duplicate) = results.is_duplicate("./resources/dupes/a.txt") {
+///     println!("a.txt shares its contents with {} files", duplicate.entries.len() - 1);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "SerializedResults"))]
+pub struct ScanResults {
+    duplicates: HashMap<String, Duplicate>,
+    // file path -> hashes of the sets it is part of
+    index: HashMap<PathBuf, Vec<String>>,
+    hardlinks: Vec<Vec<PathBuf>>,
+    // hash -> reference files of the set
+    references: HashMap<String, Vec<PathBuf>>,
+    stats: ScanStats,
+    // files that could not be read, not serialized
+    errors: Vec<ScanError>,
+}
+
+impl ScanResults {
+    pub(crate) fn new(duplicates: HashMap<String, Duplicate>) -> ScanResults {
+        let mut index: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        for duplicate in duplicates.values() {
+            for file in duplicate.paths() {
+                index.entry(file.to_path_buf()).or_default().push(duplicate.hash.clone());
+            }
+        }
+
+        ScanResults { duplicates, index, hardlinks: Vec::new(), references: HashMap::new(), stats: ScanStats::default(), errors: Vec::new() }
+    }
+
+    pub(crate) fn with_hardlinks(self, hardlinks: Vec<Vec<PathBuf>>) -> ScanResults {
+        ScanResults { hardlinks, ..self }
+    }
+
+    pub(crate) fn with_references(self, references: HashMap<String, Vec<PathBuf>>) -> ScanResults {
+        ScanResults { references, ..self }
+    }
+
+    pub(crate) fn with_stats(self, stats: ScanStats) -> ScanResults {
+        ScanResults { stats, ..self }
+    }
+
+    pub(crate) fn with_errors(self, errors: Vec<ScanError>) -> ScanResults {
+        ScanResults { errors, ..self }
+    }
+
+    /// Summary of the run that produced these results, such as files hashed and reclaimable space
+    pub fn stats(&self) -> &ScanStats {
+        &self.stats
+    }
+
+    /// Files and directories that could not be read, each with the part of the scan it failed in.
+    /// Snapshots written by `save` do not include them.
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
+    /// Files below the reference directories sharing the contents of the set `hash`,
+    /// empty unless the scan had reference directories
+    pub fn references(&self, hash: &str) -> &[PathBuf] {
+        self.references.get(hash).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Sets of paths that are hard links to the same file, only filled in with `HardlinkPolicy::Separate`
+    pub fn hardlinks(&self) -> &[Vec<PathBuf>] {
+        &self.hardlinks
+    }
+
+    /// All duplicate sets `path` is part of, paths are matched exactly as they are listed in `Duplicate::entries`
+    pub fn groups_containing<P: AsRef<Path>>(&self, path: P) -> Vec<&Duplicate> {
+        match self.index.get(path.as_ref()) {
+            Some(hashes) => hashes.iter().filter_map(|hash| self.duplicates.get(hash)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The duplicate set `path` is part of, if any
+    pub fn is_duplicate<P: AsRef<Path>>(&self, path: P) -> Option<&Duplicate> {
+        self.groups_containing(path).into_iter().next()
+    }
+
+    /// All duplicate sets with at least one file below the directory `path`
+    pub fn groups_under<P: AsRef<Path>>(&self, path: P) -> Vec<&Duplicate> {
+        let path = path.as_ref();
+        self.duplicates.values()
+            .filter(|duplicate| duplicate.paths().any(|file| file.starts_with(path)))
+            .collect()
+    }
+
+    /// All duplicate sets with a file below the directory `a` and another file below the directory `b`,
+    /// such as the files an external drive shares with a laptop
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["/media/external", "/home/user"]);
+    /// let results = checker.run();
+    /// for duplicate in results.duplicates_between("/media/external", "/home/user") {
+    ///     println!("{:?}", duplicate.paths().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn duplicates_between<A: AsRef<Path>, B: AsRef<Path>>(&self, a: A, b: B) -> Vec<&Duplicate> {
+        let (a, b) = (a.as_ref(), b.as_ref());
+        self.duplicates.values()
+            .filter(|duplicate| duplicate.paths().any(|first| first.starts_with(a) && duplicate.paths().any(|second| second != first && second.starts_with(b))))
+            .collect()
+    }
+
+    /// The duplicate sets ordered by the bytes their extra copies take up, most first,
+    /// ties ordered by hash
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+    /// let results = checker.run();
+    /// for duplicate in results.iter_by_wasted_space().take(10) {
+    ///     println!("{} copies of {} bytes", duplicate.entries.len(), duplicate.size);
+    /// }
+    /// ```
+    pub fn iter_by_wasted_space(&self) -> impl Iterator<Item = &Duplicate> {
+        let mut sets: Vec<&Duplicate> = self.duplicates.values().collect();
+        sets.sort_by(|a, b| self.wasted_space(b).cmp(&self.wasted_space(a)).then_with(|| a.hash.cmp(&b.hash)));
+        sets.into_iter()
+    }
+
+    /// Bytes freed by keeping a single file of every set, with reference directories every
+    /// listed file can go. Adds up file sizes, unlike `ScanStats::reclaimable_bytes` hard links
+    /// and shared blocks are not taken into account.
+    pub fn total_reclaimable(&self) -> u64 {
+        self.duplicates.values().map(|duplicate| self.wasted_space(duplicate)).sum()
+    }
+
+    // bytes taken up by the files of a set beyond the one kept, the references are kept instead
+    fn wasted_space(&self, duplicate: &Duplicate) -> u64 {
+        let kept: u64 = if self.references(&duplicate.hash).is_empty() { 1 } else { 0 };
+        duplicate.size * (duplicate.entries.len() as u64).saturating_sub(kept)
+    }
+
+    /// Combines the results of separate runs, such as the shards of a search (`DupeFinderBuilder::shard`)
+    ///
+    /// Sets with the same hash are joined, stats are added up.
+    pub fn merge(self, other: ScanResults) -> ScanResults {
+        let mut duplicates = self.duplicates;
+        for (hash, duplicate) in other.duplicates {
+            match duplicates.entry(hash) {
+                hash_map::Entry::Occupied(mut entry) => {
+                    let existing = entry.get_mut();
+                    existing.confidence = existing.confidence.min(duplicate.confidence);
+                    for file in duplicate.entries {
+                        if !existing.contains(&file.path) {
+                            existing.entries.push(file);
+                        }
+                    }
+                },
+                hash_map::Entry::Vacant(entry) => {
+                    entry.insert(duplicate);
+                },
+            }
+        }
+
+        let mut hardlinks = self.hardlinks;
+        hardlinks.extend(other.hardlinks);
+        let mut references = self.references;
+        for (hash, files) in other.references {
+            references.entry(hash).or_default().extend(files);
+        }
+        let mut stats = self.stats;
+        stats.merge(&other.stats);
+        let mut errors = self.errors;
+        errors.extend(other.errors);
+
+        ScanResults::new(duplicates).with_hardlinks(hardlinks).with_references(references).with_stats(stats).with_errors(errors)
+    }
+
+    /// Gives up the path index and returns the underlying map
+    pub fn into_inner(self) -> HashMap<String, Duplicate> {
+        self.duplicates
+    }
+
+    /// Writes the results as JSON to `path` so they can be reviewed later without scanning again.
+    /// Paths are written with `PathEncoding::Percent`, so paths that are not valid UTF-8 are kept.
+    ///
+    /// # Examples
+    /// ```
+    /// # let temp = tempfile::tempdir().unwrap();
+    /// # let snapshot = temp.path().join("scan.json");
+    /// let mut checker = dupefinder::DupeFinder::new(vec!["./resources/dupes"]);
+    /// checker.run().save(&snapshot).unwrap();
+    ///
+    /// let results = dupefinder::ScanResults::load(&snapshot).unwrap();
+    /// println!("{} duplicate sets", results.len());
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), std::io::Error> {
+        use std::io::Write;
+
+        let encoded = self.map_paths(&|path| Ok(PathBuf::from(crate::report::PathEncoding::Percent.encode(path).0)))?;
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(&mut writer, &Snapshot { percent_paths: true, results: &encoded })?;
+        writer.flush()
+    }
+
+    /// Reads results written by `save`, the path index is rebuilt
+    #[cfg(feature = "serde")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ScanResults, std::io::Error> {
+        ScanResults::read_from(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    /// Reads results written by `save` from `reader`, such as a snapshot sent over the network
+    #[cfg(feature = "serde")]
+    pub fn read_from<R: std::io::Read>(reader: R) -> Result<ScanResults, std::io::Error> {
+        let snapshot: Snapshot<ScanResults> = serde_json::from_reader(reader)?;
+        match snapshot.percent_paths {
+            true => snapshot.results.map_paths(&|path| {
+                path.to_str().and_then(crate::report::percent_decode)
+                    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("malformed path in snapshot: {}", path.display())))
+            }),
+            false => Ok(snapshot.results),
+        }
+    }
+
+    // the same results with every path replaced by `map`
+    #[cfg(feature = "serde")]
+    fn map_paths(&self, map: &dyn Fn(&Path) -> Result<PathBuf, std::io::Error>) -> Result<ScanResults, std::io::Error> {
+        let map_all = |paths: &[PathBuf]| paths.iter().map(|path| map(path)).collect::<Result<Vec<PathBuf>, std::io::Error>>();
+
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        for (hash, duplicate) in self.duplicates.iter() {
+            let entries = duplicate.entries.iter()
+                .map(|entry| Ok(crate::DupeEntry { path: map(&entry.path)?, ..entry.clone() }))
+                .collect::<Result<Vec<crate::DupeEntry>, std::io::Error>>()?;
+            duplicates.insert(hash.clone(), Duplicate { entries, ..duplicate.clone() });
+        }
+        let hardlinks = self.hardlinks.iter().map(|links| map_all(links)).collect::<Result<Vec<Vec<PathBuf>>, std::io::Error>>()?;
+        let references = self.references.iter()
+            .map(|(hash, files)| Ok((hash.clone(), map_all(files)?)))
+            .collect::<Result<HashMap<String, Vec<PathBuf>>, std::io::Error>>()?;
+
+        Ok(ScanResults::new(duplicates).with_hardlinks(hardlinks).with_references(references).with_stats(self.stats.clone()))
+    }
+}
+
+// file written by `ScanResults::save`
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Snapshot<R> {
+    // snapshots of earlier versions wrote paths as they are
+    #[serde(default)]
+    percent_paths: bool,
+    #[serde(flatten)]
+    results: R,
+}
+
+// serialized form of `ScanResults`, the path index is derived data
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedResults<D = HashMap<String, Duplicate>, H = Vec<Vec<PathBuf>>, R = HashMap<String, Vec<PathBuf>>, S = ScanStats> {
+    duplicates: D,
+    #[serde(default)]
+    hardlinks: H,
+    #[serde(default)]
+    references: R,
+    #[serde(default)]
+    stats: S,
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedResults> for ScanResults {
+    fn from(results: SerializedResults) -> ScanResults {
+        ScanResults::new(results.duplicates).with_hardlinks(results.hardlinks).with_references(results.references).with_stats(results.stats)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ScanResults {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedResults { duplicates: &self.duplicates, hardlinks: &self.hardlinks, references: &self.references, stats: &self.stats }.serialize(serializer)
+    }
+}
+
+impl Deref for ScanResults {
+    type Target = HashMap<String, Duplicate>;
+
+    fn deref(&self) -> &HashMap<String, Duplicate> {
+        &self.duplicates
+    }
+}
+
+impl IntoIterator for ScanResults {
+    type Item = (String, Duplicate);
+    type IntoIter = hash_map::IntoIter<String, Duplicate>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.duplicates.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ScanResults {
+    type Item = (&'a String, &'a Duplicate);
+    type IntoIter = hash_map::Iter<'a, String, Duplicate>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.duplicates.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Confidence;
+
+    #[test]
+    fn test_groups_containing() {
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        duplicates.insert(String::from("12345"), Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b.txt"]), size: 100, confidence: Confidence::FullHash});
+        duplicates.insert(String::from("67890"), Duplicate{hash: String::from("67890"), entries: crate::duplicate::entries(&["c.txt", "d.txt"]), size: 200, confidence: Confidence::FullHash});
+        let results = ScanResults::new(duplicates);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.groups_containing("b.txt").len(), 1);
+        assert_eq!(results.is_duplicate("c.txt").unwrap().hash, String::from("67890"));
+        assert!(results.is_duplicate("e.txt").is_none(), "unknown file should not match");
+        assert!(results.groups_containing(Path::new("e.txt")).is_empty(), "unknown file should not match");
+    }
+
+    #[test]
+    fn test_query_helpers() {
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        duplicates.insert(String::from("12345"), Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["/photos/a.jpg", "/photos/2020/b.jpg", "/backup/c.jpg"]), size: 100, confidence: Confidence::FullHash});
+        duplicates.insert(String::from("67890"), Duplicate{hash: String::from("67890"), entries: crate::duplicate::entries(&["/docs/d.txt", "/backup/e.txt"]), size: 150, confidence: Confidence::FullHash});
+        duplicates.insert(String::from("abcde"), Duplicate{hash: String::from("abcde"), entries: crate::duplicate::entries(&["/docs/f.txt", "/docs/g.txt"]), size: 50, confidence: Confidence::FullHash});
+        let results = ScanResults::new(duplicates);
+
+        let order: Vec<&str> = results.iter_by_wasted_space().map(|duplicate| duplicate.hash.as_str()).collect();
+        assert_eq!(order, vec!["12345", "67890", "abcde"]);
+        assert_eq!(results.total_reclaimable(), 200 + 150 + 50);
+
+        let mut under: Vec<&str> = results.groups_under("/docs").iter().map(|duplicate| duplicate.hash.as_str()).collect();
+        under.sort();
+        assert_eq!(under, vec!["67890", "abcde"]);
+        assert_eq!(results.groups_under("/photos/2020").len(), 1);
+        assert!(results.groups_under("/doc").is_empty(), "paths should match by component");
+
+        let between: Vec<&str> = results.duplicates_between("/backup", "/docs").iter().map(|duplicate| duplicate.hash.as_str()).collect();
+        assert_eq!(between, vec!["67890"]);
+        assert_eq!(results.duplicates_between("/photos/2020", "/photos").len(), 1, "nested directories should match");
+        assert!(results.duplicates_between("/photos", "/docs").is_empty(), "no set spans both directories");
+        assert!(results.duplicates_between("/photos/2020", "/photos/2020").is_empty(), "a file should not pair with itself");
+
+        let mut references: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        references.insert(String::from("abcde"), vec![PathBuf::from("/archive/f.txt")]);
+        let results = results.with_references(references);
+        assert_eq!(results.total_reclaimable(), 200 + 150 + 100, "with references every listed file can go");
+        assert_eq!(results.iter_by_wasted_space().last().unwrap().hash, "abcde");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        duplicates.insert(String::from("12345"), Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b.txt"]), size: 100, confidence: Confidence::FullHash});
+        let results = ScanResults::new(duplicates).with_hardlinks(vec![vec![PathBuf::from("c.txt"), PathBuf::from("d.txt")]]);
+
+        let json = serde_json::to_string(&results).unwrap();
+        let restored: ScanResults = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored["12345"].entries, results["12345"].entries);
+        assert_eq!(restored.is_duplicate("b.txt").unwrap().size, 100);
+        assert_eq!(restored.hardlinks(), results.hardlinks());
+
+        let minimal: ScanResults = serde_json::from_str(r#"{"duplicates":{}}"#).unwrap();
+        assert!(minimal.is_empty(), "missing optional fields should default");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_save_load() {
+        let temp = tempfile::tempdir().unwrap();
+        let snapshot = temp.path().join("scan.json");
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        duplicates.insert(String::from("12345"), Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&["a.txt", "b.txt"]), size: 100, confidence: Confidence::ByteVerified});
+        let stats = ScanStats { files_scanned: 2, ..ScanStats::default() };
+        let results = ScanResults::new(duplicates).with_stats(stats.clone());
+
+        results.save(&snapshot).unwrap();
+        let loaded = ScanResults::load(&snapshot).unwrap();
+        assert_eq!(loaded.is_duplicate("a.txt").unwrap().confidence, Confidence::ByteVerified);
+        assert_eq!(loaded.stats(), &stats);
+
+        assert!(ScanResults::load(temp.path().join("missing.json")).is_err(), "missing snapshot should fail");
+        std::fs::write(&snapshot, r#"{"duplicates": {"12345": {"hash": "12345", "entries": [{"path": "100%.txt", "size": 0, "modified": null, "file_id": null}], "size": 100, "confidence": "FullHash"}}}"#).unwrap();
+        assert!(ScanResults::load(&snapshot).unwrap().is_duplicate("100%.txt").is_some(), "earlier snapshots should load as they are");
+        std::fs::write(&snapshot, "not json").unwrap();
+        assert!(ScanResults::load(&snapshot).is_err_and(|e| e.kind() == std::io::ErrorKind::InvalidData), "invalid snapshot should fail");
+    }
+
+    #[cfg(all(feature = "serde", unix))]
+    #[test]
+    fn test_save_invalid_paths() {
+        use std::os::unix::ffi::OsStrExt;
+        let invalid = PathBuf::from(std::ffi::OsStr::from_bytes(b"a\xff.txt"));
+        let temp = tempfile::tempdir().unwrap();
+        let snapshot = temp.path().join("scan.json");
+
+        let mut duplicates: HashMap<String, Duplicate> = HashMap::new();
+        duplicates.insert(String::from("12345"), Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&[invalid.clone(), PathBuf::from("100%.txt")]), size: 100, confidence: Confidence::FullHash});
+        let results = ScanResults::new(duplicates).with_hardlinks(vec![vec![invalid.clone(), PathBuf::from("b.txt")]]);
+
+        results.save(&snapshot).unwrap();
+        let loaded = ScanResults::load(&snapshot).unwrap();
+        assert_eq!(loaded.is_duplicate(&invalid).unwrap().entries.len(), 2);
+        assert!(loaded.is_duplicate("100%.txt").is_some(), "percent signs should be kept");
+        assert_eq!(loaded.hardlinks()[0][0], invalid);
+    }
+}
@@ -0,0 +1,178 @@
+use std::{collections::HashSet, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+use crate::Duplicate;
+
+/// Reason a duplicate set is considered risky to clean up automatically
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RiskFactor {
+    /// A file lives under an operating system or application path
+    SystemPath,
+    /// A file was modified recently or its modification time could not be read
+    RecentlyModified,
+    /// The files do not all share the same extension
+    DifferingExtensions,
+    /// The set holds an unusually large number of copies
+    LargeGroup,
+}
+
+impl RiskFactor {
+    /// Contribution of the factor to a `RiskScore`
+    pub fn weight(&self) -> u32 {
+        match self {
+            RiskFactor::SystemPath => 40,
+            RiskFactor::RecentlyModified => 25,
+            RiskFactor::DifferingExtensions => 20,
+            RiskFactor::LargeGroup => 15,
+        }
+    }
+}
+
+/// Heuristic risk of removing files from a duplicate set, from 0 (no concerns) to 100
+#[derive(Clone, Debug, PartialEq)]
+pub struct RiskScore {
+    /// Sum of the weights of all factors found
+    pub score: u32,
+    /// Factors that contributed to the score
+    pub factors: Vec<RiskFactor>,
+    /// Files named like a copy of another, the safest to remove, see `Duplicate::likely_copies`
+    pub likely_copies: Vec<PathBuf>,
+}
+
+/// Thresholds used when scoring a duplicate set
+#[derive(Clone, Debug)]
+pub struct RiskOptions {
+    /// Files modified within this duration are considered recently modified
+    pub recent: Duration,
+    /// Sets with at least this many files are considered large
+    pub large_group: usize,
+    /// Paths considered system locations
+    pub system_paths: Vec<PathBuf>,
+}
+
+impl Default for RiskOptions {
+    fn default() -> RiskOptions {
+        RiskOptions {
+            recent: Duration::from_secs(7 * 24 * 60 * 60),
+            large_group: 10,
+            system_paths: default_system_paths(),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn default_system_paths() -> Vec<PathBuf> {
+    ["C:\\Windows", "C:\\Program Files", "C:\\Program Files (x86)", "C:\\ProgramData"]
+        .iter().map(PathBuf::from).collect()
+}
+
+#[cfg(not(windows))]
+fn default_system_paths() -> Vec<PathBuf> {
+    ["/bin", "/boot", "/etc", "/lib", "/lib64", "/opt", "/sbin", "/sys", "/usr", "/var", "/System", "/Library"]
+        .iter().map(PathBuf::from).collect()
+}
+
+impl Duplicate {
+    /// Scores how risky it is to remove files from this set without human review
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new(vec!["./resources"]);
+    /// let options = dupefinder::RiskOptions::default();
+    /// for duplicate in checker.run().values() {
+    ///     if duplicate.risk(&options).score < 20 {
+    ///         println!("safe to clean up: {}", duplicate.hash);
+    ///     }
+    /// }
+    /// ```
+    pub fn risk(&self, options: &RiskOptions) -> RiskScore {
+        let mut factors: Vec<RiskFactor> = Vec::new();
+        let paths: Vec<&Path> = self.paths().collect();
+
+        if paths.iter().any(|path| options.system_paths.iter().any(|system| path.starts_with(system))) {
+            factors.push(RiskFactor::SystemPath);
+        }
+
+        if self.entries.iter().any(|entry| is_recent(entry.modified, options.recent)) {
+            factors.push(RiskFactor::RecentlyModified);
+        }
+
+        let extensions: HashSet<Option<String>> = paths.iter()
+            .map(|path| path.extension().map(|extension| extension.to_string_lossy().to_lowercase()))
+            .collect();
+        if extensions.len() > 1 {
+            factors.push(RiskFactor::DifferingExtensions);
+        }
+
+        if self.entries.len() >= options.large_group {
+            factors.push(RiskFactor::LargeGroup);
+        }
+
+        RiskScore {
+            score: factors.iter().map(RiskFactor::weight).sum::<u32>().min(100),
+            factors,
+            likely_copies: self.likely_copies(),
+        }
+    }
+}
+
+// files whose modification time can not be determined are treated as recent
+fn is_recent(modified: Option<SystemTime>, recent: Duration) -> bool {
+    let modified = match modified {
+        Some(modified) => modified,
+        None => return true,
+    };
+
+    match SystemTime::now().duration_since(modified) {
+        Ok(age) => age < recent,
+        // modified in the future
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{Confidence, DupeEntry};
+
+    fn entry(path: &Path) -> DupeEntry {
+        DupeEntry::new(path.to_path_buf(), &std::fs::metadata(path).unwrap())
+    }
+
+    fn relaxed_options() -> RiskOptions {
+        RiskOptions { recent: Duration::ZERO, large_group: 10, system_paths: Vec::new() }
+    }
+
+    #[test]
+    fn test_risk_low() {
+        let path_a: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let path_b: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "b.txt"].iter().collect();
+        let duplicate = Duplicate{hash: String::from("12345"), entries: vec![entry(&path_a), entry(&path_b)], size: 100, confidence: Confidence::FullHash};
+
+        let risk = duplicate.risk(&relaxed_options());
+        assert_eq!(risk.score, 0);
+        assert!(risk.factors.is_empty(), "no risk factors expected");
+        assert!(risk.likely_copies.is_empty(), "no copies expected");
+    }
+
+    #[test]
+    fn test_risk_factors() {
+        let resources: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources"].iter().collect();
+        let path_a: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let path_b: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "images", "pixels.png"].iter().collect();
+        let duplicate = Duplicate{hash: String::from("12345"), entries: vec![entry(&path_a), entry(&path_b)], size: 100, confidence: Confidence::FullHash};
+
+        let options = RiskOptions { recent: Duration::ZERO, large_group: 2, system_paths: vec![resources] };
+        let risk = duplicate.risk(&options);
+        assert_eq!(risk.factors, vec![RiskFactor::SystemPath, RiskFactor::DifferingExtensions, RiskFactor::LargeGroup]);
+        assert_eq!(risk.score, 75);
+    }
+
+    #[test]
+    fn test_risk_unreadable_is_recent() {
+        let missing: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "doesnotexist.txt"].iter().collect();
+        let duplicate = Duplicate{hash: String::from("12345"), entries: crate::duplicate::entries(&[&missing]), size: 100, confidence: Confidence::FullHash};
+
+        let risk = duplicate.risk(&relaxed_options());
+        assert_eq!(risk.factors, vec![RiskFactor::RecentlyModified]);
+    }
+}
@@ -0,0 +1,94 @@
+use std::{collections::HashSet, path::{Path, PathBuf}};
+use crate::DupeFinder;
+
+/// Files found below one of the searched directories and how many of them have a copy
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RootStats {
+    /// Directory as it was provided to the search
+    pub path: PathBuf,
+    /// Number of files below the directory
+    pub files: usize,
+    /// Files with a copy anywhere in the scan, in this directory or another one
+    pub duplicate_files: usize,
+    /// Bytes taken up by `duplicate_files`
+    pub duplicate_bytes: u64,
+}
+
+impl DupeFinder {
+    /// Runs the search and returns the totals of every provided directory, the directory holding
+    /// the most duplicated bytes first, ties sorted by path
+    ///
+    /// A file counts towards the deepest provided directory it is found under, files given
+    /// with `DupeFinderBuilder::files` outside of all directories are left out.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources/dupes", "./resources/base"]);
+    /// for root in checker.run_root_stats() {
+    ///     println!("{}: {} of {} files duplicated, {} bytes", root.path.display(), root.duplicate_files, root.files, root.duplicate_bytes);
+    /// }
+    /// ```
+    pub fn run_root_stats(&mut self) -> Vec<RootStats> {
+        let results = self.run();
+        let duplicated: HashSet<&Path> = results.values()
+            .flat_map(|duplicate| duplicate.paths().chain(results.references(&duplicate.hash).iter().map(PathBuf::as_path)))
+            .collect();
+
+        let mut roots: Vec<RootStats> = Vec::new();
+        for directory in self.directories.iter() {
+            if !roots.iter().any(|root| root.path == *directory) {
+                roots.push(RootStats { path: directory.clone(), ..RootStats::default() });
+            }
+        }
+        for data in self.file_sizes.values().flatten() {
+            let root = roots.iter_mut()
+                .filter(|root| data.path.starts_with(&root.path))
+                .max_by_key(|root| root.path.components().count());
+
+            if let Some(root) = root {
+                root.files += 1;
+                if duplicated.contains(data.path.as_path()) {
+                    root.duplicate_files += 1;
+                    root.duplicate_bytes += data.size;
+                }
+            }
+        }
+
+        roots.sort_by(|a, b| b.duplicate_bytes.cmp(&a.duplicate_bytes).then_with(|| a.path.cmp(&b.path)));
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_run_root_stats() {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let other: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "folders"].iter().collect();
+        let nested = path.join("dir_a");
+        let mut checker = DupeFinder::new_recursive([&other, &path, &nested, &other]);
+
+        let roots = checker.run_root_stats();
+        assert_eq!(roots, vec![
+            RootStats { path: path.clone(), files: 1, duplicate_files: 1, duplicate_bytes: 100 },
+            RootStats { path: nested, files: 1, duplicate_files: 1, duplicate_bytes: 100 },
+            RootStats { path: other, files: 1, duplicate_files: 0, duplicate_bytes: 0 },
+        ]);
+    }
+
+    #[test]
+    fn test_run_root_stats_references() {
+        let reference: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "base"].iter().collect();
+        let target: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes"].iter().collect();
+        let mut checker = DupeFinder::with_reference([&reference], [&target]);
+
+        let roots = checker.run_root_stats();
+        assert_eq!(roots[0].path, target);
+        assert_eq!((roots[0].duplicate_files, roots[0].duplicate_bytes), (2, 200));
+        assert_eq!(roots[1].path, reference);
+        assert_eq!(roots[1].duplicate_files, 1, "reference files with a copy should count");
+    }
+}
@@ -0,0 +1,37 @@
+use xxhash_rust::xxh3::xxh3_64;
+
+// One of `count` deterministic slices of a search. Files are assigned by their candidate key
+// rather than their path, so every set of possible duplicates lands in a single shard and the
+// results of all shards can simply be merged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    pub fn is_valid(&self) -> bool {
+        self.index < self.count
+    }
+
+    // the assignment is stable across hosts and runs, it only depends on the key
+    pub fn owns(&self, key: u64) -> bool {
+        self.count <= 1 || xxh3_64(&key.to_le_bytes()) % self.count as u64 == self.index as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_every_key_has_one_owner() {
+        let shards: Vec<Shard> = (0..3).map(|index| Shard { index, count: 3 }).collect();
+
+        for key in 0..100 {
+            assert_eq!(shards.iter().filter(|shard| shard.owns(key)).count(), 1, "key {} should have exactly one owner", key);
+        }
+        assert!(!Shard { index: 3, count: 3 }.is_valid());
+    }
+}
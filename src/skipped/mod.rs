@@ -0,0 +1,95 @@
+use std::{fs::Metadata, path::{Path, PathBuf}, sync::Mutex};
+
+/// Reason a file was left out of a scan
+#[derive(Clone, Debug, PartialEq)]
+pub enum SkipReason {
+    /// A stat, open or read of the file exceeded the hard timeout
+    TimedOut,
+    /// The file lives on a device that exceeded the read error limit
+    DeviceQuarantined,
+    /// The entry is a FIFO, socket or device file, which are never opened
+    SpecialFile,
+    /// The path is longer than the configured limit
+    PathTooLong,
+    /// The directory is nested deeper than the configured limit
+    TooDeep,
+    /// The directory holds more entries than the configured limit, entries up to the limit were still searched
+    TooManyEntries,
+    /// The directory link leads back to a directory holding it, following it would never end
+    SymlinkLoop,
+}
+
+/// How FIFOs, sockets and device files found while traversing are handled
+///
+/// Special files are never opened for hashing, reading a FIFO could block forever.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SpecialFilePolicy {
+    /// Leave them out without a trace
+    Ignore,
+    /// Leave them out and list them in `DupeFinder::skipped_files`
+    #[default]
+    Skip,
+    /// Like `Skip`, additionally logging a traversal warning for each one
+    Report,
+}
+
+/// A file left out of a scan, see `DupeFinder::skipped_files`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
+
+// Files skipped during the current run, shared with hashing threads
+#[derive(Default)]
+pub struct SkipList {
+    files: Mutex<Vec<SkippedFile>>,
+}
+
+impl SkipList {
+    pub fn push(&self, path: &Path, reason: SkipReason) {
+        self.lock().push(SkippedFile { path: path.to_path_buf(), reason });
+    }
+
+    pub fn files(&self) -> Vec<SkippedFile> {
+        self.lock().clone()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<SkippedFile>> {
+        self.files.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// anything that is neither a regular file, a directory nor a symlink
+pub fn is_special_file(meta: &Metadata) -> bool {
+    let file_type = meta.file_type();
+    !file_type.is_file() && !file_type.is_dir() && !file_type.is_symlink()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_skip_list() {
+        let list = SkipList::default();
+        list.push(Path::new("a.txt"), SkipReason::TimedOut);
+
+        assert_eq!(list.files(), vec![SkippedFile { path: PathBuf::from("a.txt"), reason: SkipReason::TimedOut }]);
+
+        list.clear();
+        assert!(list.files().is_empty(), "list should be cleared");
+    }
+
+    #[test]
+    fn test_is_special_file() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        assert!(!is_special_file(&std::fs::metadata(&path).unwrap()));
+        assert!(!is_special_file(&std::fs::metadata(path.parent().unwrap()).unwrap()));
+    }
+}
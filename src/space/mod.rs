@@ -0,0 +1,130 @@
+use std::{collections::HashMap, fs::{self, Metadata}, path::Path};
+
+// Forecasts the disk space actually freed by removing files one after another.
+// Space is counted in allocated blocks rather than file sizes, a hard linked file only
+// frees its blocks once its last link is removed and files sharing extents with a
+// reflinked clone free nothing.
+#[derive(Default)]
+pub struct SpaceForecast {
+    // (device, inode) -> links removed so far
+    removed_links: HashMap<(u64, u64), u64>,
+}
+
+impl SpaceForecast {
+    // bytes freed by removing `path` after every file passed before, `size` is assumed when the file can not be inspected
+    pub fn remove(&mut self, path: &Path, size: u64) -> u64 {
+        match fs::symlink_metadata(path) {
+            Ok(meta) => self.freed(path, &meta),
+            Err(_) => size,
+        }
+    }
+
+    #[cfg(unix)]
+    fn freed(&mut self, path: &Path, meta: &Metadata) -> u64 {
+        use std::os::unix::fs::MetadataExt;
+
+        let removed = self.removed_links.entry((meta.dev(), meta.ino())).or_default();
+        *removed += 1;
+        if *removed < meta.nlink() || has_shared_extents(path) {
+            return 0;
+        }
+
+        // st_blocks is always counted in 512 byte units
+        meta.blocks() * 512
+    }
+
+    #[cfg(not(unix))]
+    fn freed(&mut self, _path: &Path, meta: &Metadata) -> u64 {
+        meta.len()
+    }
+}
+
+#[cfg(all(feature = "reflink", target_os = "linux"))]
+fn has_shared_extents(path: &Path) -> bool {
+    use std::{fs::File, os::fd::AsRawFd};
+
+    // linux/fiemap.h
+    const FS_IOC_FIEMAP: u32 = 0xC020660B;
+    const FIEMAP_EXTENT_LAST: u32 = 0x1;
+    const FIEMAP_EXTENT_SHARED: u32 = 0x2000;
+    const EXTENTS: usize = 32;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct FiemapExtent {
+        logical: u64,
+        physical: u64,
+        length: u64,
+        reserved64: [u64; 2],
+        flags: u32,
+        reserved: [u32; 3],
+    }
+
+    #[repr(C)]
+    struct Fiemap {
+        start: u64,
+        length: u64,
+        flags: u32,
+        mapped_extents: u32,
+        extent_count: u32,
+        reserved: u32,
+        extents: [FiemapExtent; EXTENTS],
+    }
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+
+    let mut start = 0;
+    loop {
+        let mut map = Fiemap { start, length: u64::MAX, flags: 0, mapped_extents: 0, extent_count: EXTENTS as u32, reserved: 0, extents: [FiemapExtent::default(); EXTENTS] };
+        // SAFETY: `map` is laid out as struct fiemap with room for `extent_count` extents
+        if unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP as libc::Ioctl, &mut map) } == -1 {
+            return false;
+        }
+
+        let extents = &map.extents[..(map.mapped_extents as usize).min(EXTENTS)];
+        if extents.iter().any(|extent| extent.flags & FIEMAP_EXTENT_SHARED != 0) {
+            return true;
+        }
+
+        match extents.last() {
+            Some(last) if last.flags & FIEMAP_EXTENT_LAST == 0 => start = last.logical + last.length,
+            _ => return false,
+        }
+    }
+}
+
+// shared extents can only be found through FIEMAP on Linux
+#[cfg(all(unix, not(all(feature = "reflink", target_os = "linux"))))]
+fn has_shared_extents(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_missing_file_assumes_size() {
+        let mut forecast = SpaceForecast::default();
+        assert_eq!(forecast.remove(Path::new("/missing/a.txt"), 100), 100);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hard_links_free_with_last_link() {
+        use std::os::unix::fs::MetadataExt;
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("a.txt");
+        let link = temp.path().join("b.txt");
+        fs::write(&file, "12345").unwrap();
+        fs::hard_link(&file, &link).unwrap();
+
+        let mut forecast = SpaceForecast::default();
+        assert_eq!(forecast.remove(&file, 5), 0, "the other link keeps the blocks");
+        assert_eq!(forecast.remove(&link, 5), fs::metadata(&file).unwrap().blocks() * 512);
+    }
+}
@@ -0,0 +1,108 @@
+use std::{fmt, sync::atomic::{AtomicU64, AtomicUsize, Ordering}};
+
+/// Summary of a run, see `ScanResults::stats`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScanStats {
+    /// Files found while traversing that were considered for matching
+    pub files_scanned: usize,
+    /// Files whose match key was produced, usually by hashing all of their contents
+    pub files_hashed: usize,
+    /// Bytes read to hash files, including partial hashes
+    pub bytes_read: u64,
+    /// Number of duplicate sets found
+    pub duplicate_groups: usize,
+    /// Bytes taken up by all files of all duplicate sets
+    pub duplicate_bytes: u64,
+    /// Disk space freed by removing every reported duplicate but the first file of each set,
+    /// every reported file when the scan had reference directories. Accounts for allocated
+    /// blocks and hard links like `DedupePlan::reclaimable_bytes`.
+    pub reclaimable_bytes: u64,
+    /// The run reached `DupeFinderBuilder::max_files`, `max_bytes` or `max_groups`, files beyond them were left out
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub truncated: bool,
+}
+
+impl ScanStats {
+    // totals of two runs over disjoint files, such as two shards
+    pub(crate) fn merge(&mut self, other: &ScanStats) {
+        self.files_scanned += other.files_scanned;
+        self.files_hashed += other.files_hashed;
+        self.bytes_read += other.bytes_read;
+        self.duplicate_groups += other.duplicate_groups;
+        self.duplicate_bytes += other.duplicate_bytes;
+        self.reclaimable_bytes += other.reclaimable_bytes;
+        self.truncated |= other.truncated;
+    }
+}
+
+impl fmt::Display for ScanStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} files scanned, {} hashed, {} duplicate sets of {} bytes, {} bytes reclaimable",
+            self.files_scanned, self.files_hashed, self.duplicate_groups, self.duplicate_bytes, self.reclaimable_bytes)?;
+        if self.truncated {
+            write!(f, ", scan limit reached")?;
+        }
+
+        Ok(())
+    }
+}
+
+// Counts traversal and hashing work of the current run, shared with hashing threads
+#[derive(Default)]
+pub struct ScanCounters {
+    files_scanned: AtomicUsize,
+    files_hashed: AtomicUsize,
+    bytes_read: AtomicU64,
+}
+
+impl ScanCounters {
+    pub fn scanned(&self) {
+        self.files_scanned.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn hashed(&self, bytes: u64) {
+        self.files_hashed.fetch_add(1, Ordering::Relaxed);
+        self.read(bytes);
+    }
+
+    pub fn read(&self, bytes: u64) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.files_scanned.store(0, Ordering::Relaxed);
+        self.files_hashed.store(0, Ordering::Relaxed);
+        self.bytes_read.store(0, Ordering::Relaxed);
+    }
+
+    // the counted work, set totals are left to the caller
+    pub fn stats(&self) -> ScanStats {
+        ScanStats {
+            files_scanned: self.files_scanned.load(Ordering::Relaxed),
+            files_hashed: self.files_hashed.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            ..ScanStats::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let counters = ScanCounters::default();
+        counters.scanned();
+        counters.scanned();
+        counters.hashed(100);
+        counters.read(10);
+
+        assert_eq!(counters.stats(), ScanStats { files_scanned: 2, files_hashed: 1, bytes_read: 110, ..ScanStats::default() });
+
+        counters.clear();
+        assert_eq!(counters.stats(), ScanStats::default());
+    }
+}
@@ -0,0 +1,124 @@
+use std::{fs::{File, Metadata}, io::{self, Read}, path::Path, sync::Arc};
+use crate::{Confidence, FileHasher};
+
+/// Decides which files are duplicates of each other, configured via `DupeFinderBuilder::strategy`
+///
+/// Matching runs in two steps: every traversed file gets a cheap candidate key, then only files
+/// sharing a candidate key with another file get a match key, which is usually costly to produce.
+/// Files with equal match keys are reported as duplicates. The default `ContentStrategy` uses the
+/// file size and the configured `FileHasher`.
+///
+/// Size manifests used for remote comparison carry candidate keys, so both sides need to use
+/// the same strategy.
+///
+/// # Examples
+/// ```
+/// use std::{fs::{File, Metadata}, io::{self, Read}, path::Path};
+/// use dupefinder::{FileHasher, MatchStrategy};
+///
+/// // text files that only differ in letter case
+/// struct CaseInsensitiveText;
+///
+/// impl MatchStrategy for CaseInsensitiveText {
+///     fn candidate_key(&self, path: &Path, meta: &Metadata) -> Option<u64> {
+///         match path.extension() {
+///             Some(extension) if extension == "txt" => Some(meta.len()),
+///             _ => None,
+///         }
+///     }
+///
+///     fn match_key(&self, path: &Path, hasher: &dyn FileHasher) -> Result<String, io::Error> {
+///         let contents = std::fs::read_to_string(path)?.to_lowercase();
+///         hasher.hash_reader(&mut contents.as_bytes())
+///     }
+/// }
+///
+/// let checker = dupefinder::DupeFinder::builder(vec!["./resources"])
+///     .strategy(CaseInsensitiveText)
+///     .build();
+/// ```
+pub trait MatchStrategy: Send + Sync {
+    /// Groups candidates, only files sharing a key are compared to each other.
+    /// `None` leaves the file out of the search. Defaults to the file size.
+    fn candidate_key(&self, _path: &Path, meta: &Metadata) -> Option<u64> {
+        Some(meta.len())
+    }
+
+    /// Verifies candidates, files producing the same key are duplicates.
+    /// Defaults to hashing the file contents with `hasher`.
+    ///
+    /// Going through `hasher.hash_file` lets file timeouts track progress while the file is read.
+    fn match_key(&self, path: &Path, hasher: &dyn FileHasher) -> Result<String, io::Error> {
+        hasher.hash_file(path)
+    }
+
+    /// Key of the first `bytes` of the file, compared by `DupeFinderBuilder::partial_hash` to rule out
+    /// candidates before producing their match key. Files with equal match keys need equal prefix keys.
+    /// Defaults to hashing the leading bytes of the file with `hasher`.
+    fn prefix_key(&self, path: &Path, bytes: u64, hasher: &dyn FileHasher) -> Result<String, io::Error> {
+        let file = File::open(path)?;
+        hasher.hash_reader(&mut file.take(bytes))
+    }
+
+    /// Confidence of the sets found by this strategy, strategies whose match key does not
+    /// cover the full contents should report less. Defaults to `Confidence::FullHash`.
+    fn confidence(&self) -> Confidence {
+        Confidence::FullHash
+    }
+
+    /// Tells the match keys of this strategy apart from those of others, `DupeFinderBuilder::hash_cache`
+    /// keeps the keys of each identity separate. Defaults to the type name, strategies whose keys
+    /// depend on their settings should include them.
+    fn identity(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+}
+
+/// Matches files with identical contents: same size, same content hash
+#[derive(Clone, Copy, Default)]
+pub struct ContentStrategy;
+
+impl MatchStrategy for ContentStrategy {}
+
+// Keys only the first `bytes` of a file through the prefix key of `strategy`, used to rule out
+// same-size files before hashing them in full
+pub struct PrefixStrategy {
+    pub bytes: u64,
+    pub strategy: Arc<dyn MatchStrategy>,
+}
+
+impl MatchStrategy for PrefixStrategy {
+    fn confidence(&self) -> Confidence {
+        Confidence::PrefixHash
+    }
+
+    fn match_key(&self, path: &Path, hasher: &dyn FileHasher) -> Result<String, io::Error> {
+        self.strategy.prefix_key(path, self.bytes, hasher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Xxh3Hasher;
+
+    #[test]
+    fn test_content_strategy() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let meta = std::fs::metadata(&path).unwrap();
+
+        assert_eq!(ContentStrategy.candidate_key(&path, &meta), Some(100));
+        assert_eq!(ContentStrategy.match_key(&path, &Xxh3Hasher).unwrap(), String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
+    }
+
+    #[test]
+    fn test_prefix_strategy() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let prefix = std::fs::read(&path).unwrap()[..10].to_vec();
+        let prefix_strategy = |bytes| PrefixStrategy { bytes, strategy: Arc::new(ContentStrategy) };
+
+        assert_eq!(prefix_strategy(10).match_key(&path, &Xxh3Hasher).unwrap(), Xxh3Hasher.hash_reader(&mut prefix.as_slice()).unwrap());
+        assert_eq!(prefix_strategy(1000).match_key(&path, &Xxh3Hasher).unwrap(), String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
+    }
+}
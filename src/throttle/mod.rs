@@ -0,0 +1,121 @@
+use std::{io::{self, Read}, sync::{Arc, Mutex, MutexGuard}, thread, time::{Duration, Instant}};
+use crate::FileHasher;
+
+// Token bucket shared by all hashing threads, refilled with `rate` bytes per second and holding at
+// most one second worth of them. Reads beyond the tokens left put the bucket into debt, the reader
+// waits until the debt is paid off.
+pub struct ReadThrottle {
+    rate: u64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl ReadThrottle {
+    pub fn new(rate: u64) -> ReadThrottle {
+        ReadThrottle { rate, bucket: Mutex::new(Bucket { tokens: rate as f64, refilled: Instant::now() }) }
+    }
+
+    // takes `bytes` just read from the bucket, sleeping while it is in debt
+    pub fn take(&self, bytes: usize) {
+        let rate = self.rate as f64;
+        let wait = {
+            let mut bucket = self.lock();
+            let now = Instant::now();
+            bucket.tokens = (bucket.tokens + now.duration_since(bucket.refilled).as_secs_f64() * rate).min(rate);
+            bucket.refilled = now;
+            bucket.tokens -= bytes as f64;
+
+            match bucket.tokens < 0.0 {
+                true => Duration::from_secs_f64(-bucket.tokens / rate),
+                false => Duration::ZERO,
+            }
+        };
+
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Bucket> {
+        self.bucket.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Configured hasher reading through a `ReadThrottle`, see `DupeFinderBuilder::max_read_rate`
+pub struct ThrottledHasher {
+    inner: Arc<dyn FileHasher>,
+    throttle: Arc<ReadThrottle>,
+}
+
+impl ThrottledHasher {
+    pub fn new(inner: Arc<dyn FileHasher>, throttle: Arc<ReadThrottle>) -> ThrottledHasher {
+        ThrottledHasher { inner, throttle }
+    }
+}
+
+// `hash_file` is left to the default so files are opened here and read through the throttle
+impl FileHasher for ThrottledHasher {
+    fn hash_reader(&self, reader: &mut dyn Read) -> Result<String, io::Error> {
+        self.inner.hash_reader(&mut Throttled::new(reader, Some(&self.throttle)))
+    }
+
+    // throttling does not change the digests
+    fn identity(&self) -> String {
+        self.inner.identity()
+    }
+}
+
+// reads `inner`, taking what was read from the throttle if there is one
+pub struct Throttled<'a> {
+    inner: &'a mut dyn Read,
+    throttle: Option<&'a ReadThrottle>,
+}
+
+impl<'a> Throttled<'a> {
+    pub fn new(inner: &'a mut dyn Read, throttle: Option<&'a ReadThrottle>) -> Throttled<'a> {
+        Throttled { inner, throttle }
+    }
+}
+
+impl Read for Throttled<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, io::Error> {
+        let read = self.inner.read(buffer)?;
+        if let Some(throttle) = self.throttle {
+            throttle.take(read);
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Xxh3Hasher;
+
+    #[test]
+    fn test_read_throttle() {
+        let throttle = ReadThrottle::new(1_000_000);
+        let start = Instant::now();
+        throttle.take(500_000);
+        assert!(start.elapsed() < Duration::from_millis(250), "a full bucket should not wait");
+
+        throttle.take(1_000_000);
+        assert!(start.elapsed() >= Duration::from_millis(450), "debt should be waited for");
+    }
+
+    #[test]
+    fn test_throttled_hasher() {
+        let contents = vec![7u8; 300_000];
+        let hasher = ThrottledHasher::new(Arc::new(Xxh3Hasher), Arc::new(ReadThrottle::new(200_000)));
+
+        let start = Instant::now();
+        let hash = hasher.hash_reader(&mut contents.as_slice()).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(450), "reads beyond the rate should wait");
+        assert_eq!(hash, Xxh3Hasher.hash_reader(&mut contents.as_slice()).unwrap(), "hash should not change");
+    }
+}
@@ -0,0 +1,176 @@
+use std::{fs::{DirEntry, File, Metadata}, io::{self, Read}, path::{Path, PathBuf}, sync::{mpsc, Arc}, thread, time::{Duration, Instant}};
+use crate::{hasher::{isolate_panics, FileHasher}, MatchStrategy};
+
+// Limits on how long a single stat / open / read may go without progress
+#[derive(Clone, Copy, Default)]
+pub struct Timeouts {
+    // a warning is logged once the operation has been waiting this long
+    pub soft: Option<Duration>,
+    // the file is given up on once the operation has been waiting this long
+    pub hard: Option<Duration>,
+}
+
+impl Timeouts {
+    pub fn enabled(&self) -> bool {
+        self.soft.is_some() || self.hard.is_some()
+    }
+
+    // the soft timeout only matters while it expires before the hard timeout
+    fn effective_soft(&self) -> Option<Duration> {
+        match (self.soft, self.hard) {
+            (Some(soft), Some(hard)) if soft >= hard => None,
+            (soft, _) => soft,
+        }
+    }
+}
+
+// reports progress each time a read returns
+struct ProgressReader<'a, R> {
+    inner: R,
+    heartbeat: &'a (dyn Fn() + Sync),
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        (self.heartbeat)();
+        Ok(read)
+    }
+}
+
+// opens files itself so every read that returns counts as progress
+struct ProgressHasher<'a> {
+    inner: &'a dyn FileHasher,
+    heartbeat: &'a (dyn Fn() + Sync),
+}
+
+impl FileHasher for ProgressHasher<'_> {
+    fn hash_reader(&self, reader: &mut dyn Read) -> Result<String, io::Error> {
+        self.inner.hash_reader(&mut ProgressReader { inner: reader, heartbeat: self.heartbeat })
+    }
+
+    fn hash_file(&self, path: &Path) -> Result<String, io::Error> {
+        let file = File::open(path)?;
+        (self.heartbeat)();
+        self.hash_reader(&mut ProgressReader { inner: file, heartbeat: self.heartbeat })
+    }
+}
+
+// reads the metadata of a directory entry under `timeouts`
+pub fn metadata(entry: DirEntry, timeouts: &Timeouts, on_slow: &dyn Fn(Duration)) -> Result<Metadata, io::Error> {
+    run(timeouts, on_slow, move |_| entry.metadata())
+}
+
+// produces the match key of `path` under `timeouts`, each read of the hasher that returns counts as progress
+pub fn match_key(strategy: Arc<dyn MatchStrategy>, hasher: Arc<dyn FileHasher>, path: PathBuf, timeouts: &Timeouts, on_slow: &dyn Fn(Duration)) -> Result<String, io::Error> {
+    run(timeouts, on_slow, move |heartbeat| {
+        isolate_panics(|| strategy.match_key(&path, &ProgressHasher { inner: hasher.as_ref(), heartbeat }))
+    })
+}
+
+// Runs `operation` on a helper thread while the calling thread waits on its progress.
+// A thread stuck in the kernel (dead NFS handle, failing disk) can not be cancelled,
+// on a hard timeout it is detached and left to finish or block on its own.
+fn run<T, F>(timeouts: &Timeouts, on_slow: &dyn Fn(Duration), operation: F) -> Result<T, io::Error>
+where
+    T: Send + 'static,
+    F: FnOnce(&(dyn Fn() + Sync)) -> Result<T, io::Error> + Send + 'static,
+{
+    // `None` reports progress, `Some` the outcome of the operation
+    let (sender, receiver) = mpsc::channel::<Option<Result<T, io::Error>>>();
+
+    thread::Builder::new().name(String::from("dupefinder-io")).spawn(move || {
+        let progress = sender.clone();
+        let heartbeat = move || { let _ = progress.send(None); };
+        let _ = sender.send(Some(operation(&heartbeat)));
+    })?;
+
+    let soft = timeouts.effective_soft();
+    let mut warned = false;
+    let mut last_progress = Instant::now();
+
+    loop {
+        let limit = match soft {
+            Some(soft) if !warned => Some(soft),
+            _ => timeouts.hard,
+        };
+
+        let message = match limit {
+            Some(limit) => receiver.recv_timeout((last_progress + limit).saturating_duration_since(Instant::now())),
+            None => receiver.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+
+        match message {
+            Ok(Some(result)) => return result,
+            Ok(None) => last_progress = Instant::now(),
+            Err(mpsc::RecvTimeoutError::Timeout) if soft.is_some() && !warned => {
+                warned = true;
+                on_slow(last_progress.elapsed());
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, format!("no progress for {:?}", last_progress.elapsed())));
+            },
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(io::Error::other("file operation thread exited without a result"));
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::cell::Cell;
+
+    fn hard(millis: u64) -> Timeouts {
+        Timeouts { soft: None, hard: Some(Duration::from_millis(millis)) }
+    }
+
+    #[test]
+    fn test_run_completes() {
+        let result = run(&hard(1000), &|_| {}, |_| Ok(5));
+        assert_eq!(result.unwrap(), 5);
+    }
+
+    #[test]
+    fn test_run_hard_timeout() {
+        let result: Result<(), io::Error> = run(&hard(20), &|_| {}, |_| {
+            thread::sleep(Duration::from_millis(500));
+            Ok(())
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_run_progress_extends_deadline() {
+        let result = run(&hard(100), &|_| {}, |heartbeat| {
+            for _ in 0..10 {
+                thread::sleep(Duration::from_millis(25));
+                heartbeat();
+            }
+            Ok(())
+        });
+        assert!(result.is_ok(), "steady progress should not time out");
+    }
+
+    #[test]
+    fn test_run_soft_timeout_warns_once() {
+        let warnings = Cell::new(0);
+        let timeouts = Timeouts { soft: Some(Duration::from_millis(10)), hard: None };
+        let result = run(&timeouts, &|_| warnings.set(warnings.get() + 1), |_| {
+            thread::sleep(Duration::from_millis(100));
+            Ok(())
+        });
+
+        assert!(result.is_ok(), "soft timeout should keep waiting");
+        assert_eq!(warnings.get(), 1);
+    }
+
+    #[test]
+    fn test_match_key() {
+        let path: std::path::PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes", "a.txt"].iter().collect();
+        let hash = match_key(Arc::new(crate::ContentStrategy), Arc::new(crate::Xxh3Hasher), path, &hard(1000), &|_| {});
+        assert_eq!(hash.unwrap(), String::from("1577245F909F3D4619DDA56A7B4BA1AF"));
+    }
+}
@@ -0,0 +1,86 @@
+use std::{collections::{BTreeMap, HashSet}, path::{Path, PathBuf}};
+use crate::{DupeFinder, Duplicate};
+
+/// Bytes stored below a directory, split into files with and without a copy
+///
+/// Totals include all subdirectories, so nodes nest like the directory tree they describe.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreemapNode {
+    /// Directory the totals belong to
+    pub path: PathBuf,
+    /// Number of files below the directory
+    pub files: usize,
+    /// Bytes of files without a copy anywhere in the scan
+    pub unique_bytes: u64,
+    /// Bytes of files that are part of a duplicate set, every copy included
+    pub duplicate_bytes: u64,
+}
+
+impl DupeFinder {
+    /// Runs the search and returns the unique and duplicated bytes of every directory searched,
+    /// sorted by path, for disk usage views such as treemaps
+    ///
+    /// # Examples
+    /// ```
+    /// let mut checker = dupefinder::DupeFinder::new_recursive(vec!["./resources"]);
+    /// for node in checker.run_treemap() {
+    ///     println!("{}: {} of {} bytes duplicated", node.path.display(), node.duplicate_bytes, node.duplicate_bytes + node.unique_bytes);
+    /// }
+    /// ```
+    pub fn run_treemap(&mut self) -> Vec<TreemapNode> {
+        let results = self.run();
+        let duplicated: HashSet<&Path> = results.values()
+            .flat_map(Duplicate::paths)
+            .collect();
+
+        let mut nodes: BTreeMap<PathBuf, TreemapNode> = BTreeMap::new();
+        for data in self.file_sizes.values().flatten() {
+            let parent = match data.path.parent() {
+                Some(parent) => parent,
+                None => continue,
+            };
+
+            // totals are added up to the provided directory the file was found under
+            let root = self.directories.iter()
+                .filter(|directory| data.path.starts_with(directory))
+                .max_by_key(|directory| directory.components().count())
+                .map_or(parent, PathBuf::as_path);
+
+            let duplicate = duplicated.contains(data.path.as_path());
+            for directory in parent.ancestors() {
+                let node = nodes.entry(directory.to_path_buf()).or_insert_with(|| TreemapNode { path: directory.to_path_buf(), ..TreemapNode::default() });
+                node.files += 1;
+                if duplicate {
+                    node.duplicate_bytes += data.size;
+                } else {
+                    node.unique_bytes += data.size;
+                }
+
+                if directory == root {
+                    break;
+                }
+            }
+        }
+
+        nodes.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_run_treemap() {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "dupes_directories"].iter().collect();
+        let other: PathBuf = [env!("CARGO_MANIFEST_DIR"), "resources", "folders"].iter().collect();
+        let mut checker = DupeFinder::new_recursive([&path, &other]);
+
+        let nodes = checker.run_treemap();
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(nodes[0], TreemapNode { path: path.clone(), files: 2, unique_bytes: 0, duplicate_bytes: 200 });
+        assert_eq!(nodes[1], TreemapNode { path: path.join("dir_a"), files: 1, unique_bytes: 0, duplicate_bytes: 100 });
+        assert_eq!(nodes[3], TreemapNode { path: other, files: 1, unique_bytes: 44, duplicate_bytes: 0 });
+    }
+}
@@ -0,0 +1,80 @@
+use std::{fs::File, io::{self, Read}, path::Path};
+use crate::throttle::{ReadThrottle, Throttled};
+
+// amount of data compared per step
+const COMPARE_BUFFER_SIZE: usize = 65536;
+
+// streams both files and compares them byte by byte, reading through `throttle` if there is one
+pub fn same_contents(a: &Path, b: &Path, throttle: Option<&ReadThrottle>) -> Result<bool, io::Error> {
+    let (mut file_a, mut file_b) = (File::open(a)?, File::open(b)?);
+    let mut file_a = Throttled::new(&mut file_a, throttle);
+    let mut file_b = Throttled::new(&mut file_b, throttle);
+    let mut buffer_a = vec![0u8; COMPARE_BUFFER_SIZE];
+    let mut buffer_b = vec![0u8; COMPARE_BUFFER_SIZE];
+
+    loop {
+        let read = fill(&mut file_a, &mut buffer_a)?;
+        if read != fill(&mut file_b, &mut buffer_b)? {
+            return Ok(false);
+        }
+
+        if read == 0 {
+            return Ok(true);
+        }
+
+        if buffer_a[..read] != buffer_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+// reads until `buffer` is full or the end of the file is reached
+fn fill(reader: &mut dyn Read, buffer: &mut [u8]) -> Result<usize, io::Error> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_same_contents() {
+        let temp = tempfile::tempdir().unwrap();
+        let large: Vec<u8> = (0..COMPARE_BUFFER_SIZE * 2 + 10).map(|i| (i % 251) as u8).collect();
+        let mut changed = large.clone();
+        changed[COMPARE_BUFFER_SIZE + 5] ^= 1;
+
+        std::fs::write(temp.path().join("a"), &large).unwrap();
+        std::fs::write(temp.path().join("b"), &large).unwrap();
+        std::fs::write(temp.path().join("c"), &changed).unwrap();
+        std::fs::write(temp.path().join("d"), &large[..large.len() - 1]).unwrap();
+
+        assert!(same_contents(&temp.path().join("a"), &temp.path().join("b"), None).unwrap());
+        assert!(!same_contents(&temp.path().join("a"), &temp.path().join("c"), None).unwrap());
+        assert!(!same_contents(&temp.path().join("a"), &temp.path().join("d"), None).unwrap());
+        assert!(same_contents(&temp.path().join("a"), &temp.path().join("missing"), None).is_err(), "io error expected");
+    }
+
+    #[test]
+    fn test_same_contents_throttled() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join("a"), vec![7u8; 150_000]).unwrap();
+        std::fs::write(temp.path().join("b"), vec![7u8; 150_000]).unwrap();
+
+        let throttle = ReadThrottle::new(200_000);
+        let start = std::time::Instant::now();
+        assert!(same_contents(&temp.path().join("a"), &temp.path().join("b"), Some(&throttle)).unwrap());
+        assert!(start.elapsed() >= std::time::Duration::from_millis(450), "reads beyond the rate should wait");
+    }
+}
@@ -0,0 +1,457 @@
+use std::{collections::{BTreeMap, HashMap, HashSet, VecDeque}, fs, ops::Bound, path::{Path, PathBuf}, sync::mpsc, time::{Duration, Instant}};
+use log::Level;
+use notify::{event::ModifyKind, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use crate::{dirdata::DirData, DupeEntry, Duplicate, DupeFinder, LogCategory, ScanResults, WalkDecision};
+
+/// A change to the duplicate sets while watching, see `DupeFinder::watch`
+#[derive(Clone, Debug)]
+pub enum WatchEvent {
+    /// `path` was created or modified and now shares its contents with the other files of `duplicate`
+    Duplicate { path: PathBuf, duplicate: Duplicate },
+    /// `path` was modified or deleted and left the set `hash`, `remaining` are the files still
+    /// sharing those contents, the set is gone once fewer than two are left
+    Removed { path: PathBuf, hash: String, remaining: Vec<PathBuf> },
+}
+
+/// Keeps the index of a `DupeFinder` up to date as files change, created by `DupeFinder::watch`
+///
+/// Iterating blocks until the next `WatchEvent`, dropping the watcher stops watching.
+pub struct DupeWatcher {
+    finder: DupeFinder,
+    // notifications stop once this is dropped
+    _watcher: RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<Event>>,
+    pending: VecDeque<WatchEvent>,
+    // canonical form of each searched directory along with the directory as it was given,
+    // events report paths below the former while the index holds paths below the latter
+    roots: Vec<(PathBuf, PathBuf)>,
+    // candidate key of every indexed file, sorted so the files below a directory are found by range
+    keys: BTreeMap<PathBuf, u64>,
+    // hash of every file that is part of a set, sorted like `keys`
+    members: BTreeMap<PathBuf, String>,
+    // hash -> files of each set, reference files included
+    sets: HashMap<String, Vec<PathBuf>>,
+}
+
+impl DupeFinder {
+    /// Runs the search, then keeps watching the directories for created, modified and deleted files
+    ///
+    /// Returns the results of the initial run along with a `DupeWatcher` reporting every later change
+    /// to the duplicate sets. Changed files are checked against the same filters as a run and only
+    /// files sharing their candidate key are hashed, unchanged files are never hashed again.
+    /// Directories created, moved or removed update every file below them.
+    /// Hard links are only collapsed in the initial run.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use dupefinder::WatchEvent;
+    ///
+    /// let checker = dupefinder::DupeFinder::new_recursive(vec!["./downloads"]);
+    /// let (results, watcher) = checker.watch().unwrap();
+    /// println!("{} duplicate sets", results.len());
+    ///
+    /// for event in watcher {
+    ///     if let WatchEvent::Duplicate { path, duplicate } = event {
+    ///         println!("{} duplicates {:?}", path.display(), duplicate.paths().collect::<Vec<_>>());
+    ///     }
+    /// }
+    /// ```
+    pub fn watch(mut self) -> Result<(ScanResults, DupeWatcher), notify::Error> {
+        self.options.incremental = true;
+
+        // watching starts first so changes made during the run are not missed
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        let mode = if self.options.follow_subdirs { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        let roots: Vec<(PathBuf, PathBuf)> = self.directories.iter()
+            .map(|directory| (directory.canonicalize().unwrap_or_else(|_| directory.clone()), directory.clone()))
+            .collect();
+        for (canonical, _) in roots.iter() {
+            watcher.watch(canonical, mode)?;
+        }
+
+        let results = self.run();
+
+        let keys = self.file_sizes.iter()
+            .flat_map(|(key, group)| group.iter().map(move |data| (data.path.clone(), *key)))
+            .collect();
+        let mut sets: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for (hash, duplicate) in results.iter() {
+            let files = sets.entry(hash.clone()).or_default();
+            files.extend(duplicate.paths().map(Path::to_path_buf));
+            files.extend(results.references(hash).iter().cloned());
+        }
+        let members = sets.iter()
+            .flat_map(|(hash, files)| files.iter().map(move |file| (file.clone(), hash.clone())))
+            .collect();
+
+        Ok((results, DupeWatcher { finder: self, _watcher: watcher, events, pending: VecDeque::new(), roots, keys, members, sets }))
+    }
+}
+
+impl DupeWatcher {
+    /// Waits up to `timeout` for the next change, `None` if there was none or watching stopped
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<WatchEvent> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            let event = self.events.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok()?;
+            self.handle(event);
+        }
+    }
+
+    /// Stops watching and returns the `DupeFinder`
+    pub fn into_inner(self) -> DupeFinder {
+        self.finder
+    }
+
+    fn handle(&mut self, event: notify::Result<Event>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                self.finder.log(LogCategory::Traversal, Level::Warn, format_args!("Error watching directories: {}", e));
+                return;
+            }
+        };
+
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+
+        // only directories that appeared are walked, not those whose contents or metadata changed
+        let appeared = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)) | EventKind::Any | EventKind::Other);
+        for path in event.paths {
+            let path = self.relative_to_root(path);
+            self.update(path, appeared);
+        }
+    }
+
+    // `path` as reported below a canonical root, moved below the directory as it was given so it
+    // matches the paths of the run, the deepest root wins when searched directories are nested
+    fn relative_to_root(&self, path: PathBuf) -> PathBuf {
+        let root = self.roots.iter()
+            .filter(|(canonical, _)| path.starts_with(canonical))
+            .max_by_key(|(canonical, _)| canonical.components().count());
+
+        match root {
+            Some((canonical, directory)) if path != *canonical => directory.join(path.strip_prefix(canonical).unwrap_or(&path)),
+            Some((_, directory)) => directory.clone(),
+            None => path,
+        }
+    }
+
+    // updates `path` along with the indexed files below it, a directory that `appeared` is walked
+    // and the files in it indexed
+    fn update(&mut self, path: PathBuf, appeared: bool) {
+        // files below a directory that was removed or moved away went with it
+        for file in self.indexed_below(&path) {
+            self.update_file(file, &mut Vec::new());
+        }
+
+        let mut subdirs: Vec<PathBuf> = Vec::new();
+        self.update_file(path, &mut subdirs);
+        if !appeared {
+            return;
+        }
+
+        // canonical paths of the walked directories, followed symlinks may loop back
+        let mut visited: HashSet<PathBuf> = HashSet::new();
+        while let Some(directory) = subdirs.pop() {
+            if !visited.insert(directory.canonicalize().unwrap_or_else(|_| directory.clone())) {
+                continue;
+            }
+
+            let entries = match fs::read_dir(&directory) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    self.finder.log(LogCategory::Traversal, Level::Warn, format_args!("Unable to read directory: {}; error: {}", directory.display(), e));
+                    continue;
+                }
+            };
+            for entry in entries.flatten() {
+                self.update_file(entry.path(), &mut subdirs);
+            }
+        }
+    }
+
+    // indexed files below the directory `path`, paths sort by component so they directly follow it
+    fn indexed_below(&self, path: &Path) -> Vec<PathBuf> {
+        let range = (Bound::Excluded(path), Bound::Unbounded);
+        let keys = self.keys.range::<Path, _>(range).map(|(file, _)| file).take_while(|file| file.starts_with(path));
+        let members = self.members.range::<Path, _>(range).map(|(file, _)| file).take_while(|file| file.starts_with(path));
+
+        let mut below: Vec<PathBuf> = keys.chain(members).cloned().collect();
+        below.sort();
+        below.dedup();
+        below
+    }
+
+    // re-stats `path` and moves it to the set matching its current contents, a directory
+    // is added to `subdirs` when subdirectories are followed
+    fn update_file(&mut self, path: PathBuf, subdirs: &mut Vec<PathBuf>) {
+        let left = self.forget(&path);
+        let joined = self.index(&path, subdirs);
+
+        match (left, joined) {
+            (Some((hash, _)), Some(duplicate)) if hash == duplicate.hash => {},
+            (left, joined) => {
+                if let Some((hash, remaining)) = left {
+                    self.pending.push_back(WatchEvent::Removed { path: path.clone(), hash, remaining });
+                }
+
+                let duplicate = joined.and_then(|duplicate| self.finder.split_references(duplicate));
+                if let Some((duplicate, _)) = duplicate {
+                    self.pending.push_back(WatchEvent::Duplicate { path, duplicate });
+                }
+            },
+        }
+    }
+
+    // drops `path` from the index, returns the set it left with the files remaining in it
+    fn forget(&mut self, path: &Path) -> Option<(String, Vec<PathBuf>)> {
+        if let Some(key) = self.keys.remove(path) {
+            if let Some(group) = self.finder.file_sizes.get_mut(&key) {
+                group.retain(|data| data.path != path);
+            }
+        }
+
+        let hash = self.members.remove(path)?;
+        let remaining = self.leave(path, &hash);
+        Some((hash, remaining))
+    }
+
+    // takes `path` out of the set `hash`, a set left with a single file is dropped
+    fn leave(&mut self, path: &Path, hash: &str) -> Vec<PathBuf> {
+        let remaining = match self.sets.get_mut(hash) {
+            Some(files) => {
+                files.retain(|file| file != path);
+                files.clone()
+            },
+            None => Vec::new(),
+        };
+
+        if remaining.len() < 2 {
+            self.sets.remove(hash);
+            for file in remaining.iter() {
+                self.members.remove(file);
+            }
+        }
+
+        remaining
+    }
+
+    // indexes `path` if it still exists, returns the set it is now part of
+    fn index(&mut self, path: &Path, subdirs: &mut Vec<PathBuf>) -> Option<Duplicate> {
+        let meta = fs::symlink_metadata(path).ok()?;
+        let data = self.finder.resolve_symlink(DirData { size: meta.len(), path: path.to_path_buf(), meta })?;
+        if !self.is_searched(path) || !self.finder.should_insert_size(&data, subdirs) {
+            return None;
+        }
+        if self.finder.options.filter.walk.as_ref().is_some_and(|filter| filter(&data.path, &data.meta) != WalkDecision::Accept) {
+            return None;
+        }
+
+        let key = self.finder.candidate_key(&data)?;
+        if self.finder.options.shard.is_some_and(|shard| !shard.owns(key)) {
+            return None;
+        }
+
+        let size = data.size;
+        self.keys.insert(path.to_path_buf(), key);
+        let group = self.finder.file_sizes.entry(key).or_default();
+        group.push(data);
+        if group.len() < 2 {
+            return None;
+        }
+
+        let group: Vec<&DirData> = self.finder.file_sizes[&key].iter().collect();
+        let hashes = self.finder.hash_candidates(&group);
+        let hash = match group.iter().zip(hashes.iter()).find(|(data, _)| data.path == path) {
+            Some((_, Ok(hash))) => hash.clone(),
+            Some((_, Err(e))) => {
+                self.finder.log(LogCategory::Hashing, Level::Warn, format_args!("Error generating file hash for file: {}; error: {}", path.display(), e));
+                return None;
+            },
+            None => return None,
+        };
+
+        let entries: Vec<DupeEntry> = group.iter().zip(hashes.iter())
+            .filter(|(_, file_hash)| file_hash.as_ref().is_ok_and(|file_hash| *file_hash == hash))
+            .map(|(data, _)| DupeEntry::new(data.path.clone(), &data.meta))
+            .collect();
+        let mut duplicate = Duplicate { hash, entries, size, confidence: self.finder.options.strategy.confidence() };
+        if duplicate.entries.len() < 2 || (self.finder.options.verify_contents && !self.finder.verify_duplicate(&mut duplicate)) {
+            return None;
+        }
+
+        // files already in a set move over to the new one
+        for file in duplicate.paths() {
+            if let Some(previous) = self.members.insert(file.to_path_buf(), duplicate.hash.clone()) {
+                if previous != duplicate.hash {
+                    self.leave(file, &previous);
+                }
+            }
+        }
+        self.sets.insert(duplicate.hash.clone(), duplicate.paths().map(Path::to_path_buf).collect());
+
+        Some(duplicate)
+    }
+
+    // whether `path` lies within the searched directories, below no excluded or hidden
+    // directory and within the recursion depth
+    fn is_searched(&self, path: &Path) -> bool {
+        let options = &self.finder.options;
+        let root = match self.finder.directories.iter().find(|directory| path.starts_with(directory)) {
+            Some(root) => root,
+            None => return false,
+        };
+
+        let mut depth: usize = 0;
+        let mut current = root.to_path_buf();
+        for component in path.strip_prefix(root).unwrap_or(path).components() {
+            current.push(component);
+            if options.filter.excludes(&current) || options.filter.hides_path(&current) || options.limits.path_too_long(&current) {
+                return false;
+            }
+
+            #[cfg(feature = "gitignore")]
+            if options.respect_gitignore && self.finder.ignore_rules.ignores(&current, current.is_dir()) {
+                return false;
+            }
+            depth += 1;
+        }
+
+        match options.max_depth {
+            Some(max) => depth <= max + 1,
+            None => true,
+        }
+    }
+}
+
+impl Iterator for DupeWatcher {
+    type Item = WatchEvent;
+
+    fn next(&mut self) -> Option<WatchEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            let event = self.events.recv().ok()?;
+            self.handle(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // the next event, skipping those of files outside `path`
+    fn next_for(watcher: &mut DupeWatcher, path: &Path) -> Option<WatchEvent> {
+        while let Some(event) = watcher.next_timeout(Duration::from_secs(5)) {
+            let event_path = match &event {
+                WatchEvent::Duplicate { path, .. } | WatchEvent::Removed { path, .. } => path,
+            };
+            if event_path == path {
+                return Some(event);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_watch() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().canonicalize().unwrap();
+        fs::write(directory.join("a.txt"), "12345").unwrap();
+
+        let (results, mut watcher) = DupeFinder::new(vec![&directory]).watch().unwrap();
+        assert!(results.is_empty(), "no duplicate expected");
+
+        fs::write(directory.join("b.txt"), "12345").unwrap();
+        match next_for(&mut watcher, &directory.join("b.txt")) {
+            Some(WatchEvent::Duplicate { duplicate, .. }) => assert_eq!(duplicate.entries.len(), 2),
+            _ => panic!("created copy should be reported"),
+        }
+
+        fs::remove_file(directory.join("a.txt")).unwrap();
+        match next_for(&mut watcher, &directory.join("a.txt")) {
+            Some(WatchEvent::Removed { remaining, .. }) => assert_eq!(remaining, vec![directory.join("b.txt")]),
+            _ => panic!("deleted copy should be reported"),
+        }
+    }
+
+    #[test]
+    fn test_watch_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().canonicalize().unwrap().join("watched");
+        let outside = temp.path().canonicalize().unwrap().join("outside");
+        fs::create_dir_all(directory.join("sub")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::write(directory.join("a.txt"), "12345").unwrap();
+        fs::write(directory.join("sub").join("b.txt"), "12345").unwrap();
+        fs::write(outside.join("c.txt"), "12345").unwrap();
+
+        let (results, mut watcher) = DupeFinder::new_recursive(vec![&directory]).watch().unwrap();
+        assert_eq!(results.len(), 1, "one set expected");
+
+        fs::rename(directory.join("sub"), directory.join("moved")).unwrap();
+        match next_for(&mut watcher, &directory.join("sub").join("b.txt")) {
+            Some(WatchEvent::Removed { remaining, .. }) => assert_eq!(remaining, vec![directory.join("a.txt")]),
+            _ => panic!("files of a renamed directory should leave their set"),
+        }
+        match next_for(&mut watcher, &directory.join("moved").join("b.txt")) {
+            Some(WatchEvent::Duplicate { duplicate, .. }) => assert_eq!(duplicate.entries.len(), 2),
+            _ => panic!("files of a renamed directory should be indexed under the new name"),
+        }
+
+        fs::rename(&outside, directory.join("outside")).unwrap();
+        match next_for(&mut watcher, &directory.join("outside").join("c.txt")) {
+            Some(WatchEvent::Duplicate { duplicate, .. }) => assert_eq!(duplicate.entries.len(), 3),
+            _ => panic!("files of a directory moved in should be indexed"),
+        }
+
+        fs::remove_dir_all(directory.join("moved")).unwrap();
+        match next_for(&mut watcher, &directory.join("moved").join("b.txt")) {
+            Some(WatchEvent::Removed { remaining, .. }) => assert_eq!(remaining.len(), 2),
+            _ => panic!("files of a removed directory should leave their set"),
+        }
+        fs::remove_dir_all(directory.join("outside")).unwrap();
+        match next_for(&mut watcher, &directory.join("outside").join("c.txt")) {
+            Some(WatchEvent::Removed { remaining, .. }) => assert_eq!(remaining, vec![directory.join("a.txt")]),
+            _ => panic!("files of a removed directory should leave their set"),
+        }
+        assert!(watcher.keys.keys().all(|file| file == &directory.join("a.txt")), "no stale files expected");
+        assert!(watcher.members.is_empty() && watcher.sets.is_empty(), "no stale sets expected");
+    }
+
+    #[test]
+    fn test_watch_relative_root() {
+        // notify reports absolute paths, the files of a relative root must still be found
+        let temp = tempfile::tempdir_in(".").unwrap();
+        let directory = Path::new(".").join(temp.path().file_name().unwrap());
+        fs::create_dir(directory.join("sub")).unwrap();
+        fs::write(directory.join("a.txt"), "12345").unwrap();
+
+        let (results, mut watcher) = DupeFinder::new_recursive(vec![&directory]).watch().unwrap();
+        assert!(results.is_empty(), "no duplicate expected");
+
+        fs::write(directory.join("sub").join("b.txt"), "12345").unwrap();
+        match next_for(&mut watcher, &directory.join("sub").join("b.txt")) {
+            Some(WatchEvent::Duplicate { duplicate, .. }) => assert_eq!(duplicate.entries.len(), 2),
+            _ => panic!("created copy below a relative root should be reported"),
+        }
+
+        fs::remove_dir_all(directory.join("sub")).unwrap();
+        match next_for(&mut watcher, &directory.join("sub").join("b.txt")) {
+            Some(WatchEvent::Removed { remaining, .. }) => assert_eq!(remaining, vec![directory.join("a.txt")]),
+            _ => panic!("files of a removed directory below a relative root should leave their set"),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! End-to-end scenarios run against a generated directory tree
+
+use std::{fs, path::{Path, PathBuf}};
+use dupefinder::{DupeFinder, Duplicate};
+
+// Layout of the generated tree:
+//
+// photos/                     library/
+//   a.jpg       (dup 1)         2020/b.jpg       (dup 1)
+//   same.jpg    (same size      2020/deep/c.jpg  (dup 1)
+//                as a.jpg)      notes.txt        (dup 2)
+//   notes.txt   (dup 2)         empty.txt        (empty)
+//   .hidden.jpg (dup 1)         unique.bin
+struct Fixture {
+    _temp: tempfile::TempDir,
+    photos: PathBuf,
+    library: PathBuf,
+}
+
+fn fixture() -> Fixture {
+    let temp = tempfile::tempdir().unwrap();
+    let photos = temp.path().join("photos");
+    let library = temp.path().join("library");
+    fs::create_dir_all(&photos).unwrap();
+    fs::create_dir_all(library.join("2020").join("deep")).unwrap();
+
+    let image = pattern(4096, 1);
+    write(&photos.join("a.jpg"), &image);
+    write(&photos.join("same.jpg"), &pattern(4096, 2));
+    write(&photos.join(".hidden.jpg"), &image);
+    write(&photos.join("notes.txt"), b"shopping list");
+    write(&library.join("2020").join("b.jpg"), &image);
+    write(&library.join("2020").join("deep").join("c.jpg"), &image);
+    write(&library.join("notes.txt"), b"shopping list");
+    write(&library.join("empty.txt"), b"");
+    write(&library.join("unique.bin"), &pattern(1000, 3));
+
+    Fixture { _temp: temp, photos, library }
+}
+
+fn pattern(len: usize, seed: usize) -> Vec<u8> {
+    (0..len).map(|i| ((i * 31 + seed * 7) % 251) as u8).collect()
+}
+
+fn write(path: &Path, contents: &[u8]) {
+    fs::write(path, contents).unwrap();
+}
+
+fn sorted_files(duplicate: &Duplicate) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = duplicate.paths().map(PathBuf::from).collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn recursive_scan_across_roots() {
+    let fixture = fixture();
+    let mut checker = DupeFinder::new_recursive([&fixture.photos, &fixture.library]);
+    let results = checker.run();
+
+    assert_eq!(results.len(), 2);
+    let image = results.is_duplicate(fixture.photos.join("a.jpg")).unwrap();
+    assert_eq!(image.size, 4096);
+    assert_eq!(image.entries.len(), 4);
+    assert!(results.is_duplicate(fixture.photos.join("same.jpg")).is_none(), "same size is not enough");
+    assert!(results.is_duplicate(fixture.library.join("empty.txt")).is_none(), "empty files are skipped");
+
+    let notes = results.is_duplicate(fixture.library.join("notes.txt")).unwrap();
+    assert_eq!(sorted_files(notes), vec![fixture.library.join("notes.txt"), fixture.photos.join("notes.txt")]);
+}
+
+#[test]
+fn streaming_matches_full_scan() {
+    let fixture = fixture();
+    let mut checker = DupeFinder::new_recursive([&fixture.photos, &fixture.library]);
+    let expected = checker.run();
+
+    let streamed: Vec<Duplicate> = checker.run_iter().collect();
+    assert_eq!(streamed.len(), expected.len());
+    for duplicate in streamed.iter() {
+        assert_eq!(sorted_files(duplicate), sorted_files(&expected[&duplicate.hash]));
+    }
+}
+
+#[test]
+fn find_file_service() {
+    let fixture = fixture();
+    let mut checker = DupeFinder::new_recursive([&fixture.library]);
+
+    let found = checker.run_for_file(fixture.photos.join("a.jpg")).unwrap().unwrap();
+    assert_eq!(found.entries.len(), 3);
+    assert!(checker.run_for_file(fixture.photos.join("same.jpg")).unwrap().is_none(), "no copy expected");
+    assert!(checker.run_for_file(fixture.photos.join("missing.jpg")).is_err(), "io error expected");
+}
+
+#[test]
+fn filtered_cleanup_scan() {
+    let fixture = fixture();
+    let mut checker = DupeFinder::builder([&fixture.photos, &fixture.library])
+        .recursive(true)
+        .skip_hidden(true)
+        .include_extensions(["jpg"])
+        .exclude_globs(["**/deep"])
+        .build()
+        .unwrap();
+
+    let (results, unique) = checker.run_partitioned();
+    assert_eq!(results.len(), 1);
+    let image = results.values().next().unwrap();
+    assert_eq!(sorted_files(image), vec![fixture.library.join("2020").join("b.jpg"), fixture.photos.join("a.jpg")]);
+    assert_eq!(unique, vec![fixture.photos.join("same.jpg")]);
+}
+
+#[test]
+fn recursive_scan_json_export() {
+    let fixture = fixture();
+    let mut checker = DupeFinder::new_recursive([&fixture.photos, &fixture.library]);
+    let results = checker.run();
+
+    let mut report: Vec<u8> = Vec::new();
+    dupefinder::report::json::write(&results, &mut report).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&report).unwrap();
+
+    assert_eq!(json["summary"]["groups"], 2);
+    assert_eq!(json["summary"]["reclaimable_bytes"], results.stats().reclaimable_bytes);
+    assert_eq!(json["groups"][0]["size"], 4096);
+    assert_eq!(json["groups"][0]["files"].as_array().unwrap().len(), 4);
+}